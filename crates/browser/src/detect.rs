@@ -48,13 +48,121 @@ const WINDOWS_PATHS: &[&str] = &[
     r"C:\Program Files\BraveSoftware\Brave-Browser\Application\brave.exe",
 ];
 
+/// How a packaged browser entry is launched, mirroring cosmic-files'
+/// web-apps `Browser` type (which also keys entries by packaging format so
+/// new formats are just a new variant + table row).
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageKind {
+    Flatpak,
+    Snap,
+}
+
+/// A Chromium-based browser installed through Flatpak or Snap rather than a
+/// plain PATH executable or distro package.
+#[cfg(target_os = "linux")]
+struct PackagedBrowser {
+    kind: PackageKind,
+    /// Human-readable name, used in install instructions.
+    name: &'static str,
+    /// Flatpak application ID (`flatpak run <exec>`) or the Snap binary
+    /// path, depending on `kind`.
+    exec: &'static str,
+    /// Path whose existence confirms the Snap is actually installed.
+    /// Unused for Flatpak, which is probed via `flatpak info` instead since
+    /// Flatpak apps don't live at a single well-known filesystem path.
+    test_path: &'static str,
+}
+
+/// Known Flatpak/Snap packages for Chromium-based browsers, checked in
+/// order when no PATH executable or distro-installed binary was found.
+#[cfg(target_os = "linux")]
+const PACKAGED_BROWSERS: &[PackagedBrowser] = &[
+    PackagedBrowser {
+        kind: PackageKind::Flatpak,
+        name: "Chromium",
+        exec: "org.chromium.Chromium",
+        test_path: "",
+    },
+    PackagedBrowser {
+        kind: PackageKind::Flatpak,
+        name: "Brave",
+        exec: "com.brave.Browser",
+        test_path: "",
+    },
+    PackagedBrowser {
+        kind: PackageKind::Flatpak,
+        name: "Microsoft Edge",
+        exec: "com.microsoft.Edge",
+        test_path: "",
+    },
+    PackagedBrowser {
+        kind: PackageKind::Snap,
+        name: "Chromium",
+        exec: "/snap/bin/chromium",
+        test_path: "/snap/bin/chromium",
+    },
+    PackagedBrowser {
+        kind: PackageKind::Snap,
+        name: "Brave",
+        exec: "/snap/bin/brave",
+        test_path: "/snap/bin/brave",
+    },
+];
+
+/// Whether a Flatpak app ID is actually installed, via `flatpak info`
+/// rather than guessing at its install path (Flatpak apps can live under
+/// either the system or user installation directory).
+#[cfg(target_os = "linux")]
+fn flatpak_installed(app_id: &str) -> bool {
+    std::process::Command::new("flatpak")
+        .args(["info", app_id])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Find the first installed Flatpak/Snap Chromium-based browser, returning
+/// the executable to invoke (`flatpak` for Flatpak apps, the Snap binary
+/// directly) and the arguments needed to launch it.
+#[cfg(target_os = "linux")]
+fn detect_packaged_browser() -> Option<(PathBuf, Vec<String>)> {
+    for browser in PACKAGED_BROWSERS {
+        match browser.kind {
+            PackageKind::Flatpak => {
+                if flatpak_installed(browser.exec) {
+                    tracing::debug!(browser = browser.name, id = browser.exec, "found Flatpak browser");
+                    return Some((
+                        PathBuf::from("flatpak"),
+                        vec!["run".to_string(), browser.exec.to_string()],
+                    ));
+                }
+            },
+            PackageKind::Snap => {
+                if PathBuf::from(browser.test_path).exists() {
+                    tracing::debug!(browser = browser.name, path = browser.exec, "found Snap browser");
+                    return Some((PathBuf::from(browser.exec), Vec::new()));
+                }
+            },
+        }
+    }
+    None
+}
+
 /// Result of browser detection.
 #[derive(Debug, Clone)]
 pub struct DetectionResult {
     /// Whether a browser was found.
     pub found: bool,
-    /// Path to the browser executable (if found).
+    /// Path to the browser executable (if found). For Flatpak installs,
+    /// this is the `flatpak` binary itself — see `args` for the rest of
+    /// the invocation.
     pub path: Option<PathBuf>,
+    /// Extra leading arguments required to launch `path` (e.g. `["run",
+    /// "org.chromium.Chromium"]` for a Flatpak install). Empty for a
+    /// browser invoked directly.
+    pub args: Vec<String>,
     /// Platform-specific install instructions.
     pub install_hint: String,
 }
@@ -74,6 +182,7 @@ pub fn detect_browser(custom_path: Option<&str>) -> DetectionResult {
             return DetectionResult {
                 found: true,
                 path: Some(p),
+                args: Vec::new(),
                 install_hint: String::new(),
             };
         }
@@ -86,6 +195,7 @@ pub fn detect_browser(custom_path: Option<&str>) -> DetectionResult {
             return DetectionResult {
                 found: true,
                 path: Some(p),
+                args: Vec::new(),
                 install_hint: String::new(),
             };
         }
@@ -97,6 +207,7 @@ pub fn detect_browser(custom_path: Option<&str>) -> DetectionResult {
             return DetectionResult {
                 found: true,
                 path: Some(path),
+                args: Vec::new(),
                 install_hint: String::new(),
             };
         }
@@ -110,6 +221,7 @@ pub fn detect_browser(custom_path: Option<&str>) -> DetectionResult {
             return DetectionResult {
                 found: true,
                 path: Some(p),
+                args: Vec::new(),
                 install_hint: String::new(),
             };
         }
@@ -122,19 +234,69 @@ pub fn detect_browser(custom_path: Option<&str>) -> DetectionResult {
             return DetectionResult {
                 found: true,
                 path: Some(p),
+                args: Vec::new(),
                 install_hint: String::new(),
             };
         }
     }
 
+    // Check Flatpak/Snap installs
+    #[cfg(target_os = "linux")]
+    if let Some((path, args)) = detect_packaged_browser() {
+        return DetectionResult {
+            found: true,
+            path: Some(path),
+            args,
+            install_hint: String::new(),
+        };
+    }
+
     // Not found - return with install instructions
     DetectionResult {
         found: false,
         path: None,
+        args: Vec::new(),
         install_hint: install_instructions(),
     }
 }
 
+/// Fully resolved browser launch configuration: the detected binary, plus
+/// any wrapper arguments detection already needed (e.g. `run <app-id>` for
+/// a Flatpak install), with operator-configured extra flags appended.
+#[derive(Debug, Clone)]
+pub struct BrowserLaunchConfig {
+    /// Executable to launch.
+    pub path: PathBuf,
+    /// Full argument list, including any detection wrapper args followed
+    /// by `extra_flags`.
+    pub args: Vec<String>,
+}
+
+/// Resolve the full browser launch configuration: detect the browser, then
+/// append `extra_flags` (`[tools.browser] chrome_args`, e.g. `--no-sandbox`
+/// or `--proxy-server=...` for containerized/CI environments) after any
+/// wrapper arguments detection already required.
+///
+/// Logs the resolved extra flags at startup so a misconfigured flag is
+/// visible in the logs rather than silently breaking the browser launch.
+/// Returns `None` if no browser was detected.
+pub fn resolve_launch_config(
+    custom_path: Option<&str>,
+    extra_flags: &[String],
+) -> Option<BrowserLaunchConfig> {
+    let result = detect_browser(custom_path);
+    let path = result.path?;
+
+    let mut args = result.args;
+    args.extend(extra_flags.iter().cloned());
+
+    if !extra_flags.is_empty() {
+        tracing::info!(flags = ?extra_flags, "using extra browser launch flags");
+    }
+
+    Some(BrowserLaunchConfig { path, args })
+}
+
 /// Get platform-specific install instructions.
 fn install_instructions() -> String {
     let platform = if cfg!(target_os = "macos") {
@@ -156,6 +318,8 @@ fn install_instructions() -> String {
             "  Debian/Ubuntu: sudo apt install chromium-browser\n  \
              Fedora:         sudo dnf install chromium\n  \
              Arch:           sudo pacman -S chromium\n  \
+             Flatpak:        flatpak install flathub org.chromium.Chromium\n  \
+             Snap:           snap install chromium\n  \
              # Alternatives: brave-browser, microsoft-edge-stable"
         },
         "Windows" => {
@@ -176,29 +340,55 @@ fn install_instructions() -> String {
     )
 }
 
+/// Auto-download a Chromium build when the `auto_download` feature is
+/// compiled in; otherwise a no-op so `check_and_warn` doesn't need its own
+/// `#[cfg]`.
+#[cfg(feature = "auto_download")]
+fn try_auto_download() -> Option<PathBuf> {
+    crate::download::fetch_chromium()
+}
+
+#[cfg(not(feature = "auto_download"))]
+fn try_auto_download() -> Option<PathBuf> {
+    None
+}
+
 /// Check browser availability and warn if not found.
 ///
 /// Call this at startup when browser is enabled. Prints a visible warning
-/// to stderr and logs via tracing for log file capture.
-pub fn check_and_warn(custom_path: Option<&str>) -> bool {
+/// to stderr and logs via tracing for log file capture. When `auto_download`
+/// is true (`[tools.browser] auto_download = true`) and no browser was
+/// found, attempts to download a known-good headless Chromium build before
+/// falling back to the manual-install warning.
+pub fn check_and_warn(custom_path: Option<&str>, auto_download: bool) -> bool {
     let result = detect_browser(custom_path);
 
-    if !result.found {
-        // Print to stderr for immediate visibility to users
-        eprintln!("\n⚠️  Browser tool enabled but Chrome/Chromium not found!");
-        eprintln!("{}", result.install_hint);
-        eprintln!();
-
-        // Also log for log file capture
-        tracing::warn!(
-            "Browser tool enabled but Chrome/Chromium not found.\n{}",
-            result.install_hint
-        );
-    } else if let Some(ref path) = result.path {
-        tracing::info!(path = %path.display(), "Browser detected");
+    if result.found {
+        if let Some(ref path) = result.path {
+            tracing::info!(path = %path.display(), "Browser detected");
+        }
+        return true;
+    }
+
+    if auto_download {
+        if let Some(path) = try_auto_download() {
+            tracing::info!(path = %path.display(), "downloaded Chromium to {}", path.display());
+            return true;
+        }
     }
 
-    result.found
+    // Print to stderr for immediate visibility to users
+    eprintln!("\n⚠️  Browser tool enabled but Chrome/Chromium not found!");
+    eprintln!("{}", result.install_hint);
+    eprintln!();
+
+    // Also log for log file capture
+    tracing::warn!(
+        "Browser tool enabled but Chrome/Chromium not found.\n{}",
+        result.install_hint
+    );
+
+    false
 }
 
 #[cfg(test)]
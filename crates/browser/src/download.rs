@@ -0,0 +1,140 @@
+//! Automatic download of a known-good headless Chromium build.
+//!
+//! Opt-in via `[tools.browser] auto_download = true`, for environments
+//! where no Chromium-based browser is already installed and the operator
+//! would rather not hand-install one. Gated behind the `auto_download`
+//! Cargo feature so offline/minimal builds don't pull in zip extraction or
+//! an extra HTTP client they'll never use.
+
+#![cfg(feature = "auto_download")]
+
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+/// Chromium snapshot revision known to work with this crate's CDP usage.
+/// Pinned (rather than "latest") so a run today and a run next year
+/// download the same build.
+const CHROMIUM_REVISION: &str = "1313161";
+
+/// Chromium snapshot storage platform bucket, per
+/// <https://storage.googleapis.com/chromium-browser-snapshots/>.
+fn snapshot_platform() -> Option<&'static str> {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("Linux_x64")
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("Mac_Arm")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("Mac")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("Win_x64")
+    } else {
+        None
+    }
+}
+
+/// Name of the zip asset published for a given platform bucket.
+fn snapshot_zip_name(platform: &str) -> &'static str {
+    match platform {
+        "Linux_x64" => "chrome-linux.zip",
+        "Win_x64" => "chrome-win.zip",
+        _ => "chrome-mac.zip",
+    }
+}
+
+/// Path to the browser executable inside the extracted snapshot, relative
+/// to the per-revision cache directory.
+fn relative_executable_path(platform: &str) -> PathBuf {
+    match platform {
+        "Linux_x64" => PathBuf::from("chrome-linux/chrome"),
+        "Win_x64" => PathBuf::from("chrome-win/chrome.exe"),
+        _ => PathBuf::from("chrome-mac/Chromium.app/Contents/MacOS/Chromium"),
+    }
+}
+
+/// Per-user cache directory that downloaded Chromium builds are extracted
+/// under. [`fetch_chromium`] namespaces each extraction beneath it by
+/// platform and revision so a revision bump or a platform change doesn't
+/// collide with a stale extraction.
+fn cache_root() -> Option<PathBuf> {
+    ProjectDirs::from("dev", "moltis", "moltis").map(|dirs| dirs.cache_dir().join("chromium"))
+}
+
+/// Download and extract a known-good Chromium build for the current
+/// platform, returning the path to its executable.
+///
+/// Returns `None` (rather than erroring) on anything that goes wrong —
+/// unsupported platform, network failure, or a corrupt/incomplete
+/// extraction — so callers can fall back to the existing manual-install
+/// warning. If a previous run already extracted and verified this
+/// revision, the cached executable is reused and no network request is
+/// made.
+pub fn fetch_chromium() -> Option<PathBuf> {
+    let platform = snapshot_platform()?;
+    let cache_root = cache_root()?;
+    let revision_dir = cache_root.join(platform).join(CHROMIUM_REVISION);
+    let exe_path = revision_dir.join(relative_executable_path(platform));
+
+    if is_runnable(&exe_path) {
+        return Some(exe_path);
+    }
+
+    if let Err(e) = download_and_extract(platform, &revision_dir) {
+        tracing::warn!(error = %e, "failed to auto-download Chromium");
+        return None;
+    }
+
+    is_runnable(&exe_path).then_some(exe_path)
+}
+
+fn download_and_extract(platform: &str, dest: &Path) -> Result<(), String> {
+    let url = format!(
+        "https://storage.googleapis.com/chromium-browser-snapshots/{platform}/{CHROMIUM_REVISION}/{}",
+        snapshot_zip_name(platform)
+    );
+
+    tracing::info!(url, "downloading Chromium snapshot");
+    let response = ureq::get(&url).call().map_err(|e| format!("request failed: {e}"))?;
+    let mut bytes = Vec::new();
+    std::io::Read::read_to_end(&mut response.into_reader(), &mut bytes)
+        .map_err(|e| format!("reading response: {e}"))?;
+
+    std::fs::create_dir_all(dest).map_err(|e| format!("creating cache dir: {e}"))?;
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("opening zip: {e}"))?;
+    archive.extract(dest).map_err(|e| format!("extracting zip: {e}"))?;
+
+    mark_executable(&dest.join(relative_executable_path(platform)))
+}
+
+#[cfg(unix)]
+fn mark_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = std::fs::metadata(path)
+        .map_err(|e| format!("stat extracted binary: {e}"))?
+        .permissions();
+    perms.set_mode(perms.mode() | 0o111);
+    std::fs::set_permissions(path, perms).map_err(|e| format!("chmod extracted binary: {e}"))
+}
+
+#[cfg(not(unix))]
+fn mark_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+/// Whether `path` points at a file that exists and (on unix) is marked
+/// executable, i.e. a previous extraction can be trusted without re-running
+/// it.
+fn is_runnable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path)
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
@@ -70,12 +70,49 @@ pub enum BrowserAction {
 
     /// Close the browser session.
     Close,
+
+    /// Export the current page as PDF via CDP `Page.printToPDF`, returned
+    /// as base64-encoded bytes in [`BrowserResponse::pdf`].
+    PrintToPdf {
+        #[serde(default)]
+        landscape: bool,
+        #[serde(default = "default_print_background")]
+        print_background: bool,
+        #[serde(default = "default_pdf_scale")]
+        scale: f64,
+    },
+
+    /// Seed the session's cookie jar via CDP `Network.setCookie`, so sign-in
+    /// state can be carried over from one `session_id` to another.
+    SetCookies { cookies: Vec<Cookie> },
+
+    /// Read back the session's cookie jar via CDP `Network.getAllCookies`,
+    /// returned in [`BrowserResponse::cookies`].
+    GetCookies,
+
+    /// Toggle network traffic capture for the session. While enabled,
+    /// subscribes to CDP `Network.requestWillBeSent`/`Network.responseReceived`
+    /// and accumulates request/response metadata, returned in
+    /// [`BrowserResponse::network`] by this and every later action on the
+    /// session until capture is disabled again.
+    CaptureRequests {
+        #[serde(default)]
+        enabled: bool,
+    },
 }
 
 fn default_wait_timeout_ms() -> u64 {
     30000
 }
 
+fn default_print_background() -> bool {
+    true
+}
+
+fn default_pdf_scale() -> f64 {
+    1.0
+}
+
 /// Request to the browser service.
 #[derive(Debug, Clone, Deserialize)]
 pub struct BrowserRequest {
@@ -96,6 +133,39 @@ fn default_timeout_ms() -> u64 {
     60000
 }
 
+/// A single browser cookie, as accepted by CDP's `Network.setCookie` and
+/// returned by `Network.getAllCookies`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Defaults to the current page's domain when omitted on `set_cookies`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    /// Expiration as seconds since the Unix epoch; omitted means a session
+    /// cookie.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<f64>,
+}
+
+/// One request/response pair collected while `capture_requests` is enabled.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedRequest {
+    pub url: String,
+    pub method: String,
+    /// Absent until `Network.responseReceived` fires for this request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
 /// Element reference in a DOM snapshot.
 #[derive(Debug, Clone, Serialize)]
 pub struct ElementRef {
@@ -203,6 +273,19 @@ pub struct BrowserResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
 
+    /// PDF export as base64-encoded bytes (for print_to_pdf).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf: Option<String>,
+
+    /// The session's cookie jar (for set_cookies/get_cookies).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookies: Option<Vec<Cookie>>,
+
+    /// Captured request/response metadata (for capture_requests, and any
+    /// later action while capture is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<Vec<CapturedRequest>>,
+
     /// Duration of the action in milliseconds.
     pub duration_ms: u64,
 }
@@ -218,6 +301,9 @@ impl BrowserResponse {
             result: None,
             url: None,
             title: None,
+            pdf: None,
+            cookies: None,
+            network: None,
             duration_ms,
         }
     }
@@ -232,6 +318,9 @@ impl BrowserResponse {
             result: None,
             url: None,
             title: None,
+            pdf: None,
+            cookies: None,
+            network: None,
             duration_ms,
         }
     }
@@ -260,6 +349,21 @@ impl BrowserResponse {
         self.title = Some(title);
         self
     }
+
+    pub fn with_pdf(mut self, pdf: String) -> Self {
+        self.pdf = Some(pdf);
+        self
+    }
+
+    pub fn with_cookies(mut self, cookies: Vec<Cookie>) -> Self {
+        self.cookies = Some(cookies);
+        self
+    }
+
+    pub fn with_network(mut self, network: Vec<CapturedRequest>) -> Self {
+        self.network = Some(network);
+        self
+    }
 }
 
 /// Browser configuration.
@@ -284,9 +388,16 @@ pub struct BrowserConfig {
     pub navigation_timeout_ms: u64,
     /// User agent string (uses default if not set).
     pub user_agent: Option<String>,
-    /// Additional Chrome arguments.
+    /// Additional Chrome launch flags (e.g. `--no-sandbox`,
+    /// `--disable-gpu`, `--proxy-server=...`), appended to the detected
+    /// browser's invocation via `detect::resolve_launch_config`. Useful for
+    /// containerized/CI environments.
     #[serde(default)]
     pub chrome_args: Vec<String>,
+    /// When no browser is detected, download a known-good headless
+    /// Chromium build instead of only printing install instructions.
+    /// Requires the crate's `auto_download` feature.
+    pub auto_download: bool,
 }
 
 impl Default for BrowserConfig {
@@ -302,6 +413,7 @@ impl Default for BrowserConfig {
             navigation_timeout_ms: 30000,
             user_agent: None,
             chrome_args: Vec::new(),
+            auto_download: false,
         }
     }
 }
@@ -319,6 +431,7 @@ impl From<&moltis_config::schema::BrowserConfig> for BrowserConfig {
             navigation_timeout_ms: cfg.navigation_timeout_ms,
             user_agent: cfg.user_agent.clone(),
             chrome_args: cfg.chrome_args.clone(),
+            auto_download: cfg.auto_download,
         }
     }
 }
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+/// Configuration for a single Fediverse (ActivityPub/Mastodon-compatible)
+/// account.
+///
+/// Unlike the other channels, credentials aren't stored here — posting
+/// reuses the OAuth subsystem's `mastodon` provider entry (see
+/// `LiveProviderSetupService`), so the access token lives in the shared
+/// `TokenStore` instead of this config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FediverseAccountConfig {
+    /// Base URL of the instance, e.g. `https://mastodon.social`.
+    pub instance_url: String,
+
+    /// Maximum status length before `FediverseOutbound` splits a reply
+    /// into a reply chain of multiple statuses. `0` means "use the
+    /// instance's typical default" (500, Mastodon's own limit).
+    pub character_limit: usize,
+
+    /// Default status visibility (`public`, `unlisted`, `private`,
+    /// `direct`) applied to every post from this account.
+    pub default_visibility: String,
+
+    /// Default model ID for this account's sessions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Default for FediverseAccountConfig {
+    fn default() -> Self {
+        Self {
+            instance_url: String::new(),
+            character_limit: 0,
+            default_visibility: "public".to_string(),
+            model: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config() {
+        let cfg = FediverseAccountConfig::default();
+        assert_eq!(cfg.instance_url, "");
+        assert_eq!(cfg.character_limit, 0);
+        assert_eq!(cfg.default_visibility, "public");
+        assert!(cfg.model.is_none());
+    }
+
+    #[test]
+    fn deserialize_from_json() {
+        let json = r#"{
+            "instance_url": "https://mastodon.social",
+            "character_limit": 500
+        }"#;
+        let cfg: FediverseAccountConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.instance_url, "https://mastodon.social");
+        assert_eq!(cfg.character_limit, 500);
+        // default for unspecified fields
+        assert_eq!(cfg.default_visibility, "public");
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let cfg = FediverseAccountConfig {
+            instance_url: "https://example.social".into(),
+            character_limit: 280,
+            default_visibility: "unlisted".into(),
+            model: Some("anthropic/claude-sonnet".into()),
+        };
+        let json = serde_json::to_string(&cfg).unwrap();
+        let cfg2: FediverseAccountConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(cfg2.instance_url, "https://example.social");
+        assert_eq!(cfg2.character_limit, 280);
+        assert_eq!(cfg2.default_visibility, "unlisted");
+        assert_eq!(cfg2.model.as_deref(), Some("anthropic/claude-sonnet"));
+    }
+}
@@ -0,0 +1,13 @@
+//! Fediverse channel plugin for moltis.
+//!
+//! Implements outbound delivery to Mastodon/Misskey-style ActivityPub
+//! instances over their (Mastodon-originated, widely compatible) REST API.
+//! There's no bot connection to hold open — each send is a stateless
+//! authenticated HTTP call — so this crate is considerably smaller than the
+//! Telegram/WhatsApp/Matrix channels it sits alongside.
+
+pub mod config;
+pub mod outbound;
+pub mod state;
+
+pub use {config::FediverseAccountConfig, outbound::FediverseOutbound};
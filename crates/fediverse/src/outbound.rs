@@ -0,0 +1,198 @@
+use std::io::Read as _;
+
+use {
+    anyhow::{Context, Result},
+    async_trait::async_trait,
+    serde::Deserialize,
+};
+
+use moltis_channels::plugin::ChannelOutbound;
+use moltis_common::types::ReplyPayload;
+use moltis_oauth::TokenStore;
+
+use crate::state::AccountStateMap;
+
+/// Provider name this channel's access token is filed under in the shared
+/// `TokenStore` — matches the `mastodon` entry registered in
+/// `KNOWN_PROVIDERS` (see `crate::provider_setup` in the gateway crate).
+const TOKEN_PROVIDER: &str = "mastodon";
+
+/// Fallback status length cap used when an account's `character_limit` is
+/// left at `0`. Mastodon's own default; compatible instances (Misskey,
+/// Pleroma, etc.) vary but this is a safe floor for chunking.
+const DEFAULT_CHARACTER_LIMIT: usize = 500;
+
+#[derive(Deserialize)]
+struct StatusResponse {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct MediaResponse {
+    id: String,
+}
+
+/// Fetch the bytes of a hosted media URL so they can be uploaded to the
+/// instance's media endpoint. `ReplyPayload::media` only carries a URL —
+/// same shape the Telegram outbound hands straight to `InputFile::url` —
+/// but Mastodon's status API takes pre-uploaded media IDs, not remote
+/// URLs, so the bytes have to be fetched and re-uploaded first.
+fn fetch_media_bytes(url: &str) -> Result<Vec<u8>> {
+    let response = ureq::get(url).call().context("fetching media url")?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .context("reading media bytes")?;
+    Ok(bytes)
+}
+
+/// Outbound message sender for Mastodon/Misskey-style ActivityPub
+/// instances, via their (shared, Mastodon-originated) REST API.
+pub struct FediverseOutbound {
+    pub(crate) accounts: AccountStateMap,
+    pub(crate) token_store: TokenStore,
+}
+
+impl FediverseOutbound {
+    fn account_config(&self, account_id: &str) -> Result<crate::config::FediverseAccountConfig> {
+        let accounts = self.accounts.read().unwrap();
+        accounts
+            .get(account_id)
+            .map(|s| s.config.clone())
+            .ok_or_else(|| anyhow::anyhow!("unknown account: {account_id}"))
+    }
+
+    fn access_token(&self) -> Result<String> {
+        self.token_store
+            .load(TOKEN_PROVIDER)
+            .map(|tokens| tokens.access_token)
+            .ok_or_else(|| anyhow::anyhow!("no mastodon access token on file; complete the OAuth flow first"))
+    }
+
+    /// Split `text` into status-sized chunks on word boundaries, never
+    /// cutting a word in half. Mirrors the byte-budget chunking WhatsApp's
+    /// rate limiter and Telegram's streaming truncation both reason about,
+    /// but on whole-status granularity instead of bytes-in-flight.
+    fn chunk_status(text: &str, limit: usize) -> Vec<String> {
+        if text.len() <= limit {
+            return vec![text.to_string()];
+        }
+
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        for word in text.split_whitespace() {
+            let extra = if current.is_empty() { word.len() } else { word.len() + 1 };
+            if current.len() + extra > limit && !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+        chunks
+    }
+
+    /// Post a single status, optionally as a reply (`in_reply_to_id`) and/or
+    /// with attached media (`media_ids`). Returns the new status's id so a
+    /// reply chain can thread off it.
+    fn post_status(
+        &self,
+        instance_url: &str,
+        status: &str,
+        visibility: &str,
+        in_reply_to_id: Option<&str>,
+        media_ids: &[String],
+    ) -> Result<String> {
+        let token = self.access_token()?;
+        let mut body = serde_json::json!({
+            "status": status,
+            "visibility": visibility,
+        });
+        if let Some(reply_id) = in_reply_to_id {
+            body["in_reply_to_id"] = serde_json::Value::String(reply_id.to_string());
+        }
+        if !media_ids.is_empty() {
+            body["media_ids"] = serde_json::Value::from(media_ids.to_vec());
+        }
+
+        let response = ureq::post(&format!("{instance_url}/api/v1/statuses"))
+            .set("Authorization", &format!("Bearer {token}"))
+            .send_json(body)
+            .context("posting status")?;
+        let parsed: StatusResponse = response.into_json().context("parsing status response")?;
+        Ok(parsed.id)
+    }
+
+    /// Upload media ahead of a status post; Mastodon's v2 media endpoint
+    /// returns immediately with the attachment still processing, but the
+    /// returned id is usable in a status right away.
+    fn upload_media(&self, instance_url: &str, filename: &str, mime_type: &str, bytes: Vec<u8>) -> Result<String> {
+        let token = self.access_token()?;
+        let response = ureq::post(&format!("{instance_url}/api/v2/media"))
+            .set("Authorization", &format!("Bearer {token}"))
+            .set("Content-Type", mime_type)
+            .set("Content-Disposition", &format!("form-data; name=\"file\"; filename=\"{filename}\""))
+            .send_bytes(&bytes)
+            .context("uploading media")?;
+        let parsed: MediaResponse = response.into_json().context("parsing media response")?;
+        Ok(parsed.id)
+    }
+}
+
+#[async_trait]
+impl ChannelOutbound for FediverseOutbound {
+    async fn send_text(&self, account_id: &str, _to: &str, text: &str) -> Result<()> {
+        let config = self.account_config(account_id)?;
+        let limit = if config.character_limit == 0 {
+            DEFAULT_CHARACTER_LIMIT
+        } else {
+            config.character_limit
+        };
+
+        let mut reply_to: Option<String> = None;
+        for chunk in Self::chunk_status(text, limit) {
+            // Chain each chunk off the previous one so a long reply reads
+            // as a status thread instead of several unrelated top-level
+            // posts.
+            let id = self.post_status(&config.instance_url, &chunk, &config.default_visibility, reply_to.as_deref(), &[])?;
+            reply_to = Some(id);
+        }
+
+        Ok(())
+    }
+
+    async fn send_typing(&self, _account_id: &str, _to: &str) -> Result<()> {
+        // Mastodon's API has no typing-indicator concept.
+        Ok(())
+    }
+
+    fn typing_interval(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    fn supports_markdown(&self) -> bool {
+        // Status text is plain text; `send_text` doesn't render markdown.
+        false
+    }
+
+    async fn send_media(&self, account_id: &str, _to: &str, payload: &ReplyPayload) -> Result<()> {
+        let config = self.account_config(account_id)?;
+
+        let media = payload
+            .media
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("send_media called without a media payload"))?;
+
+        let bytes = fetch_media_bytes(&media.url)?;
+        let filename = media.filename.clone().unwrap_or_else(|| "file".to_string());
+        let media_id = self.upload_media(&config.instance_url, &filename, &media.mime_type, bytes)?;
+        self.post_status(&config.instance_url, &payload.text, &config.default_visibility, None, &[media_id])?;
+
+        Ok(())
+    }
+}
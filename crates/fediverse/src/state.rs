@@ -0,0 +1,21 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{config::FediverseAccountConfig, outbound::FediverseOutbound};
+
+/// Shared account state map.
+pub type AccountStateMap = Arc<RwLock<HashMap<String, AccountState>>>;
+
+/// Per-account runtime state.
+///
+/// There's no persistent connection handle here, unlike Telegram/WhatsApp/
+/// Matrix — posting to an ActivityPub instance is stateless REST over
+/// `instance_url` plus a bearer token from the shared `TokenStore`, so
+/// nothing needs to be held open between sends.
+pub struct AccountState {
+    pub account_id: String,
+    pub config: FediverseAccountConfig,
+    pub outbound: Arc<FediverseOutbound>,
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use moltis_channels::ChannelReplyTarget;
+
+/// Routes inbound messages from one channel/account/chat to one or more
+/// destination chats, possibly on different channel types and accounts.
+///
+/// This is the matterbridge-style relay table behind
+/// [`GatewayChannelEventSink::relay_message`][crate::channel_events::GatewayChannelEventSink::relay_message]:
+/// a source `(channel_type, account_id, chat_id)` triple maps to the
+/// `ChannelReplyTarget`s it should be mirrored to.
+#[derive(Default)]
+pub struct BridgeRouter {
+    routes: RwLock<HashMap<(String, String, String), Vec<ChannelReplyTarget>>>,
+}
+
+impl BridgeRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map a source chat to one or more destination chats.
+    ///
+    /// Replaces any existing mapping for the same source.
+    pub fn set_route(
+        &self,
+        channel_type: &str,
+        account_id: &str,
+        chat_id: &str,
+        destinations: Vec<ChannelReplyTarget>,
+    ) {
+        let mut routes = self.routes.write().unwrap_or_else(|e| e.into_inner());
+        routes.insert(
+            (channel_type.to_string(), account_id.to_string(), chat_id.to_string()),
+            destinations,
+        );
+    }
+
+    /// Remove the mapping for a source chat, if any.
+    pub fn remove_route(&self, channel_type: &str, account_id: &str, chat_id: &str) {
+        let mut routes = self.routes.write().unwrap_or_else(|e| e.into_inner());
+        routes.remove(&(channel_type.to_string(), account_id.to_string(), chat_id.to_string()));
+    }
+
+    /// Look up the destinations configured for a source chat.
+    pub fn routes_for(&self, channel_type: &str, account_id: &str, chat_id: &str) -> Vec<ChannelReplyTarget> {
+        let routes = self.routes.read().unwrap_or_else(|e| e.into_inner());
+        routes
+            .get(&(channel_type.to_string(), account_id.to_string(), chat_id.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn target(channel_type: &str, account_id: &str, chat_id: &str) -> ChannelReplyTarget {
+        ChannelReplyTarget {
+            channel_type: channel_type.into(),
+            account_id: account_id.into(),
+            chat_id: chat_id.into(),
+            message_id: None,
+        }
+    }
+
+    #[test]
+    fn no_route_returns_empty() {
+        let router = BridgeRouter::new();
+        assert!(router.routes_for("whatsapp", "wa1", "123@s.whatsapp.net").is_empty());
+    }
+
+    #[test]
+    fn set_and_look_up_route() {
+        let router = BridgeRouter::new();
+        let dest = vec![target("telegram", "tg1", "-100555")];
+        router.set_route("whatsapp", "wa1", "123@s.whatsapp.net", dest);
+        let routes = router.routes_for("whatsapp", "wa1", "123@s.whatsapp.net");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].channel_type, "telegram");
+        assert_eq!(routes[0].account_id, "tg1");
+        assert_eq!(routes[0].chat_id, "-100555");
+    }
+
+    #[test]
+    fn set_route_replaces_existing() {
+        let router = BridgeRouter::new();
+        router.set_route("whatsapp", "wa1", "123", vec![target("telegram", "tg1", "a")]);
+        router.set_route("whatsapp", "wa1", "123", vec![target("telegram", "tg1", "b")]);
+        let routes = router.routes_for("whatsapp", "wa1", "123");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].chat_id, "b");
+    }
+
+    #[test]
+    fn remove_route_clears_mapping() {
+        let router = BridgeRouter::new();
+        router.set_route("whatsapp", "wa1", "123", vec![target("telegram", "tg1", "a")]);
+        router.remove_route("whatsapp", "wa1", "123");
+        assert!(router.routes_for("whatsapp", "wa1", "123").is_empty());
+    }
+
+    #[test]
+    fn same_chat_id_different_channel_types_are_distinct_sources() {
+        let router = BridgeRouter::new();
+        router.set_route("whatsapp", "acct1", "123", vec![target("telegram", "tg1", "a")]);
+        assert!(router.routes_for("telegram", "acct1", "123").is_empty());
+        assert_eq!(router.routes_for("whatsapp", "acct1", "123").len(), 1);
+    }
+}
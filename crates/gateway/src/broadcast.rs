@@ -0,0 +1,79 @@
+//! Core WebSocket/SSE fan-out: the single place an event produced anywhere
+//! in the gateway (channel bridge, chat pipeline) reaches every connected
+//! client. See [`crate::channel_events`] (the channel bridge's sink) for a
+//! caller, and [`crate::sse_routes`] (the SSE transport) for the
+//! pseudo-clients it registers, which this fan-out reaches like any other.
+
+use std::sync::Arc;
+
+use tracing::{debug, warn};
+
+use crate::{
+    channel_filters::filter_allows,
+    methods::subscribe::client_wants_event,
+    state::GatewayState,
+};
+
+/// Tuning knobs for a single [`broadcast`] call.
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastOpts {
+    /// Drop the frame for a client whose send queue is full instead of
+    /// waiting for it to drain — used for high-frequency streams (channel
+    /// events, chat deltas) where one slow client shouldn't stall everyone
+    /// else.
+    pub drop_if_slow: bool,
+}
+
+/// Derive the `channel.join` channel key a broadcast payload belongs to,
+/// for the two payload shapes callers in this crate actually produce:
+/// `channel_events::dispatch_to_chat`'s `sessionKey` field directly
+/// (`"{channel_type}:{account_id}:{chat_id}"`), or a bare
+/// `channel_type`/`account_id` pair for events with no chat-level detail.
+fn payload_channel_key(payload: &serde_json::Value) -> Option<String> {
+    if let Some(key) = payload.get("sessionKey").and_then(|v| v.as_str()) {
+        return Some(key.to_string());
+    }
+    let channel_type = payload.get("channel_type").and_then(|v| v.as_str())?;
+    let account_id = payload.get("account_id").and_then(|v| v.as_str())?;
+    Some(format!("{channel_type}:{account_id}"))
+}
+
+/// Fan `payload` out to every client subscribed to `event_name`.
+///
+/// Two filters gate delivery, in order: first, when `payload` carries
+/// message text alongside a channel key ([`payload_channel_key`]), the
+/// persisted per-channel mute/keyword filter set up via `channel.join`
+/// ([`crate::channel_filters::filter_allows`]) can suppress the whole
+/// broadcast before any client is even considered. Second, each remaining
+/// client's own [`SubscriptionSpec`](crate::methods::subscribe::SubscriptionSpec)
+/// filter is consulted via [`client_wants_event`] so a client that
+/// subscribed with a narrowing filter only receives matching events.
+pub async fn broadcast(
+    state: &Arc<GatewayState>,
+    event_name: &str,
+    payload: serde_json::Value,
+    opts: BroadcastOpts,
+) {
+    if let Some(text) = payload.get("text").and_then(|v| v.as_str())
+        && let Some(channel) = payload_channel_key(&payload)
+    {
+        let store = state.gateway.channel_filter_store.as_deref();
+        if !filter_allows(store, &channel, text).await {
+            debug!(event_name, channel, "channel filter suppressed broadcast");
+            return;
+        }
+    }
+
+    let inner = state.inner.read().await;
+    for (conn_id, client) in inner.clients.iter() {
+        let Some(subs) = client.subscriptions.as_ref() else {
+            continue;
+        };
+        if !client_wants_event(subs, event_name, &payload) {
+            continue;
+        }
+        if let Err(e) = client.try_send(event_name, &payload, opts.drop_if_slow).await {
+            warn!(conn_id, "dropping client after failed send: {e}");
+        }
+    }
+}
@@ -1,11 +1,15 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use {anyhow::anyhow, async_trait::async_trait, tracing::{error, debug, warn}};
 
-use moltis_channels::{ChannelEvent, ChannelEventSink, ChannelMessageMeta, ChannelReplyTarget};
+use moltis_channels::{
+    ChannelAttachment, ChannelEvent, ChannelEventSink, ChannelMessageMeta, ChannelReplyTarget,
+};
 
 use crate::{
+    bridge::BridgeRouter,
     broadcast::{BroadcastOpts, broadcast},
+    commands::{self, CommandRegistry},
     state::GatewayState,
 };
 
@@ -20,11 +24,92 @@ fn channel_session_key(target: &ChannelReplyTarget) -> String {
 /// `GatewayState` exists (same pattern as cron callbacks).
 pub struct GatewayChannelEventSink {
     state: Arc<tokio::sync::OnceCell<Arc<GatewayState>>>,
+    bridges: Arc<BridgeRouter>,
+    commands: Arc<CommandRegistry>,
+    /// Per-session `/model` overrides (see `commands::register_builtins`),
+    /// consulted by `dispatch_to_chat` ahead of a channel's static default
+    /// model.
+    session_models: Arc<tokio::sync::Mutex<HashMap<String, String>>>,
+    /// Per-session `/arena <modelA> <modelB>` pairs. When a session has one,
+    /// `dispatch_to_chat` fans the user's text out to both models instead of
+    /// sending a single `chat.send`.
+    session_arenas: Arc<tokio::sync::Mutex<HashMap<String, (String, String)>>>,
 }
 
 impl GatewayChannelEventSink {
     pub fn new(state: Arc<tokio::sync::OnceCell<Arc<GatewayState>>>) -> Self {
-        Self { state }
+        let session_models = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let session_arenas = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let mut commands = CommandRegistry::default();
+        commands::register_builtins(&mut commands, session_models.clone(), session_arenas.clone());
+        commands.finalize_help();
+
+        Self {
+            state,
+            bridges: Arc::new(BridgeRouter::new()),
+            commands: Arc::new(commands),
+            session_models,
+            session_arenas,
+        }
+    }
+
+    /// The bridge routing table backing [`ChannelEventSink::relay_message`].
+    /// Exposed so account setup can populate routes from config.
+    pub fn bridges(&self) -> &Arc<BridgeRouter> {
+        &self.bridges
+    }
+
+    /// The slash-command registry backing [`ChannelEventSink::dispatch_command`].
+    /// Shared behind an `Arc`, so it is read-only here: the command set and
+    /// the `/help` listing are fixed when [`Self::new`] registers the
+    /// builtins and calls `finalize_help`.
+    pub fn commands(&self) -> &Arc<CommandRegistry> {
+        &self.commands
+    }
+
+    /// Fan `text` out to both arena models concurrently under sub-session
+    /// keys derived from `session_key` (`#arenaA`/`#arenaB`), each
+    /// registered against the same reply target so both final replies route
+    /// back to the originating chat. Each `chat.send` carries its model, but
+    /// this function adds no model label to the replies themselves.
+    /// Independent `chat.send` calls via `tokio::join!` so one slow model
+    /// can't block the other.
+    async fn dispatch_arena(
+        &self,
+        state: &Arc<GatewayState>,
+        text: &str,
+        reply_to: &ChannelReplyTarget,
+        meta: &ChannelMessageMeta,
+        session_key: &str,
+        model_a: String,
+        model_b: String,
+    ) {
+        let chat = state.chat().await;
+
+        let session_a = format!("{session_key}#arenaA");
+        let session_b = format!("{session_key}#arenaB");
+        state.push_channel_reply(&session_a, reply_to.clone()).await;
+        state.push_channel_reply(&session_b, reply_to.clone()).await;
+
+        let params_for = |arena_session_key: &str, model: &str| {
+            serde_json::json!({
+                "text": text,
+                "channel": meta,
+                "_session_key": arena_session_key,
+                "model": model,
+            })
+        };
+
+        let (result_a, result_b) = tokio::join!(
+            chat.send(params_for(&session_a, &model_a)),
+            chat.send(params_for(&session_b, &model_b)),
+        );
+        if let Err(e) = result_a {
+            error!("arena dispatch to {model_a} failed: {e}");
+        }
+        if let Err(e) = result_b {
+            error!("arena dispatch to {model_b} failed: {e}");
+        }
     }
 }
 
@@ -39,6 +124,10 @@ impl ChannelEventSink for GatewayChannelEventSink {
                     return;
                 },
             };
+            // `broadcast` consults each subscriber's `SubscriptionSpec` via
+            // `crate::methods::subscribe::client_wants_event`, matching this
+            // serialized payload against any `filter` the client registered
+            // on `subscribe` before sending the frame.
             broadcast(
                 state,
                 "channel",
@@ -79,76 +168,125 @@ impl ChannelEventSink for GatewayChannelEventSink {
             // route the response back to the originating channel.
             state.push_channel_reply(&session_key, reply_to.clone()).await;
 
+            // `/arena <modelA> <modelB>` (see `crate::commands`) fans this
+            // message out to both models at once instead of the normal
+            // single-model send below.
+            let arena = self.session_arenas.lock().await.get(&session_key).cloned();
+            if let Some((model_a, model_b)) = arena {
+                self.dispatch_arena(state, text, &reply_to, &meta, &session_key, model_a, model_b)
+                    .await;
+                return;
+            }
+
             let chat = state.chat().await;
             let mut params = serde_json::json!({
                 "text": text,
                 "channel": &meta,
                 "_session_key": &session_key,
             });
-            // Forward the channel's default model to chat.send() if configured.
-            if let Some(ref model) = meta.model {
+            // A per-session `/model` override (see `crate::commands`) wins
+            // over the channel's static default model.
+            let session_model = self.session_models.lock().await.get(&session_key).cloned();
+            if let Some(model) = session_model.or_else(|| meta.model.clone()) {
                 params["model"] = serde_json::json!(model);
             }
 
-            // Send a repeating "typing" indicator every 4s until chat.send()
-            // completes. Telegram's typing status expires after ~5s.
-            if let Some(outbound) = state.services.channel_outbound_arc() {
-                let (done_tx, mut done_rx) = tokio::sync::oneshot::channel::<()>();
-                let account_id = reply_to.account_id.clone();
-                let chat_id = reply_to.chat_id.clone();
-                tokio::spawn(async move {
-                    loop {
-                        if let Err(e) = outbound.send_typing(&account_id, &chat_id).await {
-                            debug!("typing indicator failed: {e}");
-                        }
-                        tokio::select! {
-                            _ = tokio::time::sleep(std::time::Duration::from_secs(4)) => {},
-                            _ = &mut done_rx => break,
+            // Only spawn a repeating typing indicator when the backend
+            // declares an interval to resend it at — Telegram/WhatsApp need
+            // one since their typing state expires, Matrix's SDK handles
+            // its own heartbeat, and channels with no typing concept at all
+            // (Mastodon, Twitch/YouTube chat) declare `None`.
+            let typing_loop = match state.services.channel_outbound_arc().and_then(|o| o.typing_interval().map(|i| (o, i))) {
+                Some((outbound, interval)) => {
+                    let (done_tx, mut done_rx) = tokio::sync::oneshot::channel::<()>();
+                    let account_id = reply_to.account_id.clone();
+                    let chat_id = reply_to.chat_id.clone();
+                    tokio::spawn(async move {
+                        loop {
+                            if let Err(e) = outbound.send_typing(&account_id, &chat_id).await {
+                                debug!("typing indicator failed: {e}");
+                            }
+                            tokio::select! {
+                                _ = tokio::time::sleep(interval) => {},
+                                _ = &mut done_rx => break,
+                            }
                         }
-                    }
-                });
-                if let Err(e) = chat.send(params).await {
-                    error!("channel dispatch_to_chat failed: {e}");
-                }
-                let _ = done_tx.send(());
-            } else if let Err(e) = chat.send(params).await {
+                    });
+                    Some(done_tx)
+                },
+                _ => None,
+            };
+
+            if let Err(e) = chat.send(params).await {
                 error!("channel dispatch_to_chat failed: {e}");
             }
+            if let Some(done_tx) = typing_loop {
+                let _ = done_tx.send(());
+            }
         } else {
             warn!("channel dispatch_to_chat: gateway not ready");
         }
     }
 
     async fn dispatch_command(&self, command: &str, reply_to: ChannelReplyTarget) -> anyhow::Result<String> {
-        let state = self.state.get().ok_or_else(|| anyhow!("gateway not ready"))?;
+        let state = self.state.get().ok_or_else(|| anyhow!("gateway not ready"))?.clone();
         let session_key = channel_session_key(&reply_to);
-        let chat = state.chat().await;
-        let params = serde_json::json!({ "_session_key": &session_key });
-
-        match command {
-            "new" | "clear" => {
-                chat.clear(params).await.map_err(|e| anyhow!("{e}"))?;
-                let label = if command == "new" { "New session started." } else { "Session cleared." };
-                Ok(label.to_string())
-            },
-            "compact" => {
-                chat.compact(params).await.map_err(|e| anyhow!("{e}"))?;
-                Ok("Session compacted.".to_string())
-            },
-            "context" => {
-                let res = chat.context(params).await.map_err(|e| anyhow!("{e}"))?;
-                // Format context info as a readable text summary.
-                let session_info = res.get("session").cloned().unwrap_or_default();
-                let msg_count = session_info.get("messageCount").and_then(|v| v.as_u64()).unwrap_or(0);
-                let model = session_info.get("model").and_then(|v| v.as_str()).unwrap_or("default");
-                let tokens = res.get("tokenUsage").cloned().unwrap_or_default();
-                let estimated = tokens.get("estimatedTotal").and_then(|v| v.as_u64()).unwrap_or(0);
-                let context_window = tokens.get("contextWindow").and_then(|v| v.as_u64()).unwrap_or(0);
-                Ok(format!(
-                    "Session: {session_key}\nMessages: {msg_count}\nModel: {model}\nTokens: ~{estimated}/{context_window}"
-                ))
-            },
-            _ => Err(anyhow!("unknown command: /{command}")),
+        self.commands.dispatch(command, state, session_key, reply_to).await
+    }
+
+    async fn relay_message(
+        &self,
+        source: ChannelReplyTarget,
+        sender_name: Option<&str>,
+        text: &str,
+        attachments: Vec<ChannelAttachment>,
+    ) {
+        let destinations = self
+            .bridges
+            .routes_for(&source.channel_type, &source.account_id, &source.chat_id);
+        if destinations.is_empty() {
+            return;
+        }
+
+        let Some(state) = self.state.get() else {
+            warn!("channel relay_message: gateway not ready");
+            return;
+        };
+        let Some(outbound) = state.services.channel_outbound_arc() else {
+            warn!("channel relay_message: no channel outbound configured");
+            return;
+        };
+
+        let mut prefixed = match sender_name {
+            Some(name) if !name.is_empty() => format!("{name}: {text}"),
+            _ => text.to_string(),
+        };
+        // Media relay needs a hosted URL (`ReplyPayload::media`), which we
+        // don't have for freshly-downloaded attachment bytes yet. Note what
+        // was dropped rather than silently discarding it.
+        if !attachments.is_empty() {
+            prefixed.push_str(&format!(
+                "\n[{} attachment(s) not relayed — media bridging not yet supported]",
+                attachments.len()
+            ));
+        }
+
+        for dest in destinations {
+            // Never bounce a message back to the chat it came from.
+            if dest.channel_type == source.channel_type
+                && dest.account_id == source.account_id
+                && dest.chat_id == source.chat_id
+            {
+                continue;
+            }
+
+            if let Err(e) = outbound.send_text(&dest.account_id, &dest.chat_id, &prefixed, None).await {
+                warn!(
+                    destination_channel = %dest.channel_type,
+                    destination_account = %dest.account_id,
+                    "bridge relay failed: {e}"
+                );
+            }
         }
     }
 }
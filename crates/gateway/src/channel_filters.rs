@@ -0,0 +1,133 @@
+//! Per-channel subscription filter state for the gateway's pub/sub channel
+//! bus — mute, keyword filters, and enabled/disabled — keyed by channel name
+//! rather than by connection, so it survives a client reconnect the way the
+//! in-memory `joined_channels` set on a websocket connection does not.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Filter state for one pub/sub channel, applied before a broadcast on that
+/// channel is dispatched to a joined client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelFilterState {
+    /// Whether the channel is currently enabled. `channel.leave` disables
+    /// rather than deletes the record, so a later `channel.join` restores
+    /// prior mute/keyword settings instead of starting over.
+    pub enabled: bool,
+    /// Suppress dispatch without actually leaving the channel.
+    #[serde(default)]
+    pub muted: bool,
+    /// Only dispatch messages containing at least one of these keywords
+    /// (case-insensitive). Empty means no keyword filtering.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl Default for ChannelFilterState {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            muted: false,
+            keywords: Vec::new(),
+        }
+    }
+}
+
+impl ChannelFilterState {
+    /// Whether a message on this channel should be dispatched, given the
+    /// current filter settings.
+    pub fn allows(&self, text: &str) -> bool {
+        if !self.enabled || self.muted {
+            return false;
+        }
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let lower = text.to_lowercase();
+        self.keywords.iter().any(|kw| lower.contains(&kw.to_lowercase()))
+    }
+}
+
+/// Durable storage for per-channel filter state, so `channel.join` and
+/// `channel.leave` settings survive both a client reconnect and a gateway
+/// restart. Mirrors the `credential_store` pattern used for env vars (see
+/// [`crate::env_routes`]).
+#[async_trait]
+pub trait ChannelFilterStore: Send + Sync {
+    /// List every channel with a persisted filter record.
+    async fn list_channel_filters(&self) -> anyhow::Result<Vec<(String, ChannelFilterState)>>;
+
+    /// Look up the persisted filter record for one channel, if any.
+    async fn get_channel_filter(&self, channel: &str) -> anyhow::Result<Option<ChannelFilterState>>;
+
+    /// Upsert the filter record for one channel.
+    async fn set_channel_filter(&self, channel: &str, state: &ChannelFilterState) -> anyhow::Result<()>;
+}
+
+/// Load a channel's persisted filter record, falling back to the default
+/// (enabled, unmuted, no keywords) when no store is configured or no record
+/// exists yet.
+pub async fn load_filter(store: Option<&dyn ChannelFilterStore>, channel: &str) -> ChannelFilterState {
+    let Some(store) = store else {
+        return ChannelFilterState::default();
+    };
+    store.get_channel_filter(channel).await.ok().flatten().unwrap_or_default()
+}
+
+/// Whether a message on `channel` should be dispatched, consulting the
+/// persisted filter record. Called by the broadcast layer before fanning a
+/// message out to clients that joined `channel`.
+pub async fn filter_allows(store: Option<&dyn ChannelFilterStore>, channel: &str, text: &str) -> bool {
+    load_filter(store, channel).await.allows(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_allows_everything() {
+        let state = ChannelFilterState::default();
+        assert!(state.allows("anything"));
+    }
+
+    #[test]
+    fn muted_blocks_dispatch() {
+        let state = ChannelFilterState {
+            muted: true,
+            ..Default::default()
+        };
+        assert!(!state.allows("hello"));
+    }
+
+    #[test]
+    fn disabled_blocks_dispatch() {
+        let state = ChannelFilterState {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(!state.allows("hello"));
+    }
+
+    #[test]
+    fn keyword_filter_is_case_insensitive() {
+        let state = ChannelFilterState {
+            keywords: vec!["urgent".into()],
+            ..Default::default()
+        };
+        assert!(state.allows("this is URGENT"));
+        assert!(!state.allows("nothing special"));
+    }
+
+    #[tokio::test]
+    async fn load_filter_without_store_is_default() {
+        let state = load_filter(None, "alerts").await;
+        assert!(state.enabled);
+        assert!(!state.muted);
+    }
+
+    #[tokio::test]
+    async fn filter_allows_without_store_defaults_to_true() {
+        assert!(filter_allows(None, "alerts", "anything").await);
+    }
+}
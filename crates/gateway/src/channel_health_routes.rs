@@ -0,0 +1,35 @@
+//! Push-based channel health streaming, alongside the config routes.
+//!
+//! Replaces clients polling `ChannelStatus::probe`'s 30s-stale cache with a
+//! server-sent-events stream of `ChannelHealthSnapshot` deltas, fired the
+//! instant a plugin's event handler sees one (see
+//! `WhatsAppPlugin::subscribe_health`) rather than on a timer.
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, Sse},
+};
+use futures_util::{Stream, StreamExt as _};
+use tokio_stream::wrappers::BroadcastStream;
+
+/// Stream every channel health snapshot as it's published, multiplexed
+/// across all channel plugins that support `subscribe_health`.
+pub async fn channel_health_stream(
+    State(state): State<crate::server::AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let rx = state.services.channel_health_subscribe();
+    let stream = BroadcastStream::new(rx).filter_map(|item| async move {
+        let snapshot = match item {
+            Ok(snapshot) => snapshot,
+            // A slow subscriber lagged behind and missed some snapshots —
+            // the next one it does receive is still the current state, so
+            // just keep going rather than closing the stream.
+            Err(_lagged) => return None,
+        };
+        let payload = serde_json::to_string(&snapshot).ok()?;
+        Some(Ok(Event::default().event("channel_health").data(payload)))
+    });
+    Sse::new(stream)
+}
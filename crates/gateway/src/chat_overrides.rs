@@ -0,0 +1,50 @@
+//! Per-chat runtime overrides for channel accounts — enabled/disabled and
+//! model — keyed by `(account_id, chat_id)` rather than by channel name, so
+//! an operator can flip a single group on/off or swap its model without
+//! editing config and restarting. Mirrors the `channel_filters` pattern
+//! used for pub/sub channel filters, and the `ChatOverrideStore` trait a
+//! channel crate consults directly (see `telegram::state::ChatOverrideStore`)
+//! — this gateway-side copy exists so the same records are inspectable over
+//! HTTP without the gateway depending on any specific channel crate.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Runtime override record for one chat. `None` fields defer to the
+/// account's static config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatOverrideState {
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Durable storage for per-chat runtime overrides, so the env/HTTP API can
+/// list and inspect the same overrides a channel's in-process admin
+/// commands write.
+#[async_trait]
+pub trait ChatOverrideStore: Send + Sync {
+    /// List every chat with a persisted override record for `account_id`.
+    async fn list_chat_overrides(&self, account_id: &str) -> anyhow::Result<Vec<(String, ChatOverrideState)>>;
+
+    /// Look up the persisted override record for one chat, if any.
+    async fn get_chat_override(&self, account_id: &str, chat_id: &str) -> anyhow::Result<Option<ChatOverrideState>>;
+}
+
+/// Load a chat's persisted overrides, falling back to the default (no
+/// overrides) when no store is configured or no record exists yet.
+pub async fn load_chat_override(
+    store: Option<&dyn ChatOverrideStore>,
+    account_id: &str,
+    chat_id: &str,
+) -> ChatOverrideState {
+    let Some(store) = store else {
+        return ChatOverrideState::default();
+    };
+    store
+        .get_chat_override(account_id, chat_id)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
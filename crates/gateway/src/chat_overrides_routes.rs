@@ -0,0 +1,93 @@
+//! HTTP routes exposing per-chat runtime overrides (see
+//! `crate::chat_overrides`) so operators can inspect which chats have been
+//! toggled on/off or switched to a different model from inside the chat,
+//! without grepping through a channel crate's own storage.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::chat_overrides::{ChatOverrideState, load_chat_override};
+
+const CHAT_OVERRIDE_STORE_UNAVAILABLE: &str = "CHAT_OVERRIDE_STORE_UNAVAILABLE";
+const CHAT_OVERRIDE_LIST_FAILED: &str = "CHAT_OVERRIDE_LIST_FAILED";
+
+/// JSON error with an HTTP status code.
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn service_unavailable(code: &'static str, msg: &str) -> Self {
+        Self { status: StatusCode::SERVICE_UNAVAILABLE, code, message: msg.into() }
+    }
+
+    fn internal(code: &'static str, err: impl std::fmt::Display) -> Self {
+        Self { status: StatusCode::INTERNAL_SERVER_ERROR, code, message: err.to_string() }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body {
+            code: &'static str,
+            error: String,
+        }
+        (self.status, Json(Body { code: self.code, error: self.message })).into_response()
+    }
+}
+
+/// Per-chat overrides listing response (`{"overrides": [...]}`).
+#[derive(Serialize)]
+pub struct ChatOverrideListResponse {
+    overrides: Vec<ChatOverrideEntry>,
+}
+
+#[derive(Serialize)]
+struct ChatOverrideEntry {
+    chat_id: String,
+    #[serde(flatten)]
+    state: ChatOverrideState,
+}
+
+impl IntoResponse for ChatOverrideListResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// List every chat override recorded for one account.
+pub async fn chat_overrides_list(
+    State(state): State<crate::server::AppState>,
+    Path(account_id): Path<String>,
+) -> Result<ChatOverrideListResponse, ApiError> {
+    let store = state.gateway.chat_override_store.as_ref().ok_or_else(|| {
+        ApiError::service_unavailable(CHAT_OVERRIDE_STORE_UNAVAILABLE, "no chat override store")
+    })?;
+
+    let overrides = store
+        .list_chat_overrides(&account_id)
+        .await
+        .map_err(|err| ApiError::internal(CHAT_OVERRIDE_LIST_FAILED, err))?
+        .into_iter()
+        .map(|(chat_id, state)| ChatOverrideEntry { chat_id, state })
+        .collect();
+
+    Ok(ChatOverrideListResponse { overrides })
+}
+
+/// Look up the override record for one chat, or the default if it has none.
+pub async fn chat_override_get(
+    State(state): State<crate::server::AppState>,
+    Path((account_id, chat_id)): Path<(String, String)>,
+) -> Result<Json<ChatOverrideState>, ApiError> {
+    let store = state.gateway.chat_override_store.as_deref();
+    Ok(Json(load_chat_override(store, &account_id, &chat_id).await))
+}
@@ -0,0 +1,209 @@
+//! Pluggable registry of channel slash-commands (`/new`, `/compact`, ...),
+//! mirroring `crate::methods::MethodRegistry`'s name → async-handler-closure
+//! shape so other modules can register additional commands instead of
+//! editing one hard-coded match in `dispatch_command`.
+
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use moltis_channels::ChannelReplyTarget;
+
+use crate::state::GatewayState;
+
+/// Everything a command handler needs: the session it's running against and
+/// the text typed after the command name.
+pub struct CommandCtx {
+    pub state: Arc<GatewayState>,
+    pub session_key: String,
+    pub reply_to: ChannelReplyTarget,
+    pub args: String,
+}
+
+pub type CommandHandler = Box<
+    dyn Fn(CommandCtx) -> Pin<Box<dyn Future<Output = anyhow::Result<String>> + Send>> + Send + Sync,
+>;
+
+/// Maps a command name (without the leading `/`) to its handler.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: HashMap<String, CommandHandler>,
+    /// Snapshot of registered names for `/help`, captured once all builtins
+    /// (and any caller-registered extras) are in place — see
+    /// [`CommandRegistry::finalize_help`].
+    help_names: Vec<String>,
+}
+
+impl CommandRegistry {
+    pub fn register(&mut self, name: &str, handler: CommandHandler) {
+        self.commands.insert(name.to_string(), handler);
+    }
+
+    /// Snapshot the currently-registered command names so `/help` can list
+    /// them. Call this once, after every `register` call a caller intends to
+    /// make, right before the registry starts serving traffic.
+    pub fn finalize_help(&mut self) {
+        let mut names: Vec<String> = self.commands.keys().cloned().collect();
+        names.sort();
+        self.help_names = names;
+    }
+
+    /// Split `"model gpt-4"` into `("model", "gpt-4")`, or `"new"` into
+    /// `("new", "")`.
+    fn split_command(command: &str) -> (&str, String) {
+        match command.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim().to_string()),
+            None => (command, String::new()),
+        }
+    }
+
+    pub async fn dispatch(
+        &self,
+        command: &str,
+        state: Arc<GatewayState>,
+        session_key: String,
+        reply_to: ChannelReplyTarget,
+    ) -> anyhow::Result<String> {
+        let (name, args) = Self::split_command(command);
+
+        if name == "help" {
+            return Ok(format!(
+                "Available commands: {}",
+                self.help_names.iter().map(|n| format!("/{n}")).collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        match self.commands.get(name) {
+            Some(handler) => handler(CommandCtx { state, session_key, reply_to, args }).await,
+            None => Err(anyhow::anyhow!("unknown command: /{name}")),
+        }
+    }
+}
+
+/// Register the built-in commands: `/new`, `/clear`, `/compact`, `/context`,
+/// `/models`, `/model <name>`, and `/arena <modelA> <modelB>`. `/help` is
+/// handled directly by [`CommandRegistry::dispatch`] rather than
+/// registered, since it needs the full name list rather than per-command
+/// state.
+pub fn register_builtins(
+    reg: &mut CommandRegistry,
+    session_models: Arc<tokio::sync::Mutex<HashMap<String, String>>>,
+    session_arenas: Arc<tokio::sync::Mutex<HashMap<String, (String, String)>>>,
+) {
+    reg.register(
+        "new",
+        Box::new(|ctx| {
+            Box::pin(async move {
+                let chat = ctx.state.chat().await;
+                chat.clear(serde_json::json!({ "_session_key": &ctx.session_key }))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+                Ok("New session started.".to_string())
+            })
+        }),
+    );
+
+    reg.register(
+        "clear",
+        Box::new(|ctx| {
+            Box::pin(async move {
+                let chat = ctx.state.chat().await;
+                chat.clear(serde_json::json!({ "_session_key": &ctx.session_key }))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+                Ok("Session cleared.".to_string())
+            })
+        }),
+    );
+
+    reg.register(
+        "compact",
+        Box::new(|ctx| {
+            Box::pin(async move {
+                let chat = ctx.state.chat().await;
+                chat.compact(serde_json::json!({ "_session_key": &ctx.session_key }))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+                Ok("Session compacted.".to_string())
+            })
+        }),
+    );
+
+    reg.register(
+        "context",
+        Box::new(|ctx| {
+            Box::pin(async move {
+                let chat = ctx.state.chat().await;
+                let res = chat
+                    .context(serde_json::json!({ "_session_key": &ctx.session_key }))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+                let session_info = res.get("session").cloned().unwrap_or_default();
+                let msg_count = session_info.get("messageCount").and_then(|v| v.as_u64()).unwrap_or(0);
+                let model = session_info.get("model").and_then(|v| v.as_str()).unwrap_or("default");
+                let tokens = res.get("tokenUsage").cloned().unwrap_or_default();
+                let estimated = tokens.get("estimatedTotal").and_then(|v| v.as_u64()).unwrap_or(0);
+                let context_window = tokens.get("contextWindow").and_then(|v| v.as_u64()).unwrap_or(0);
+                Ok(format!(
+                    "Session: {}\nMessages: {msg_count}\nModel: {model}\nTokens: ~{estimated}/{context_window}",
+                    ctx.session_key
+                ))
+            })
+        }),
+    );
+
+    reg.register(
+        "models",
+        Box::new(|ctx| {
+            Box::pin(async move {
+                let registry = ctx.state.services.provider_registry();
+                let registry = registry.read().await;
+                let ids: Vec<String> = registry.models().into_iter().map(|m| m.id).collect();
+                if ids.is_empty() {
+                    return Ok("No models are currently configured.".to_string());
+                }
+                Ok(format!("Available models:\n{}", ids.join("\n")))
+            })
+        }),
+    );
+
+    {
+        let session_models = session_models.clone();
+        reg.register(
+            "model",
+            Box::new(move |ctx| {
+                let session_models = session_models.clone();
+                Box::pin(async move {
+                    let name = ctx.args.trim();
+                    if name.is_empty() {
+                        return Err(anyhow::anyhow!("usage: /model <name>"));
+                    }
+                    session_models.lock().await.insert(ctx.session_key.clone(), name.to_string());
+                    Ok(format!("Model for this chat set to {name}."))
+                })
+            }),
+        );
+    }
+
+    {
+        let session_arenas = session_arenas.clone();
+        reg.register(
+            "arena",
+            Box::new(move |ctx| {
+                let session_arenas = session_arenas.clone();
+                Box::pin(async move {
+                    let mut parts = ctx.args.split_whitespace();
+                    let (Some(model_a), Some(model_b)) = (parts.next(), parts.next()) else {
+                        return Err(anyhow::anyhow!("usage: /arena <modelA> <modelB>"));
+                    };
+                    let (model_a, model_b) = (model_a.to_string(), model_b.to_string());
+                    session_arenas
+                        .lock()
+                        .await
+                        .insert(ctx.session_key.clone(), (model_a.clone(), model_b.clone()));
+                    Ok(format!(
+                        "Arena mode on for this chat: {model_a} vs {model_b}. Your next messages go to both."
+                    ))
+                })
+            }),
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Transparent encryption-at-rest for the env var credential store.
+//!
+//! `env_set`/`env_list` (see `crate::env_routes`) previously wrote plaintext
+//! values straight to whatever `CredentialStore` backs them (see
+//! `crate::auth`). Wrapping that store in [`EncryptedCredentialStore`] seals
+//! every value with AES-256-GCM under a passphrase-derived key before it
+//! reaches the inner store, and opens it again on the way out — so
+//! `list_env_vars` keeps returning names-only summaries and a stolen
+//! database file never exposes raw secrets. Mirrors the `EncryptedBackend`
+//! decorator that WhatsApp's session store can be opened with (see
+//! `whatsapp::kv_backend::EncryptedBackend`), adapted to wrap a
+//! `CredentialStore` instead of a raw `KvBackend`.
+
+use std::{fmt::Write as _, sync::Arc};
+
+use tracing::info;
+
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use async_trait::async_trait;
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+
+use crate::auth::{CredentialStore, EnvVarEntry};
+
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const PBKDF2_ROUNDS: u32 = 600_000;
+const SALT_KEY: &str = "__encryption_salt";
+const SENTINEL_KEY: &str = "__encryption_sentinel";
+const SENTINEL_PLAINTEXT: &[u8] = b"moltis-credential-store-sentinel";
+/// Keys in this double-underscore namespace are this module's own
+/// bookkeeping (salt, sentinel), stored through `inner` alongside real
+/// entries since `CredentialStore` has no separate keyspace to isolate
+/// them in. [`EncryptedCredentialStore::list_env_vars`] filters them back
+/// out so they never masquerade as user-set env vars.
+const RESERVED_KEY_PREFIX: &str = "__";
+
+/// Environment variable carrying the credential-store encryption
+/// passphrase. Encryption-at-rest is opt-in: [`open_with_env_passphrase`]
+/// wraps a plaintext `CredentialStore` in [`EncryptedCredentialStore`] only
+/// when this is set.
+pub const PASSPHRASE_ENV_VAR: &str = "MOLTIS_CREDENTIAL_PASSPHRASE";
+
+/// Hex-encode bytes without pulling in the `hex` crate.
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
+
+/// Inverse of [`hex_encode`].
+fn hex_decode(s: &str) -> anyhow::Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        anyhow::bail!("hex string has odd length");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| anyhow::anyhow!("invalid hex byte: {e}")))
+        .collect()
+}
+
+/// Derive a 32-byte AES-256 key from `passphrase` and `salt` via
+/// PBKDF2-HMAC-SHA256.
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+/// Encrypt `plaintext` under `key` with a fresh random 12-byte nonce,
+/// returning `nonce || ciphertext` (the nonce is public and need not be
+/// secret).
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow::anyhow!("failed to encrypt credential value"))?;
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Inverse of [`seal`]. A failure here (truncated blob, bad tag) means
+/// either corruption or — when checking the sentinel — a wrong passphrase.
+fn open_sealed(key: &[u8; 32], sealed: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        anyhow::bail!("encrypted credential value too short");
+    }
+    let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(key.into());
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| anyhow::anyhow!("wrong passphrase or corrupted credential value"))
+}
+
+/// Wraps any [`CredentialStore`] so every value is sealed before reaching
+/// it and opened again before being returned. Key names/ids pass through
+/// unchanged, so `list_env_vars`'s names-only summaries need no decryption
+/// at all.
+pub struct EncryptedCredentialStore<S: CredentialStore> {
+    inner: S,
+    key: Arc<[u8; 32]>,
+}
+
+impl<S: CredentialStore> EncryptedCredentialStore<S> {
+    /// Wrap `inner`, deriving the encryption key from `passphrase` via
+    /// PBKDF2-HMAC-SHA256 with a random salt generated and stored (as a
+    /// reserved entry in the inner store) the first time this runs, or read
+    /// back from it on reuse. Verifies `passphrase` against a sealed
+    /// sentinel value stored the same way, so a wrong passphrase fails here
+    /// with a clear error instead of surfacing as a confusing decrypt
+    /// failure the first time a real value is read.
+    pub async fn open(inner: S, passphrase: &str) -> anyhow::Result<Self> {
+        let salt = match inner.get_env_var(SALT_KEY).await? {
+            Some(existing) => hex_decode(&existing)?,
+            None => {
+                let mut salt = vec![0u8; SALT_LEN];
+                rand::RngCore::fill_bytes(&mut rand::rng(), &mut salt);
+                inner.set_env_var(SALT_KEY, &hex_encode(&salt)).await?;
+                salt
+            },
+        };
+
+        let key = derive_key(passphrase, &salt);
+
+        match inner.get_env_var(SENTINEL_KEY).await? {
+            Some(sealed_hex) => {
+                let sealed = hex_decode(&sealed_hex)?;
+                if open_sealed(&key, &sealed)? != SENTINEL_PLAINTEXT {
+                    anyhow::bail!("wrong passphrase for credential store");
+                }
+            },
+            None => {
+                let sealed = seal(&key, SENTINEL_PLAINTEXT)?;
+                inner.set_env_var(SENTINEL_KEY, &hex_encode(&sealed)).await?;
+            },
+        }
+
+        Ok(Self { inner, key: Arc::new(key) })
+    }
+}
+
+/// Wrap `inner` in [`EncryptedCredentialStore`] if [`PASSPHRASE_ENV_VAR`] is
+/// set in the environment, otherwise hand `inner` back unwrapped, so
+/// encryption-at-rest only kicks in once an operator has opted in by
+/// setting a passphrase — without it, env vars are stored as plaintext.
+/// Nothing in this module installs the result as `GatewayState.gateway`'s
+/// `credential_store`; the caller that builds that store does so.
+pub async fn open_with_env_passphrase<S>(inner: S) -> anyhow::Result<Arc<dyn CredentialStore>>
+where
+    S: CredentialStore + Send + Sync + 'static,
+{
+    match std::env::var(PASSPHRASE_ENV_VAR) {
+        Ok(passphrase) if !passphrase.is_empty() => {
+            info!("credential store encryption-at-rest enabled via {PASSPHRASE_ENV_VAR}");
+            Ok(Arc::new(EncryptedCredentialStore::open(inner, &passphrase).await?))
+        },
+        _ => Ok(Arc::new(inner)),
+    }
+}
+
+#[async_trait]
+impl<S: CredentialStore> CredentialStore for EncryptedCredentialStore<S> {
+    async fn list_env_vars(&self) -> anyhow::Result<Vec<EnvVarEntry>> {
+        // Names-only; nothing to decrypt. Strip the reserved salt/sentinel
+        // entries this module writes through `inner` directly — they live
+        // in the same flat keyspace `inner` serves everything else from,
+        // so they'd otherwise show up as fake user env vars here (and in
+        // `env_export`, where `get_env_var` would then fail trying to
+        // `open_sealed` the raw salt bytes as if they were a sealed
+        // value).
+        let entries = self.inner.list_env_vars().await?;
+        Ok(entries.into_iter().filter(|e| !e.key.starts_with(RESERVED_KEY_PREFIX)).collect())
+    }
+
+    async fn get_env_var(&self, key: &str) -> anyhow::Result<Option<String>> {
+        match self.inner.get_env_var(key).await? {
+            Some(sealed_hex) => {
+                let sealed = hex_decode(&sealed_hex)?;
+                let plaintext = open_sealed(&self.key, &sealed)?;
+                Ok(Some(String::from_utf8(plaintext)?))
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn set_env_var(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        let sealed = seal(&self.key, value.as_bytes())?;
+        self.inner.set_env_var(key, &hex_encode(&sealed)).await
+    }
+
+    async fn delete_env_var(&self, id: i64) -> anyhow::Result<()> {
+        self.inner.delete_env_var(id).await
+    }
+}
@@ -1,11 +1,11 @@
 use {
     axum::{
         Json,
-        extract::{Path, State},
-        http::StatusCode,
+        extract::{Path, Query, State},
+        http::{StatusCode, header},
         response::{IntoResponse, Response},
     },
-    serde::Serialize,
+    serde::{Deserialize, Serialize},
 };
 
 use crate::auth::EnvVarEntry;
@@ -18,6 +18,8 @@ const ENV_KEY_INVALID: &str = "ENV_KEY_INVALID";
 const ENV_LIST_FAILED: &str = "ENV_LIST_FAILED";
 const ENV_SET_FAILED: &str = "ENV_SET_FAILED";
 const ENV_DELETE_FAILED: &str = "ENV_DELETE_FAILED";
+const ENV_IMPORT_PARSE_FAILED: &str = "ENV_IMPORT_PARSE_FAILED";
+const ENV_EXPORT_FAILED: &str = "ENV_EXPORT_FAILED";
 
 /// Successful mutation response (`{"ok": true}`).
 #[derive(Serialize)]
@@ -141,8 +143,7 @@ pub async fn env_set(
         return Err(ApiError::bad_request(ENV_KEY_REQUIRED, "key is required"));
     }
 
-    // Validate key format: letters, digits, underscores.
-    if !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+    if !is_valid_env_key(key) {
         return Err(ApiError::bad_request(
             ENV_KEY_INVALID,
             "key must contain only letters, digits, and underscores",
@@ -174,6 +175,138 @@ pub async fn env_delete(
     Ok(OkResponse::success())
 }
 
+/// Key format shared by `env_set` and `env_import`: letters, digits, and
+/// underscores only.
+fn is_valid_env_key(key: &str) -> bool {
+    key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse a `.env`-format body into validated `(key, value)` pairs, skipping
+/// blank lines and `#`-led comments. A double-quoted value has its quotes
+/// stripped. Collects every malformed or invalid line rather than stopping
+/// at the first, so the caller can report them all at once.
+fn parse_dotenv(body: &str) -> Result<Vec<(String, String)>, ApiError> {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, line) in body.lines().enumerate() {
+        let line_no = idx + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once('=') else {
+            errors.push(format!("line {line_no}: expected KEY=VALUE"));
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim().trim_matches('"');
+
+        if key.is_empty() || !is_valid_env_key(key) {
+            errors.push(format!("line {line_no}: invalid key '{key}'"));
+            continue;
+        }
+
+        entries.push((key.to_string(), value.to_string()));
+    }
+
+    if !errors.is_empty() {
+        return Err(ApiError::bad_request(ENV_IMPORT_PARSE_FAILED, &errors.join("; ")));
+    }
+    Ok(entries)
+}
+
+/// Bulk-import a `.env`-format body, upserting every entry.
+///
+/// The whole body is parsed and validated before anything is written, so a
+/// malformed line fails the import with nothing changed; once parsing
+/// succeeds, entries are upserted one at a time since the underlying
+/// `CredentialStore` has no multi-key batch primitive to commit them as a
+/// single transaction.
+pub async fn env_import(
+    State(state): State<crate::server::AppState>,
+    body: String,
+) -> Result<OkResponse, ApiError> {
+    let store = state.gateway.credential_store.as_ref().ok_or_else(|| {
+        ApiError::service_unavailable(ENV_STORE_UNAVAILABLE, "no credential store")
+    })?;
+
+    let entries = parse_dotenv(&body)?;
+    for (key, value) in &entries {
+        store
+            .set_env_var(key, value)
+            .await
+            .map_err(|err| ApiError::internal(ENV_SET_FAILED, err))?;
+    }
+
+    Ok(OkResponse::success())
+}
+
+#[derive(Deserialize)]
+pub struct EnvExportParams {
+    /// Emit real values instead of a redacted placeholder. Defaults to
+    /// `false` so a casual export doesn't leak secrets into logs/clipboards.
+    #[serde(default)]
+    reveal: bool,
+}
+
+/// `.env`-format export response, served as `text/plain` rather than JSON
+/// since it's meant to be saved straight to a `.env` file.
+pub struct EnvExportResponse(String);
+
+impl IntoResponse for EnvExportResponse {
+    fn into_response(self) -> Response {
+        ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], self.0).into_response()
+    }
+}
+
+/// Quote a `.env` value if it contains anything that would otherwise change
+/// how the line parses back (whitespace, `#`, or a literal quote).
+fn escape_dotenv_value(value: &str) -> String {
+    if value.is_empty() || value.chars().any(|c| c.is_whitespace() || c == '#' || c == '"') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Re-emit the current env var set in `.env` syntax, for backup or
+/// migration. Values are redacted as `"***"` unless `?reveal=true` is
+/// given.
+pub async fn env_export(
+    State(state): State<crate::server::AppState>,
+    Query(params): Query<EnvExportParams>,
+) -> Result<EnvExportResponse, ApiError> {
+    let store = state.gateway.credential_store.as_ref().ok_or_else(|| {
+        ApiError::service_unavailable(ENV_STORE_UNAVAILABLE, "no credential store")
+    })?;
+
+    let entries = store
+        .list_env_vars()
+        .await
+        .map_err(|err| ApiError::internal(ENV_EXPORT_FAILED, err))?;
+
+    let mut out = String::new();
+    for entry in entries {
+        let value = if params.reveal {
+            store
+                .get_env_var(&entry.key)
+                .await
+                .map_err(|err| ApiError::internal(ENV_EXPORT_FAILED, err))?
+                .unwrap_or_default()
+        } else {
+            "***".to_string()
+        };
+        out.push_str(&entry.key);
+        out.push('=');
+        out.push_str(&escape_dotenv_value(&value));
+        out.push('\n');
+    }
+
+    Ok(EnvExportResponse(out))
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, axum::body::to_bytes};
@@ -206,4 +339,32 @@ mod tests {
         assert_eq!(json["code"], ENV_SET_FAILED);
         assert_eq!(json["error"], "boom");
     }
+
+    #[test]
+    fn parse_dotenv_skips_blanks_and_comments() {
+        let entries = parse_dotenv("# a comment\n\nFOO=bar\nBAZ=\"quoted value\"\n").unwrap();
+        assert_eq!(entries, vec![
+            ("FOO".to_string(), "bar".to_string()),
+            ("BAZ".to_string(), "quoted value".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn parse_dotenv_rejects_invalid_key() {
+        let err = parse_dotenv("FOO-BAR=baz").unwrap_err();
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn parse_dotenv_rejects_missing_equals() {
+        assert!(parse_dotenv("not a valid line").is_err());
+    }
+
+    #[test]
+    fn escape_dotenv_value_quotes_when_needed() {
+        assert_eq!(escape_dotenv_value("simple"), "simple");
+        assert_eq!(escape_dotenv_value("has space"), "\"has space\"");
+        assert_eq!(escape_dotenv_value(""), "\"\"");
+    }
 }
@@ -1,9 +1,62 @@
 use moltis_protocol::{ErrorShape, error_codes};
 
 use super::MethodRegistry;
+use crate::channel_filters::ChannelFilterState;
+
+/// Merge `channel.join`'s optional `mute`/`keywords` overrides onto a
+/// channel's existing filter record (or the default, if it has none yet),
+/// then persist the result if a filter store is configured. Returns the
+/// record that ends up in effect, so the caller can echo it back.
+async fn upsert_joined_filter(
+    ctx: &super::MethodCtx,
+    channel: &str,
+    mute: Option<bool>,
+    keywords: Option<&Vec<String>>,
+) -> ChannelFilterState {
+    let store = ctx.state.gateway.channel_filter_store.as_deref();
+    let mut filter = crate::channel_filters::load_filter(store, channel).await;
+    filter.enabled = true;
+    if let Some(mute) = mute {
+        filter.muted = mute;
+    }
+    if let Some(keywords) = keywords {
+        filter.keywords = keywords.clone();
+    }
+    if let Some(store) = store {
+        if let Err(e) = store.set_channel_filter(channel, &filter).await {
+            tracing::warn!(channel, "failed to persist channel filter: {e}");
+        }
+    }
+    filter
+}
+
+/// Disable a channel's persisted filter record on `channel.leave`, keeping
+/// its mute/keyword settings intact so a later `channel.join` restores them
+/// instead of starting over. Returns the record that ends up in effect.
+async fn disable_left_filter(ctx: &super::MethodCtx, channel: &str) -> ChannelFilterState {
+    let store = ctx.state.gateway.channel_filter_store.as_deref();
+    let mut filter = crate::channel_filters::load_filter(store, channel).await;
+    filter.enabled = false;
+    if let Some(store) = store {
+        if let Err(e) = store.set_channel_filter(channel, &filter).await {
+            tracing::warn!(channel, "failed to persist channel filter: {e}");
+        }
+    }
+    filter
+}
+
+fn filter_json(state: &ChannelFilterState) -> serde_json::Value {
+    serde_json::json!({
+        "enabled": state.enabled,
+        "muted": state.muted,
+        "keywords": state.keywords,
+    })
+}
 
 pub(super) fn register(reg: &mut MethodRegistry) {
-    // channel.join: add channels to client's joined set
+    // channel.join: add channels to client's joined set, and upsert their
+    // persisted filter record (mute/keywords/enabled) so it survives a
+    // reconnect.
     reg.register(
         "channel.join",
         Box::new(|ctx| {
@@ -18,20 +71,35 @@ pub(super) fn register(reg: &mut MethodRegistry) {
                             "missing or invalid 'channels' array",
                         )
                     })?;
+                let mute: Option<bool> = ctx.params.get("mute").and_then(|v| v.as_bool());
+                let keywords: Option<Vec<String>> = ctx
+                    .params
+                    .get("keywords")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok());
 
-                let mut inner = ctx.state.inner.write().await;
-                if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id) {
-                    for ch in &channels {
-                        client.joined_channels.insert(ch.clone());
+                {
+                    let mut inner = ctx.state.inner.write().await;
+                    if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id) {
+                        for ch in &channels {
+                            client.joined_channels.insert(ch.clone());
+                        }
                     }
                 }
 
-                Ok(serde_json::json!({ "joined": channels }))
+                let mut filters = serde_json::Map::new();
+                for ch in &channels {
+                    let filter = upsert_joined_filter(&ctx, ch, mute, keywords.as_ref()).await;
+                    filters.insert(ch.clone(), filter_json(&filter));
+                }
+
+                Ok(serde_json::json!({ "joined": channels, "filters": filters }))
             })
         }),
     );
 
-    // channel.leave: remove channels from client's joined set
+    // channel.leave: remove channels from client's joined set, and disable
+    // (not delete) their persisted filter record so settings like mute or
+    // keywords survive for a later rejoin.
     reg.register(
         "channel.leave",
         Box::new(|ctx| {
@@ -47,14 +115,54 @@ pub(super) fn register(reg: &mut MethodRegistry) {
                         )
                     })?;
 
-                let mut inner = ctx.state.inner.write().await;
-                if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id) {
-                    for ch in &channels {
-                        client.joined_channels.remove(ch);
+                {
+                    let mut inner = ctx.state.inner.write().await;
+                    if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id) {
+                        for ch in &channels {
+                            client.joined_channels.remove(ch);
+                        }
                     }
                 }
 
-                Ok(serde_json::json!({ "left": channels }))
+                let mut filters = serde_json::Map::new();
+                for ch in &channels {
+                    let filter = disable_left_filter(&ctx, ch).await;
+                    filters.insert(ch.clone(), filter_json(&filter));
+                }
+
+                Ok(serde_json::json!({ "left": channels, "filters": filters }))
+            })
+        }),
+    );
+
+    // channel.list: every channel this connection has joined, alongside its
+    // current persisted filter state.
+    reg.register(
+        "channel.list",
+        Box::new(|ctx| {
+            Box::pin(async move {
+                let joined: Vec<String> = {
+                    let inner = ctx.state.inner.read().await;
+                    inner
+                        .clients
+                        .get(&ctx.client_conn_id)
+                        .map(|client| client.joined_channels.iter().cloned().collect())
+                        .unwrap_or_default()
+                };
+
+                let store = ctx.state.gateway.channel_filter_store.as_deref();
+                let mut channels = Vec::with_capacity(joined.len());
+                for ch in &joined {
+                    let filter = crate::channel_filters::load_filter(store, ch).await;
+                    channels.push(serde_json::json!({
+                        "channel": ch,
+                        "enabled": filter.enabled,
+                        "muted": filter.muted,
+                        "keywords": filter.keywords,
+                    }));
+                }
+
+                Ok(serde_json::json!({ "channels": channels }))
             })
         }),
     );
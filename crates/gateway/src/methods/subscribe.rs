@@ -1,9 +1,67 @@
+use std::collections::{HashMap, HashSet};
+
 use moltis_protocol::{ErrorShape, error_codes};
 
 use super::MethodRegistry;
 
+/// One `subscribe` call's durable handle: the event names it covers, plus
+/// an optional server-side filter narrowing which instances of those events
+/// are delivered (see [`filter_matches`]). Keyed in `client.subscriptions`
+/// by the `subscriptionId` returned from `subscribe`, so `unsubscribe` can
+/// cancel exactly this call without touching any sibling subscription the
+/// same client holds — mirrors the durable-handle model of
+/// `eth_subscribe`-style pubsub rather than flattening everything into one
+/// set.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionSpec {
+    pub events: HashSet<String>,
+    pub filter: Option<serde_json::Value>,
+}
+
+/// Is this client subscribed to `event_name` under *any* of its specs?
+pub fn is_subscribed(subs: &HashMap<String, SubscriptionSpec>, event_name: &str) -> bool {
+    subs.values().any(|spec| spec.events.contains(event_name))
+}
+
+/// Look up a dotted path (`"channel.account_id"`) in a serialized event
+/// payload.
+fn payload_path<'a>(payload: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(payload, |v, segment| v.get(segment))
+}
+
+/// Does `filter` match `payload`? Every key in `filter` must equal the
+/// value at the same (possibly dotted) path in `payload`; a key the payload
+/// doesn't have matches everything, so a filter can only narrow, never
+/// reject on absence. A non-object filter always matches.
+pub fn filter_matches(filter: &serde_json::Value, payload: &serde_json::Value) -> bool {
+    let Some(obj) = filter.as_object() else {
+        return true;
+    };
+    obj.iter().all(|(key, expected)| match payload_path(payload, key) {
+        Some(actual) => actual == expected,
+        None => true,
+    })
+}
+
+/// Should this client receive `event_name` carrying `payload`? True if any
+/// spec covers the event name and either has no filter or its filter
+/// matches the payload. This is what the broadcast path
+/// (`crate::broadcast::broadcast`) consults per-client before sending a
+/// frame.
+pub fn client_wants_event(
+    subs: &HashMap<String, SubscriptionSpec>,
+    event_name: &str,
+    payload: &serde_json::Value,
+) -> bool {
+    subs.values().any(|spec| {
+        spec.events.contains(event_name)
+            && spec.filter.as_ref().is_none_or(|f| filter_matches(f, payload))
+    })
+}
+
 pub(super) fn register(reg: &mut MethodRegistry) {
-    // subscribe: add events to client's subscription set
+    // subscribe: register a new subscription spec, keyed by a fresh
+    // subscriptionId, on the client's subscription map.
     reg.register(
         "subscribe",
         Box::new(|ctx| {
@@ -19,14 +77,16 @@ pub(super) fn register(reg: &mut MethodRegistry) {
                         )
                     })?;
 
+                let filter = ctx.params.get("filter").cloned();
                 let subscription_id = uuid::Uuid::new_v4().to_string();
 
                 let mut inner = ctx.state.inner.write().await;
                 if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id) {
-                    let subs = client.subscriptions.get_or_insert_with(Default::default);
-                    for event in &events {
-                        subs.insert(event.clone());
-                    }
+                    let subs = client.subscriptions.get_or_insert_with(HashMap::new);
+                    subs.insert(
+                        subscription_id.clone(),
+                        SubscriptionSpec { events: events.iter().cloned().collect(), filter },
+                    );
                 }
 
                 Ok(serde_json::json!({
@@ -37,7 +97,9 @@ pub(super) fn register(reg: &mut MethodRegistry) {
         }),
     );
 
-    // unsubscribe: remove events from client's subscription set
+    // unsubscribe: by subscriptionId, remove exactly that spec; by events,
+    // strip those event names from every matching spec (dropping any spec
+    // left with no events).
     reg.register(
         "unsubscribe",
         Box::new(|ctx| {
@@ -61,19 +123,17 @@ pub(super) fn register(reg: &mut MethodRegistry) {
                 }
 
                 let mut inner = ctx.state.inner.write().await;
-                if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id) {
-                    if let Some(ref events) = events
-                        && let Some(ref mut subs) = client.subscriptions
-                    {
-                        for event in events {
-                            subs.remove(event);
-                        }
+                if let Some(client) = inner.clients.get_mut(&ctx.client_conn_id)
+                    && let Some(ref mut subs) = client.subscriptions
+                {
+                    if let Some(ref sid) = subscription_id {
+                        subs.remove(sid);
                     }
-                    // If subscriptionId is provided, clear all subscriptions
-                    // (each subscribe call returns a unique ID — unsubscribing by ID
-                    // resets to empty set).
-                    if subscription_id.is_some() && events.is_none() {
-                        client.subscriptions = Some(Default::default());
+                    if let Some(ref events) = events {
+                        for spec in subs.values_mut() {
+                            spec.events.retain(|e| !events.contains(e));
+                        }
+                        subs.retain(|_, spec| !spec.events.is_empty());
                     }
                 }
 
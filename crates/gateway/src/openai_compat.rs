@@ -0,0 +1,173 @@
+//! OpenAI-compatible HTTP API over the provider registry.
+//!
+//! Lets any OpenAI SDK client point at moltis and reuse its centralized
+//! key/OAuth management: `GET /v1/models` lists every model the registry
+//! can currently route to, and `POST /v1/chat/completions` dispatches a
+//! standard `{model, messages, stream}` body to the right provider,
+//! returning either a single JSON completion or an SSE stream of
+//! `data: {…}` chunks terminated by `data: [DONE]`.
+
+use std::convert::Infallible;
+
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{
+        IntoResponse,
+        sse::{Event, Sse},
+    },
+};
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+
+use moltis_agents::providers::{ChatMessage, ProviderError};
+
+/// `GET /v1/models` response, shaped like OpenAI's `/v1/models`.
+#[derive(Serialize)]
+struct ModelsResponse {
+    object: &'static str,
+    data: Vec<ModelObject>,
+}
+
+#[derive(Serialize)]
+struct ModelObject {
+    id: String,
+    object: &'static str,
+    owned_by: String,
+}
+
+/// List every model the provider registry can currently route to — i.e.
+/// every model belonging to a provider with a configured API key or OAuth
+/// token, straight from the live `ProviderRegistry` rather than a static
+/// table, so it always reflects what `chat_completions` can actually serve.
+pub async fn list_models(State(state): State<crate::server::AppState>) -> impl IntoResponse {
+    let registry = state.services.provider_registry();
+    let registry = registry.read().await;
+    let data = registry
+        .models()
+        .into_iter()
+        .map(|m| ModelObject {
+            id: m.id,
+            object: "model",
+            owned_by: m.provider,
+        })
+        .collect();
+    Json(ModelsResponse {
+        object: "list",
+        data,
+    })
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<ChatMessageDto>,
+    #[serde(default)]
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ChatMessageDto {
+    role: String,
+    content: String,
+}
+
+impl From<ChatMessageDto> for ChatMessage {
+    fn from(m: ChatMessageDto) -> Self {
+        ChatMessage {
+            role: m.role,
+            content: m.content,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChatCompletionResponse {
+    id: String,
+    object: &'static str,
+    model: String,
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Serialize)]
+struct ChatChoice {
+    index: u32,
+    message: ChatChoiceMessage,
+    finish_reason: &'static str,
+}
+
+#[derive(Serialize)]
+struct ChatChoiceMessage {
+    role: &'static str,
+    content: String,
+}
+
+/// `POST /v1/chat/completions` — dispatches to whichever provider owns
+/// `model`, returning a single JSON completion or, when `stream: true`, an
+/// SSE stream of incremental deltas terminated by `data: [DONE]`.
+pub async fn chat_completions(
+    State(state): State<crate::server::AppState>,
+    Json(body): Json<ChatCompletionRequest>,
+) -> axum::response::Response {
+    let registry = state.services.provider_registry();
+    let model = body.model.clone();
+    let messages: Vec<ChatMessage> = body.messages.into_iter().map(Into::into).collect();
+
+    if body.stream {
+        let reg = registry.read().await;
+        let provider_stream = match reg.chat_completion_stream(&model, messages).await {
+            Ok(s) => s,
+            Err(e) => return provider_error_response(e),
+        };
+        drop(reg);
+
+        let completion_id = uuid::Uuid::new_v4().to_string();
+        let sse_model = model.clone();
+        let chunks = provider_stream.map(move |delta| {
+            let payload = match delta {
+                Ok(delta) => serde_json::json!({
+                    "id": completion_id,
+                    "object": "chat.completion.chunk",
+                    "model": sse_model,
+                    "choices": [{
+                        "index": 0,
+                        "delta": { "content": delta.content },
+                        "finish_reason": delta.finish_reason,
+                    }],
+                }),
+                Err(e) => serde_json::json!({ "error": e.to_string() }),
+            };
+            Ok::<_, Infallible>(Event::default().data(payload.to_string()))
+        });
+        let done = futures_util::stream::once(async { Ok::<_, Infallible>(Event::default().data("[DONE]")) });
+        Sse::new(chunks.chain(done)).into_response()
+    } else {
+        let reg = registry.read().await;
+        match reg.chat_completion(&model, messages).await {
+            Ok(completion) => Json(ChatCompletionResponse {
+                id: uuid::Uuid::new_v4().to_string(),
+                object: "chat.completion",
+                model,
+                choices: vec![ChatChoice {
+                    index: 0,
+                    message: ChatChoiceMessage {
+                        role: "assistant",
+                        content: completion.content,
+                    },
+                    finish_reason: "stop",
+                }],
+            })
+            .into_response(),
+            Err(e) => provider_error_response(e),
+        }
+    }
+}
+
+fn provider_error_response(e: ProviderError) -> axum::response::Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(serde_json::json!({ "error": e.to_string() })),
+    )
+        .into_response()
+}
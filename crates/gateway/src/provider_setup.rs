@@ -1,4 +1,8 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use {async_trait::async_trait, serde_json::Value, tokio::sync::RwLock, tracing::info};
 
@@ -10,7 +14,32 @@ use {
 
 use crate::services::{ProviderSetupService, ServiceResult};
 
-/// Known provider definitions used to populate the "available providers" list.
+/// OOB redirect URI, mirroring Mastodon's `urn:ietf:wg:oauth:2.0:oob`
+/// convention: the provider shows the user a code instead of redirecting
+/// to a localhost callback, so it works when the browser completing the
+/// flow is on a different machine than the moltis server.
+const OOB_REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+/// How long a manual OAuth session's PKCE verifier is kept around waiting
+/// for `oauth_complete` before it's considered stale and rejected.
+const PENDING_SESSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// State held between a manual `oauth_start` and the matching
+/// `oauth_complete` call: the PKCE verifier and expected `state` needed to
+/// exchange the code, keyed by a short-lived session id.
+struct PendingOAuthSession {
+    provider_name: String,
+    flow: OAuthFlow,
+    verifier: String,
+    expected_state: String,
+    created_at: Instant,
+}
+
+/// Known provider definitions used to populate the "available providers"
+/// list. Mostly model providers, but also covers other integrations that
+/// want the same "save a key" / "do an OAuth dance, persist the token"
+/// machinery — e.g. `mastodon`, whose token backs `FediverseOutbound`
+/// rather than any chat model.
 struct KnownProvider {
     name: &'static str,
     display_name: &'static str,
@@ -61,12 +90,19 @@ const KNOWN_PROVIDERS: &[KnownProvider] = &[
         auth_type: "oauth",
         env_key: None,
     },
+    KnownProvider {
+        name: "mastodon",
+        display_name: "Mastodon",
+        auth_type: "oauth",
+        env_key: None,
+    },
 ];
 
 pub struct LiveProviderSetupService {
     registry: Arc<RwLock<ProviderRegistry>>,
     config: ProvidersConfig,
     token_store: TokenStore,
+    pending_oauth: Arc<RwLock<HashMap<String, PendingOAuthSession>>>,
 }
 
 impl LiveProviderSetupService {
@@ -75,6 +111,7 @@ impl LiveProviderSetupService {
             registry,
             config,
             token_store: TokenStore::new(),
+            pending_oauth: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -96,6 +133,13 @@ impl LiveProviderSetupService {
         }
         false
     }
+
+    /// Drop pending manual OAuth sessions that have outlived
+    /// `PENDING_SESSION_TTL` without an `oauth_complete` call.
+    async fn prune_expired_oauth_sessions(&self) {
+        let mut pending = self.pending_oauth.write().await;
+        pending.retain(|_, session| session.created_at.elapsed() < PENDING_SESSION_TTL);
+    }
 }
 
 #[async_trait]
@@ -161,6 +205,9 @@ impl ProviderSetupService for LiveProviderSetupService {
         let oauth_config = load_oauth_config(&provider_name)
             .ok_or_else(|| format!("no OAuth config for provider: {provider_name}"))?;
 
+        let manual_mode = params.get("mode").and_then(|v| v.as_str()) == Some("manual")
+            || oauth_config.redirect_uri == OOB_REDIRECT_URI;
+
         let port = callback_port(&oauth_config);
         let flow = OAuthFlow::new(oauth_config);
         let auth_req = flow.start();
@@ -169,6 +216,26 @@ impl ProviderSetupService for LiveProviderSetupService {
         let verifier = auth_req.pkce.verifier.clone();
         let expected_state = auth_req.state.clone();
 
+        if manual_mode {
+            self.prune_expired_oauth_sessions().await;
+            let session_id = uuid::Uuid::new_v4().to_string();
+            self.pending_oauth.write().await.insert(
+                session_id.clone(),
+                PendingOAuthSession {
+                    provider_name,
+                    flow,
+                    verifier,
+                    expected_state,
+                    created_at: Instant::now(),
+                },
+            );
+            return Ok(serde_json::json!({
+                "authUrl": auth_url,
+                "sessionId": session_id,
+                "mode": "manual",
+            }));
+        }
+
         // Spawn background task to wait for the callback and exchange the code
         let token_store = self.token_store.clone();
         let registry = Arc::clone(&self.registry);
@@ -231,6 +298,62 @@ impl ProviderSetupService for LiveProviderSetupService {
             "authenticated": has_tokens,
         }))
     }
+
+    async fn oauth_complete(&self, params: Value) -> ServiceResult {
+        let provider_name = params
+            .get("provider")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "missing 'provider' parameter".to_string())?;
+        let session_id = params
+            .get("sessionId")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "missing 'sessionId' parameter".to_string())?;
+        let code = params
+            .get("code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "missing 'code' parameter".to_string())?;
+
+        self.prune_expired_oauth_sessions().await;
+        let session = self
+            .pending_oauth
+            .write()
+            .await
+            .remove(session_id)
+            .ok_or_else(|| "unknown or expired OAuth session".to_string())?;
+
+        if session.provider_name != provider_name {
+            return Err(format!(
+                "session {session_id} belongs to provider {}, not {provider_name}",
+                session.provider_name
+            ));
+        }
+        if let Some(state) = params.get("state").and_then(|v| v.as_str())
+            && state != session.expected_state
+        {
+            return Err("OAuth state mismatch".to_string());
+        }
+
+        let tokens = session
+            .flow
+            .exchange(code, &session.verifier)
+            .await
+            .map_err(|e| format!("OAuth token exchange failed: {e}"))?;
+
+        self.token_store
+            .save(provider_name, &tokens)
+            .map_err(|e| format!("failed to save OAuth tokens: {e}"))?;
+
+        let new_registry = ProviderRegistry::from_env_with_config(&self.config);
+        let mut reg = self.registry.write().await;
+        *reg = new_registry;
+
+        info!(
+            provider = provider_name,
+            "manual OAuth flow complete, rebuilt provider registry"
+        );
+
+        Ok(serde_json::json!({ "ok": true }))
+    }
 }
 
 #[cfg(test)]
@@ -346,6 +469,36 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn oauth_complete_rejects_unknown_session() {
+        let registry = Arc::new(RwLock::new(ProviderRegistry::from_env_with_config(
+            &ProvidersConfig::default(),
+        )));
+        let svc = LiveProviderSetupService::new(registry, ProvidersConfig::default());
+        let result = svc
+            .oauth_complete(serde_json::json!({
+                "provider": "openai-codex",
+                "sessionId": "does-not-exist",
+                "code": "abc123",
+            }))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn oauth_complete_rejects_missing_params() {
+        let registry = Arc::new(RwLock::new(ProviderRegistry::from_env_with_config(
+            &ProvidersConfig::default(),
+        )));
+        let svc = LiveProviderSetupService::new(registry, ProvidersConfig::default());
+        assert!(svc.oauth_complete(serde_json::json!({})).await.is_err());
+        assert!(
+            svc.oauth_complete(serde_json::json!({"provider": "openai-codex"}))
+                .await
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn oauth_status_returns_not_authenticated() {
         let registry = Arc::new(RwLock::new(ProviderRegistry::from_env_with_config(
@@ -0,0 +1,105 @@
+//! Server-Sent Events transport for gateway events, alongside the
+//! WebSocket broadcast.
+//!
+//! Reuses the same subscription-set semantics `register`'s
+//! `subscribe`/`unsubscribe` gives WebSocket clients (see
+//! `crate::methods::subscribe`): a GET here registers a pseudo-client in
+//! `GatewayState.inner.clients` with its own `SubscriptionSpec`, so it
+//! receives the exact same `broadcast(state, "channel"/"chat", payload,
+//! ...)` frames a WebSocket client would, just re-encoded as
+//! `event: <name>\ndata: <json>\n\n`. The pseudo-client is deregistered the
+//! moment the stream is dropped (the browser closed the connection or
+//! navigated away).
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::Stream;
+use serde::Deserialize;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::methods::subscribe::SubscriptionSpec;
+
+/// One event frame handed to a subscribed client's send channel — the same
+/// shape the WebSocket write loop forwards to a real socket, just consumed
+/// here as SSE instead.
+pub struct SseFrame {
+    pub event: String,
+    pub data: String,
+}
+
+#[derive(Deserialize)]
+pub struct SseParams {
+    /// Comma-separated event names to subscribe to, e.g. `?events=channel,chat`.
+    #[serde(default)]
+    events: String,
+}
+
+fn parse_events(raw: &str) -> HashSet<String> {
+    raw.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
+}
+
+/// Wraps the pseudo-client's receiver so dropping the stream (client
+/// disconnect) deregisters it from `GatewayState.inner.clients` instead of
+/// leaking a dead entry that `broadcast` would keep trying — and failing —
+/// to send to.
+struct EventStream {
+    inner: ReceiverStream<SseFrame>,
+    conn_id: String,
+    state: crate::server::AppState,
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        let conn_id = self.conn_id.clone();
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            state.inner.write().await.clients.remove(&conn_id);
+        });
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner)
+            .poll_next(cx)
+            .map(|opt| opt.map(|frame| Ok(Event::default().event(frame.event).data(frame.data))))
+    }
+}
+
+/// Stream gateway events over SSE, subscribed to the same event names a
+/// WebSocket client would pass to `subscribe`. Drops frames instead of
+/// blocking when this connection falls behind, matching the
+/// `BroadcastOpts { drop_if_slow: true }` behavior `broadcast` already
+/// applies to WebSocket clients.
+pub async fn events_stream(
+    State(state): State<crate::server::AppState>,
+    Query(params): Query<SseParams>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = parse_events(&params.events);
+    let conn_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = tokio::sync::mpsc::channel(32);
+
+    {
+        let mut inner = state.inner.write().await;
+        let mut subscriptions = HashMap::new();
+        subscriptions.insert(conn_id.clone(), SubscriptionSpec { events, filter: None });
+        inner.clients.insert(
+            conn_id.clone(),
+            crate::server::GatewayClient::sse(tx, subscriptions),
+        );
+    }
+
+    Sse::new(EventStream { inner: ReceiverStream::new(rx), conn_id, state })
+        .keep_alive(KeepAlive::default())
+}
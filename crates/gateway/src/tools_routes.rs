@@ -2,7 +2,61 @@
 //!
 //! Provides endpoints to get, validate, and save the full moltis config as TOML.
 
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{Json, extract::{Path, State}, http::StatusCode, response::IntoResponse};
+use serde::Serialize;
+
+/// Severity of a single [`ConfigIssue`].
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+/// One structured, UI-addressable config validation issue.
+///
+/// `path` is a dotted key path (e.g. `tools.browser.max_instances`), empty
+/// when a parse error can't be attributed to a single field. `line`/`column`
+/// are 1-based and only set for TOML parse errors that carry a span.
+#[derive(Serialize)]
+struct ConfigIssue {
+    path: String,
+    severity: IssueSeverity,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+}
+
+impl ConfigIssue {
+    fn warning(path: &str, message: impl Into<String>) -> Self {
+        Self { path: path.to_string(), severity: IssueSeverity::Warning, message: message.into(), line: None, column: None }
+    }
+
+    fn parse_error(toml_str: &str, e: &toml::de::Error) -> Self {
+        let (line, column) = e
+            .span()
+            .map(|span| line_col_at(toml_str, span.start))
+            .unzip();
+        Self { path: String::new(), severity: IssueSeverity::Error, message: e.message().to_string(), line, column }
+    }
+}
+
+/// Convert a byte offset into 1-based (line, column) within `text`.
+fn line_col_at(text: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut col = 1;
+    for ch in text[..offset.min(text.len())].chars() {
+        if ch == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+    (line, col)
+}
 
 /// Get the current configuration as TOML.
 pub async fn config_get(State(_state): State<crate::server::AppState>) -> impl IntoResponse {
@@ -26,6 +80,11 @@ pub async fn config_get(State(_state): State<crate::server::AppState>) -> impl I
 }
 
 /// Validate configuration TOML without saving.
+///
+/// Returns a flat list of `{path, severity, message}` issues rather than one
+/// opaque string, so the editor can underline the exact offending key: parse
+/// errors carry a `line`/`column` when the TOML parser reports a span, and
+/// semantic checks from [`validate_config`] carry a dotted field `path`.
 pub async fn config_validate(
     State(_state): State<crate::server::AppState>,
     Json(body): Json<serde_json::Value>,
@@ -41,27 +100,29 @@ pub async fn config_validate(
     // Try to parse the TOML as MoltisConfig
     match toml::from_str::<moltis_config::MoltisConfig>(toml_str) {
         Ok(config) => {
-            // Run validation checks
-            let warnings = validate_config(&config);
-
+            let issues = validate_config(&config);
             Json(serde_json::json!({
                 "valid": true,
-                "warnings": warnings,
-            }))
-            .into_response()
-        },
-        Err(e) => {
-            // Parse error message to extract line/column if available
-            let error_msg = e.to_string();
-            Json(serde_json::json!({
-                "valid": false,
-                "error": error_msg,
+                "issues": issues,
             }))
             .into_response()
         },
+        Err(e) => Json(serde_json::json!({
+            "valid": false,
+            "issues": [ConfigIssue::parse_error(toml_str, &e)],
+        }))
+        .into_response(),
     }
 }
 
+/// Emit a JSON Schema for `MoltisConfig` — field types, required/optional,
+/// enum values (e.g. the exec sandbox `mode`), and documented constraints
+/// such as `max_instances` bounds — so the web editor can do client-side
+/// completion and validation instead of waiting on a full save round-trip.
+pub async fn config_schema(State(_state): State<crate::server::AppState>) -> impl IntoResponse {
+    Json(moltis_config::json_schema())
+}
+
 /// Get the default configuration template with all options documented.
 /// Preserves the current port from the existing config.
 pub async fn config_template(State(_state): State<crate::server::AppState>) -> impl IntoResponse {
@@ -74,9 +135,78 @@ pub async fn config_template(State(_state): State<crate::server::AppState>) -> i
     }))
 }
 
+/// Recursively collect dotted-path keys whose value differs between `old`
+/// and `new`. Object subtrees recurse key-by-key; anything else (scalars,
+/// arrays) is compared wholesale, so e.g. an allowlist edit reports as
+/// `channels.whatsapp.accounts.bot1.allowlist` rather than one entry per
+/// array element.
+fn diff_paths(old: &serde_json::Value, new: &serde_json::Value, prefix: &str, out: &mut Vec<String>) {
+    match (old, new) {
+        (serde_json::Value::Object(old_map), serde_json::Value::Object(new_map)) => {
+            let mut keys: std::collections::BTreeSet<&String> = old_map.keys().collect();
+            keys.extend(new_map.keys());
+            for key in keys {
+                let child_prefix = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                match (old_map.get(key), new_map.get(key)) {
+                    (Some(o), Some(n)) => diff_paths(o, n, &child_prefix, out),
+                    _ => out.push(child_prefix),
+                }
+            }
+        },
+        _ => {
+            if old != new {
+                out.push(prefix.to_string());
+            }
+        },
+    }
+}
+
+/// Split changed config paths into ones that can be hot-applied (account
+/// configs under `channels.<plugin>.accounts.<account_id>`, handled via
+/// [`moltis_channels::plugin::ConfigReload`]) and everything else, which
+/// still needs a restart.
+fn classify_diff(paths: &[String]) -> (bool, Vec<(String, String)>) {
+    let mut restart_required = false;
+    let mut hot_accounts: std::collections::BTreeSet<(String, String)> = Default::default();
+    for path in paths {
+        let segments: Vec<&str> = path.split('.').collect();
+        if segments.len() >= 4 && segments[0] == "channels" && segments[2] == "accounts" {
+            hot_accounts.insert((segments[1].to_string(), segments[3].to_string()));
+        } else {
+            restart_required = true;
+        }
+    }
+    (restart_required, hot_accounts.into_iter().collect())
+}
+
+/// Hand one account's new config to its plugin's `ConfigReload` hook.
+/// Returns whether the change was actually applied without a restart.
+async fn reload_channel_account(
+    state: &crate::server::AppState,
+    plugin_id: &str,
+    account_id: &str,
+    config: serde_json::Value,
+) -> bool {
+    match state
+        .services
+        .reload_channel_account_config(plugin_id, account_id, config)
+        .await
+    {
+        Ok(applied) => applied,
+        Err(e) => {
+            tracing::warn!(plugin_id, account_id, "hot-reload failed: {e}");
+            false
+        },
+    }
+}
+
 /// Save configuration from TOML.
+///
+/// Diffs the incoming config against the currently loaded one so fields
+/// that a plugin can apply live (e.g. a WhatsApp account's allowlist) skip
+/// the `restart_required` flag — everything else still needs one.
 pub async fn config_save(
-    State(_state): State<crate::server::AppState>,
+    State(state): State<crate::server::AppState>,
     Json(body): Json<serde_json::Value>,
 ) -> impl IntoResponse {
     let Some(toml_str) = body.get("toml").and_then(|v| v.as_str()) else {
@@ -102,13 +232,42 @@ pub async fn config_save(
         },
     };
 
+    let previous = moltis_config::discover_and_load();
+    let (old_value, new_value) = match (serde_json::to_value(&previous), serde_json::to_value(&config)) {
+        (Ok(o), Ok(n)) => (o, n),
+        _ => (serde_json::Value::Null, serde_json::Value::Null),
+    };
+    let mut changed_paths = Vec::new();
+    diff_paths(&old_value, &new_value, "", &mut changed_paths);
+    let (mut restart_required, hot_accounts) = classify_diff(&changed_paths);
+
+    let mut hot_reloaded = Vec::new();
+    for (plugin_id, account_id) in &hot_accounts {
+        let account_config = new_value
+            .pointer(&format!("/channels/{plugin_id}/accounts/{account_id}"))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        if reload_channel_account(&state, plugin_id, account_id, account_config).await {
+            hot_reloaded.push(format!("{plugin_id}.{account_id}"));
+        } else {
+            restart_required = true;
+        }
+    }
+
     match moltis_config::save_config(&config) {
         Ok(path) => {
-            tracing::info!(path = %path.display(), "saved config");
+            tracing::info!(
+                path = %path.display(),
+                restart_required,
+                hot_reloaded = hot_reloaded.len(),
+                "saved config"
+            );
             Json(serde_json::json!({
                 "ok": true,
                 "path": path.to_string_lossy(),
-                "restart_required": true,
+                "restart_required": restart_required,
+                "hot_reloaded": hot_reloaded,
+                "changed_fields": changed_paths,
             }))
             .into_response()
         },
@@ -120,65 +279,212 @@ pub async fn config_save(
     }
 }
 
-/// Validate config and return warnings.
-fn validate_config(config: &moltis_config::MoltisConfig) -> Vec<String> {
-    let mut warnings = Vec::new();
+/// List saved config revisions, newest first.
+///
+/// Revisions are the timestamped backups `moltis_config::save_config` now
+/// leaves behind in the config history directory before each overwrite.
+pub async fn config_history(State(_state): State<crate::server::AppState>) -> impl IntoResponse {
+    match moltis_config::history::list_revisions() {
+        Ok(revisions) => Json(serde_json::json!({
+            "revisions": revisions
+                .iter()
+                .map(|r| serde_json::json!({
+                    "id": r.id,
+                    "timestamp": r.timestamp.to_rfc3339(),
+                    "bytes": r.bytes,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("failed to list config history: {e}") })),
+        )
+            .into_response(),
+    }
+}
+
+/// Restore a previously saved config revision.
+///
+/// Re-parses the revision's TOML with `toml::from_str::<MoltisConfig>`, as
+/// `config_save` does, before writing it back out as the active config, so a
+/// revision that no longer parses is rejected. `validate_config` warnings are
+/// returned alongside the result and do not block the rollback. The
+/// write itself goes through `moltis_config::save_config`, so rolling back
+/// also backs up the config being replaced and prunes old revisions.
+pub async fn config_rollback(
+    State(state): State<crate::server::AppState>,
+    Path(revision_id): Path<String>,
+) -> impl IntoResponse {
+    let toml_str = match moltis_config::history::read_revision(&revision_id) {
+        Ok(toml_str) => toml_str,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": format!("revision '{revision_id}' not found: {e}") })),
+            )
+                .into_response();
+        },
+    };
+
+    let config: moltis_config::MoltisConfig = match toml::from_str(&toml_str) {
+        Ok(c) => c,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("revision '{revision_id}' is not valid TOML: {e}"),
+                    "valid": false,
+                })),
+            )
+                .into_response();
+        },
+    };
+
+    // Run `validate_config`'s warning checks, purely informational —
+    // a revision that once saved successfully is allowed back even if it now
+    // trips a warning (e.g. TLS disabled).
+    let warnings = validate_config(&config);
+
+    let previous = moltis_config::discover_and_load();
+    let (old_value, new_value) = match (serde_json::to_value(&previous), serde_json::to_value(&config)) {
+        (Ok(o), Ok(n)) => (o, n),
+        _ => (serde_json::Value::Null, serde_json::Value::Null),
+    };
+    let mut changed_paths = Vec::new();
+    diff_paths(&old_value, &new_value, "", &mut changed_paths);
+    let (mut restart_required, hot_accounts) = classify_diff(&changed_paths);
+
+    let mut hot_reloaded = Vec::new();
+    for (plugin_id, account_id) in &hot_accounts {
+        let account_config = new_value
+            .pointer(&format!("/channels/{plugin_id}/accounts/{account_id}"))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        if reload_channel_account(&state, plugin_id, account_id, account_config).await {
+            hot_reloaded.push(format!("{plugin_id}.{account_id}"));
+        } else {
+            restart_required = true;
+        }
+    }
+
+    match moltis_config::save_config(&config) {
+        Ok(path) => {
+            tracing::info!(
+                path = %path.display(),
+                revision_id,
+                restart_required,
+                "rolled back config"
+            );
+            Json(serde_json::json!({
+                "ok": true,
+                "path": path.to_string_lossy(),
+                "restart_required": restart_required,
+                "hot_reloaded": hot_reloaded,
+                "warnings": warnings,
+            }))
+            .into_response()
+        },
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("failed to save rolled-back config: {e}") })),
+        )
+            .into_response(),
+    }
+}
+
+/// Validate config and return structured issues (all `Warning` severity —
+/// none of these checks reject an already-parsed config, they just flag
+/// risky settings), each addressed to the dotted field path it's about.
+fn validate_config(config: &moltis_config::MoltisConfig) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
 
     // Check browser config
     if config.tools.browser.enabled {
         if config.tools.browser.sandbox {
-            warnings.push(
+            issues.push(ConfigIssue::warning(
+                "tools.browser.sandbox",
                 "Browser sandbox mode is enabled but not yet implemented. \
-                 Browser will run on host."
-                    .to_string(),
-            );
+                 Browser will run on host.",
+            ));
         }
 
         if config.tools.browser.allowed_domains.is_empty() {
-            warnings.push(
+            issues.push(ConfigIssue::warning(
+                "tools.browser.allowed_domains",
                 "No allowed_domains set for browser. All domains are accessible. \
-                 Consider restricting to trusted domains for security."
-                    .to_string(),
-            );
+                 Consider restricting to trusted domains for security.",
+            ));
         }
 
         if config.tools.browser.max_instances > 10 {
-            warnings.push(format!(
-                "max_instances={} is high. Consider reducing to prevent resource exhaustion.",
-                config.tools.browser.max_instances
+            issues.push(ConfigIssue::warning(
+                "tools.browser.max_instances",
+                format!(
+                    "max_instances={} is high. Consider reducing to prevent resource exhaustion.",
+                    config.tools.browser.max_instances
+                ),
             ));
         }
     }
 
     // Check exec config
     if config.tools.exec.sandbox.mode == "off" {
-        warnings.push(
-            "Sandbox mode is off. Commands will run directly on host without isolation."
-                .to_string(),
-        );
+        issues.push(ConfigIssue::warning(
+            "tools.exec.sandbox.mode",
+            "Sandbox mode is off. Commands will run directly on host without isolation.",
+        ));
     }
 
     // Check auth config
     if config.auth.disabled {
-        warnings.push(
-            "Authentication is disabled. Anyone with network access can use the gateway."
-                .to_string(),
-        );
+        issues.push(ConfigIssue::warning(
+            "auth.disabled",
+            "Authentication is disabled. Anyone with network access can use the gateway.",
+        ));
     }
 
     // Check TLS config
     if !config.tls.enabled {
-        warnings.push("TLS is disabled. Connections will use unencrypted HTTP.".to_string());
+        issues.push(ConfigIssue::warning("tls.enabled", "TLS is disabled. Connections will use unencrypted HTTP."));
+    }
+
+    // Check the `[tls.trust]` root-CA configuration.
+    if !config.tls.trust.use_system_roots && config.tls.trust.extra_ca_certs.is_empty() {
+        issues.push(ConfigIssue::warning(
+            "tls.trust.use_system_roots",
+            "tls.trust.use_system_roots is false with no extra_ca_certs configured. \
+             No certificate authority would be trusted; outbound TLS connections will fail.",
+        ));
+    }
+    for ca_path in &config.tls.trust.extra_ca_certs {
+        match std::fs::read(ca_path) {
+            Ok(bytes) => {
+                if rustls_pemfile::certs(&mut bytes.as_slice()).collect::<Vec<_>>().iter().any(|c| c.is_err()) {
+                    issues.push(ConfigIssue::warning(
+                        "tls.trust.extra_ca_certs",
+                        format!("tls.trust.extra_ca_certs entry '{ca_path}' could not be parsed as a PEM certificate."),
+                    ));
+                }
+            },
+            Err(e) => {
+                issues.push(ConfigIssue::warning(
+                    "tls.trust.extra_ca_certs",
+                    format!("tls.trust.extra_ca_certs entry '{ca_path}' could not be read: {e}"),
+                ));
+            },
+        }
     }
 
     // Check heartbeat active hours
     if config.heartbeat.enabled
         && config.heartbeat.active_hours.start == config.heartbeat.active_hours.end
     {
-        warnings.push(
-            "Heartbeat active_hours start and end are the same. Heartbeat may not run.".to_string(),
-        );
+        issues.push(ConfigIssue::warning(
+            "heartbeat.active_hours",
+            "Heartbeat active_hours start and end are the same. Heartbeat may not run.",
+        ));
     }
 
-    warnings
+    issues
 }
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+/// Which live-stream platform an account's chat is ingested from.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LivechatPlatform {
+    #[default]
+    Twitch,
+    Youtube,
+}
+
+/// Configuration for a single live-stream chat account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LivechatAccountConfig {
+    /// Which platform `channel` identifies a stream on.
+    pub platform: LivechatPlatform,
+
+    /// Twitch channel login (lowercase, no leading `#`) or YouTube video id
+    /// of the live broadcast, depending on `platform`.
+    pub channel: String,
+
+    /// Credential enabling replies instead of read-only ingestion. For
+    /// Twitch, an `oauth:`-prefixed chat token for `nickname` — omitted,
+    /// the account connects anonymously as a `justinfanNNNNN` viewer and
+    /// can only read. For YouTube, a Data API key is enough to poll chat,
+    /// but posting replies needs an OAuth access token with the
+    /// `youtube.force-ssl` scope instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oauth_token: Option<String>,
+
+    /// Twitch login to authenticate as when `oauth_token` is set. Ignored
+    /// for YouTube and for anonymous Twitch ingestion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+
+    /// Default model ID for this account's sessions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Default for LivechatAccountConfig {
+    fn default() -> Self {
+        Self {
+            platform: LivechatPlatform::default(),
+            channel: String::new(),
+            oauth_token: None,
+            nickname: None,
+            model: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config() {
+        let cfg = LivechatAccountConfig::default();
+        assert_eq!(cfg.platform, LivechatPlatform::Twitch);
+        assert_eq!(cfg.channel, "");
+        assert!(cfg.oauth_token.is_none());
+    }
+
+    #[test]
+    fn deserialize_from_json() {
+        let json = r#"{
+            "platform": "youtube",
+            "channel": "dQw4w9WgXcQ",
+            "oauth_token": "api-key"
+        }"#;
+        let cfg: LivechatAccountConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.platform, LivechatPlatform::Youtube);
+        assert_eq!(cfg.channel, "dQw4w9WgXcQ");
+        assert_eq!(cfg.oauth_token.as_deref(), Some("api-key"));
+        assert!(cfg.nickname.is_none());
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let cfg = LivechatAccountConfig {
+            platform: LivechatPlatform::Twitch,
+            channel: "somechannel".into(),
+            nickname: Some("mybot".into()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&cfg).unwrap();
+        let cfg2: LivechatAccountConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(cfg2.platform, LivechatPlatform::Twitch);
+        assert_eq!(cfg2.channel, "somechannel");
+        assert_eq!(cfg2.nickname.as_deref(), Some("mybot"));
+    }
+}
@@ -0,0 +1,15 @@
+//! Live-stream chat channel plugin for moltis.
+//!
+//! Ingests chat from Twitch (IRC-over-WebSocket) and YouTube Live (polled
+//! continuation-token API) and emits it through the same
+//! `ChannelEventSink`/`MessageLog` pipeline the other channel plugins use,
+//! so the assistant can see and reply to livestream chat the same way it
+//! does a Telegram or Matrix conversation.
+
+pub mod config;
+pub mod outbound;
+pub mod state;
+pub mod twitch;
+pub mod youtube;
+
+pub use {config::LivechatAccountConfig, outbound::LivechatOutbound};
@@ -0,0 +1,105 @@
+use {anyhow::Result, async_trait::async_trait};
+
+use moltis_channels::plugin::ChannelOutbound;
+use moltis_common::types::ReplyPayload;
+
+use crate::{config::LivechatPlatform, state::AccountStateMap};
+
+/// Outbound message sender for live-stream chat.
+///
+/// Twitch replies go out over the IRC connection's write half (see
+/// `crate::twitch`); YouTube replies post against the live chat id
+/// discovered when polling started (see `crate::youtube`), via a
+/// `liveChatMessages.insert` call.
+pub struct LivechatOutbound {
+    pub(crate) accounts: AccountStateMap,
+}
+
+impl LivechatOutbound {
+    fn send_twitch(&self, account_id: &str, text: &str) -> Result<()> {
+        let accounts = self.accounts.read().unwrap();
+        let state = accounts
+            .get(account_id)
+            .ok_or_else(|| anyhow::anyhow!("unknown account: {account_id}"))?;
+        let sender = state.twitch_sender.lock().unwrap();
+        let sender = sender
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("twitch chat connection for {account_id} is not open"))?;
+
+        // IRC lines can't carry embedded newlines; collapse them rather
+        // than splitting into several PRIVMSGs the way the other channels
+        // chunk long replies, since chat messages are expected to be short.
+        sender
+            .send(format!("PRIVMSG #{} :{}", state.config.channel, text.replace('\n', " ")))
+            .map_err(|_| anyhow::anyhow!("twitch chat connection for {account_id} has closed"))
+    }
+
+    fn send_youtube(&self, account_id: &str, text: &str) -> Result<()> {
+        let (chat_id, token) = {
+            let accounts = self.accounts.read().unwrap();
+            let state = accounts
+                .get(account_id)
+                .ok_or_else(|| anyhow::anyhow!("unknown account: {account_id}"))?;
+            let chat_id = state
+                .youtube_live_chat_id
+                .lock()
+                .unwrap()
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("youtube live chat id for {account_id} not yet resolved"))?;
+            let token = state
+                .config
+                .oauth_token
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("no oauth token configured for youtube account {account_id}"))?;
+            (chat_id, token)
+        };
+
+        ureq::post("https://www.googleapis.com/youtube/v3/liveChat/messages?part=snippet")
+            .set("Authorization", &format!("Bearer {token}"))
+            .send_json(serde_json::json!({
+                "snippet": {
+                    "liveChatId": chat_id,
+                    "type": "textMessageEvent",
+                    "textMessageDetails": { "messageText": text },
+                }
+            }))
+            .map_err(|e| anyhow::anyhow!("posting youtube live chat message: {e}"))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelOutbound for LivechatOutbound {
+    async fn send_text(&self, account_id: &str, _to: &str, text: &str) -> Result<()> {
+        let platform = {
+            let accounts = self.accounts.read().unwrap();
+            accounts
+                .get(account_id)
+                .map(|s| s.config.platform)
+                .ok_or_else(|| anyhow::anyhow!("unknown account: {account_id}"))?
+        };
+
+        match platform {
+            LivechatPlatform::Twitch => self.send_twitch(account_id, text),
+            LivechatPlatform::Youtube => self.send_youtube(account_id, text),
+        }
+    }
+
+    async fn send_typing(&self, _account_id: &str, _to: &str) -> Result<()> {
+        // Neither platform's chat has a typing-indicator concept.
+        Ok(())
+    }
+
+    fn typing_interval(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    fn supports_markdown(&self) -> bool {
+        // Twitch/YouTube chat render plain text only.
+        false
+    }
+
+    async fn send_media(&self, _account_id: &str, _to: &str, _payload: &ReplyPayload) -> Result<()> {
+        Err(anyhow::anyhow!("media attachments aren't supported in live-stream chat"))
+    }
+}
@@ -0,0 +1,37 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+
+use moltis_channels::{ChannelEventSink, message_log::MessageLog};
+
+use crate::{config::LivechatAccountConfig, outbound::LivechatOutbound};
+
+/// Shared account state map.
+pub type AccountStateMap = Arc<RwLock<HashMap<String, AccountState>>>;
+
+/// Per-account runtime state.
+///
+/// Unlike the stateless Fediverse channel, sending a reply needs a live
+/// handle back into whichever platform connection `twitch::start_polling`/
+/// `youtube::start_polling` set up: an outgoing IRC line sender for
+/// Twitch, or the live chat id YouTube assigned to the broadcast for
+/// YouTube (the video id in `config.channel` is the *watch* id; replies
+/// post against a different, only-discoverable-at-connect-time chat id).
+/// Both fields are present regardless of platform and simply stay `None`
+/// for the platform that doesn't use them.
+pub struct AccountState {
+    pub account_id: String,
+    pub config: LivechatAccountConfig,
+    pub outbound: Arc<LivechatOutbound>,
+    pub cancel: CancellationToken,
+    pub message_log: Option<Arc<dyn MessageLog>>,
+    pub event_sink: Option<Arc<dyn ChannelEventSink>>,
+    /// Set once the Twitch IRC-over-WebSocket connection is open.
+    pub twitch_sender: Mutex<Option<UnboundedSender<String>>>,
+    /// Set once YouTube polling resolves the broadcast's live chat id.
+    pub youtube_live_chat_id: Mutex<Option<String>>,
+}
@@ -0,0 +1,247 @@
+//! Twitch live chat ingestion over IRC-over-WebSocket.
+//!
+//! Twitch's chat server speaks IRC framed inside a WebSocket connection at
+//! `wss://irc-ws.chat.twitch.tv:443`. Anonymous, read-only access just
+//! needs a `justinfanNNNNN` nick and any `PASS` value; sending replies
+//! needs a real login plus an `oauth:`-prefixed chat token (see
+//! [`crate::config::LivechatAccountConfig::oauth_token`]).
+
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use moltis_channels::{
+    ChannelEvent, ChannelEventSink, ChannelType,
+    message_log::{MessageLog, MessageLogEntry},
+};
+
+use crate::{
+    config::LivechatAccountConfig,
+    outbound::LivechatOutbound,
+    state::{AccountState, AccountStateMap},
+};
+
+const TWITCH_IRC_WS_URL: &str = "wss://irc-ws.chat.twitch.tv:443";
+
+/// How long to wait before reconnecting after the socket drops.
+const RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Start ingesting Twitch chat for one account. Reconnects with a fixed
+/// backoff on any socket error until cancelled, mirroring the retry loop
+/// `telegram::bot::start_polling` runs around `getUpdates`.
+pub async fn start_polling(
+    account_id: String,
+    config: LivechatAccountConfig,
+    accounts: AccountStateMap,
+    message_log: Option<Arc<dyn MessageLog>>,
+    event_sink: Option<Arc<dyn ChannelEventSink>>,
+) -> anyhow::Result<CancellationToken> {
+    let cancel = CancellationToken::new();
+    let outbound = Arc::new(LivechatOutbound { accounts: Arc::clone(&accounts) });
+
+    let state = AccountState {
+        account_id: account_id.clone(),
+        config: config.clone(),
+        outbound,
+        cancel: cancel.clone(),
+        message_log,
+        event_sink,
+        twitch_sender: Mutex::new(None),
+        youtube_live_chat_id: Mutex::new(None),
+    };
+    {
+        let mut map = accounts.write().unwrap();
+        map.insert(account_id.clone(), state);
+    }
+
+    let cancel_clone = cancel.clone();
+    tokio::spawn(async move {
+        info!(account_id, channel = %config.channel, "starting twitch chat ingestion");
+        loop {
+            if cancel_clone.is_cancelled() {
+                info!(account_id, "twitch chat ingestion stopped");
+                return;
+            }
+            if let Err(e) = run_connection(&account_id, &config, &accounts, &cancel_clone).await {
+                warn!(account_id, error = %e, "twitch chat connection dropped, reconnecting");
+            }
+            if cancel_clone.is_cancelled() {
+                return;
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+
+    Ok(cancel)
+}
+
+/// Open one IRC-over-WebSocket connection, authenticate, join the
+/// configured channel, and pump messages until the socket closes or
+/// `cancel` fires. Returns on any of those; the caller reconnects.
+async fn run_connection(
+    account_id: &str,
+    config: &LivechatAccountConfig,
+    accounts: &AccountStateMap,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let (ws_stream, _) = tokio_tungstenite::connect_async(TWITCH_IRC_WS_URL).await?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let nick = config
+        .nickname
+        .clone()
+        .unwrap_or_else(|| format!("justinfan{}", rand::rng().random_range(10_000..99_999)));
+    let pass = config.oauth_token.clone().unwrap_or_else(|| "SCHMOOPIIE".to_string());
+
+    write.send(WsMessage::Text(format!("PASS {pass}"))).await?;
+    write.send(WsMessage::Text(format!("NICK {nick}"))).await?;
+    // Tagged membership/commands capability so PRIVMSG lines carry the
+    // `display-name` tag parsed out in `parse_privmsg`.
+    write
+        .send(WsMessage::Text("CAP REQ :twitch.tv/tags twitch.tv/commands".to_string()))
+        .await?;
+    write.send(WsMessage::Text(format!("JOIN #{}", config.channel))).await?;
+
+    let (line_tx, mut line_rx) = mpsc::unbounded_channel::<String>();
+    {
+        let accounts = accounts.read().unwrap();
+        if let Some(state) = accounts.get(account_id) {
+            *state.twitch_sender.lock().unwrap() = Some(line_tx);
+        }
+    }
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return Ok(()),
+            outgoing = line_rx.recv() => {
+                match outgoing {
+                    Some(line) => write.send(WsMessage::Text(line)).await?,
+                    None => return Ok(()),
+                }
+            },
+            incoming = read.next() => {
+                let Some(frame) = incoming else { return Ok(()) };
+                let WsMessage::Text(text) = frame? else { continue };
+                for line in text.lines() {
+                    if let Some(rest) = line.strip_prefix("PING") {
+                        write.send(WsMessage::Text(format!("PONG{rest}"))).await?;
+                        continue;
+                    }
+                    if let Some(chat_msg) = parse_privmsg(line) {
+                        handle_chat_message(account_id, accounts, &chat_msg).await;
+                    } else {
+                        debug!(account_id, line, "ignoring non-chat twitch irc line");
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// A single Twitch chat message parsed out of a raw IRC line.
+struct TwitchChatMessage {
+    author: String,
+    text: String,
+}
+
+/// Parse a raw IRC line into a chat message if it's a `PRIVMSG`, pulling
+/// the display name out of the leading `@tag=value;...` block when tags
+/// capability is enabled, falling back to the `nick!user@host` prefix.
+fn parse_privmsg(line: &str) -> Option<TwitchChatMessage> {
+    let (tags, rest) = match line.strip_prefix('@') {
+        Some(stripped) => stripped.split_once(' ')?,
+        None => ("", line),
+    };
+
+    let rest = rest.trim_start().strip_prefix(':')?;
+    let (prefix, rest) = rest.split_once(' ')?;
+    let after_cmd = rest.strip_prefix("PRIVMSG ")?;
+    let (_target, message) = after_cmd.split_once(" :")?;
+
+    let author = tags
+        .split(';')
+        .find_map(|kv| kv.strip_prefix("display-name="))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| prefix.split('!').next().unwrap_or(prefix).to_string());
+
+    Some(TwitchChatMessage { author, text: message.to_string() })
+}
+
+/// Log and emit one inbound Twitch chat message. Twitch chat has no DM/
+/// group distinction or access gate of its own, so everything is logged as
+/// a granted group message under the configured channel.
+async fn handle_chat_message(account_id: &str, accounts: &AccountStateMap, msg: &TwitchChatMessage) {
+    let (message_log, event_sink, channel) = {
+        let accounts = accounts.read().unwrap();
+        let Some(state) = accounts.get(account_id) else { return };
+        (state.message_log.clone(), state.event_sink.clone(), state.config.channel.clone())
+    };
+
+    if let Some(log) = message_log {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let entry = MessageLogEntry {
+            id: 0,
+            account_id: account_id.to_string(),
+            channel_type: ChannelType::Twitch.to_string(),
+            peer_id: msg.author.clone(),
+            username: Some(msg.author.clone()),
+            sender_name: Some(msg.author.clone()),
+            chat_id: channel,
+            chat_type: "group".into(),
+            body: msg.text.clone(),
+            access_granted: true,
+            created_at: now,
+        };
+        if let Err(e) = log.log(entry).await {
+            warn!(account_id, error = %e, "failed to log twitch chat message");
+        }
+    }
+
+    if let Some(sink) = event_sink {
+        sink.emit(ChannelEvent::InboundMessage {
+            channel_type: ChannelType::Twitch,
+            account_id: account_id.to_string(),
+            peer_id: msg.author.clone(),
+            username: Some(msg.author.clone()),
+            sender_name: Some(msg.author.clone()),
+            message_count: None,
+            access_granted: true,
+        })
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tagged_privmsg() {
+        let line = "@display-name=CoolUser;room-id=123 :cooluser!cooluser@cooluser.tmi.twitch.tv PRIVMSG #somechannel :hello there";
+        let msg = parse_privmsg(line).unwrap();
+        assert_eq!(msg.author, "CoolUser");
+        assert_eq!(msg.text, "hello there");
+    }
+
+    #[test]
+    fn falls_back_to_prefix_nick_without_tags() {
+        let line = ":cooluser!cooluser@cooluser.tmi.twitch.tv PRIVMSG #somechannel :hi";
+        let msg = parse_privmsg(line).unwrap();
+        assert_eq!(msg.author, "cooluser");
+        assert_eq!(msg.text, "hi");
+    }
+
+    #[test]
+    fn ignores_non_privmsg_lines() {
+        assert!(parse_privmsg(":tmi.twitch.tv 376 justinfan1234 :>").is_none());
+    }
+}
@@ -0,0 +1,250 @@
+//! YouTube Live chat ingestion via the `liveChat/messages` polling API.
+//!
+//! Unlike Twitch's persistent IRC connection, YouTube chat is polled: each
+//! response carries an opaque `nextPageToken` continuation plus a
+//! server-provided `pollingIntervalMillis` the client is expected to honor
+//! before its next request — threaded between polls the same way
+//! Telegram's `getUpdates` offset carries state across calls.
+
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use moltis_channels::{
+    ChannelEvent, ChannelEventSink, ChannelType,
+    message_log::{MessageLog, MessageLogEntry},
+};
+
+use crate::{
+    config::LivechatAccountConfig,
+    outbound::LivechatOutbound,
+    state::{AccountState, AccountStateMap},
+};
+
+const API_BASE: &str = "https://www.googleapis.com/youtube/v3";
+
+/// Floor applied to the server-reported polling interval so a
+/// misbehaving/misconfigured response can't busy-loop requests against the
+/// API.
+const MIN_POLL_INTERVAL_MS: u64 = 1_000;
+
+/// Fallback polling interval when the API response doesn't include one.
+const DEFAULT_POLL_INTERVAL_MS: u64 = 5_000;
+
+#[derive(Deserialize)]
+struct VideosResponse {
+    items: Vec<VideoItem>,
+}
+
+#[derive(Deserialize)]
+struct VideoItem {
+    #[serde(rename = "liveStreamingDetails")]
+    live_streaming_details: Option<LiveStreamingDetails>,
+}
+
+#[derive(Deserialize)]
+struct LiveStreamingDetails {
+    #[serde(rename = "activeLiveChatId")]
+    active_live_chat_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LiveChatResponse {
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+    #[serde(rename = "pollingIntervalMillis")]
+    polling_interval_millis: Option<u64>,
+    items: Vec<LiveChatItem>,
+}
+
+#[derive(Deserialize)]
+struct LiveChatItem {
+    #[serde(rename = "authorDetails")]
+    author_details: AuthorDetails,
+    snippet: LiveChatSnippet,
+}
+
+#[derive(Deserialize)]
+struct AuthorDetails {
+    #[serde(rename = "displayName")]
+    display_name: String,
+}
+
+#[derive(Deserialize)]
+struct LiveChatSnippet {
+    #[serde(rename = "displayMessage")]
+    display_message: Option<String>,
+}
+
+/// Resolve the video id in `config.channel` to the broadcast's active live
+/// chat id — the API key/OAuth token passed to every other call here only
+/// identifies the chat once this has run.
+fn resolve_live_chat_id(video_id: &str, api_key: &str) -> anyhow::Result<String> {
+    let response: VideosResponse = ureq::get(&format!("{API_BASE}/videos"))
+        .query("part", "liveStreamingDetails")
+        .query("id", video_id)
+        .query("key", api_key)
+        .call()?
+        .into_json()?;
+    response
+        .items
+        .into_iter()
+        .find_map(|item| item.live_streaming_details?.active_live_chat_id)
+        .ok_or_else(|| anyhow::anyhow!("video {video_id} has no active live chat"))
+}
+
+fn poll_once(live_chat_id: &str, api_key: &str, page_token: Option<&str>) -> anyhow::Result<LiveChatResponse> {
+    let mut request = ureq::get(&format!("{API_BASE}/liveChat/messages"))
+        .query("liveChatId", live_chat_id)
+        .query("part", "snippet,authorDetails")
+        .query("key", api_key);
+    if let Some(token) = page_token {
+        request = request.query("pageToken", token);
+    }
+    Ok(request.call()?.into_json()?)
+}
+
+/// Start ingesting YouTube Live chat for one account. `config.channel` is
+/// the video id of the live broadcast; `config.oauth_token` doubles as the
+/// Data API key used for polling (a plain API key is enough to read —
+/// posting replies through [`crate::outbound::LivechatOutbound`] needs a
+/// real OAuth access token instead).
+pub async fn start_polling(
+    account_id: String,
+    config: LivechatAccountConfig,
+    accounts: AccountStateMap,
+    message_log: Option<Arc<dyn MessageLog>>,
+    event_sink: Option<Arc<dyn ChannelEventSink>>,
+) -> anyhow::Result<CancellationToken> {
+    let api_key = config
+        .oauth_token
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("youtube account {account_id} has no API key/oauth token configured"))?;
+
+    let cancel = CancellationToken::new();
+    let outbound = Arc::new(LivechatOutbound { accounts: Arc::clone(&accounts) });
+
+    let state = AccountState {
+        account_id: account_id.clone(),
+        config: config.clone(),
+        outbound,
+        cancel: cancel.clone(),
+        message_log,
+        event_sink,
+        twitch_sender: Mutex::new(None),
+        youtube_live_chat_id: Mutex::new(None),
+    };
+    {
+        let mut map = accounts.write().unwrap();
+        map.insert(account_id.clone(), state);
+    }
+
+    let cancel_clone = cancel.clone();
+    let aid = account_id.clone();
+    tokio::spawn(async move {
+        let video_id = config.channel.clone();
+
+        let live_chat_id = loop {
+            if cancel_clone.is_cancelled() {
+                return;
+            }
+            match resolve_live_chat_id(&video_id, &api_key) {
+                Ok(id) => break id,
+                Err(e) => {
+                    warn!(account_id = aid, error = %e, "failed to resolve youtube live chat id, retrying");
+                    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                },
+            }
+        };
+
+        {
+            let accounts_guard = accounts.read().unwrap();
+            if let Some(state) = accounts_guard.get(&aid) {
+                *state.youtube_live_chat_id.lock().unwrap() = Some(live_chat_id.clone());
+            }
+        }
+
+        info!(account_id = aid, %live_chat_id, "starting youtube live chat polling");
+        let mut page_token: Option<String> = None;
+
+        loop {
+            if cancel_clone.is_cancelled() {
+                info!(account_id = aid, "youtube chat polling stopped");
+                return;
+            }
+
+            match poll_once(&live_chat_id, &api_key, page_token.as_deref()) {
+                Ok(response) => {
+                    for item in &response.items {
+                        let Some(text) = item.snippet.display_message.clone() else {
+                            continue;
+                        };
+                        handle_chat_message(&aid, &accounts, &item.author_details.display_name, &text).await;
+                    }
+                    page_token = response.next_page_token;
+
+                    let delay = response
+                        .polling_interval_millis
+                        .unwrap_or(DEFAULT_POLL_INTERVAL_MS)
+                        .max(MIN_POLL_INTERVAL_MS);
+                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                },
+                Err(e) => {
+                    warn!(account_id = aid, error = %e, "youtube getLiveChatMessages failed");
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                },
+            }
+        }
+    });
+
+    Ok(cancel)
+}
+
+/// Log and emit one inbound YouTube chat message. Like Twitch, there's no
+/// per-viewer access gate, so everything is logged as a granted group
+/// message under the account id.
+async fn handle_chat_message(account_id: &str, accounts: &AccountStateMap, author: &str, text: &str) {
+    let (message_log, event_sink) = {
+        let accounts = accounts.read().unwrap();
+        let Some(state) = accounts.get(account_id) else { return };
+        (state.message_log.clone(), state.event_sink.clone())
+    };
+
+    if let Some(log) = message_log {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let entry = MessageLogEntry {
+            id: 0,
+            account_id: account_id.to_string(),
+            channel_type: ChannelType::Youtube.to_string(),
+            peer_id: author.to_string(),
+            username: Some(author.to_string()),
+            sender_name: Some(author.to_string()),
+            chat_id: account_id.to_string(),
+            chat_type: "group".into(),
+            body: text.to_string(),
+            access_granted: true,
+            created_at: now,
+        };
+        if let Err(e) = log.log(entry).await {
+            warn!(account_id, error = %e, "failed to log youtube chat message");
+        }
+    }
+
+    if let Some(sink) = event_sink {
+        sink.emit(ChannelEvent::InboundMessage {
+            channel_type: ChannelType::Youtube,
+            account_id: account_id.to_string(),
+            peer_id: author.to_string(),
+            username: Some(author.to_string()),
+            sender_name: Some(author.to_string()),
+            message_count: None,
+            access_granted: true,
+        })
+        .await;
+    }
+}
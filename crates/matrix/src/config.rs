@@ -0,0 +1,117 @@
+use {
+    moltis_channels::gating::{DmPolicy, GroupPolicy},
+    serde::{Deserialize, Serialize},
+};
+
+/// Configuration for a single Matrix account.
+///
+/// Unlike Telegram, there's no bot-token registration step — the account
+/// logs in (or is handed a long-lived `access_token`) against a homeserver
+/// and then behaves like any other Matrix client joined to its rooms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatrixAccountConfig {
+    /// Homeserver base URL, e.g. `https://matrix.org`.
+    pub homeserver_url: String,
+
+    /// Fully-qualified Matrix user ID, e.g. `@bot:matrix.org`.
+    pub user_id: String,
+
+    /// Long-lived access token for `user_id`. Minted once out of band
+    /// (interactive login, or the homeserver's admin API) rather than
+    /// stored password credentials.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+
+    /// DM access policy.
+    pub dm_policy: DmPolicy,
+
+    /// Group (room) access policy.
+    pub group_policy: GroupPolicy,
+
+    /// User ID allowlist for DMs.
+    pub allowlist: Vec<String>,
+
+    /// Room ID allowlist.
+    pub group_allowlist: Vec<String>,
+
+    /// Minimum interval between edit-in-place updates (ms).
+    pub edit_throttle_ms: u64,
+
+    /// Maximum body length an `m.replace` edit may carry before
+    /// [`MatrixOutbound::send_stream`](crate::outbound::MatrixOutbound::send_stream)
+    /// falls back to a new message instead of growing the existing one
+    /// further.
+    pub max_edit_body_len: usize,
+
+    /// Default model ID for this account's sessions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Default for MatrixAccountConfig {
+    fn default() -> Self {
+        Self {
+            homeserver_url: String::new(),
+            user_id: String::new(),
+            access_token: None,
+            dm_policy: DmPolicy::default(),
+            group_policy: GroupPolicy::default(),
+            allowlist: Vec::new(),
+            group_allowlist: Vec::new(),
+            edit_throttle_ms: 500,
+            max_edit_body_len: 4000,
+            model: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config() {
+        let cfg = MatrixAccountConfig::default();
+        assert_eq!(cfg.dm_policy, DmPolicy::Open);
+        assert_eq!(cfg.group_policy, GroupPolicy::Open);
+        assert_eq!(cfg.edit_throttle_ms, 500);
+        assert_eq!(cfg.max_edit_body_len, 4000);
+        assert!(cfg.allowlist.is_empty());
+        assert!(cfg.group_allowlist.is_empty());
+    }
+
+    #[test]
+    fn deserialize_from_json() {
+        let json = r#"{
+            "homeserver_url": "https://matrix.org",
+            "user_id": "@bot:matrix.org",
+            "dm_policy": "allowlist",
+            "allowlist": ["@alice:matrix.org"]
+        }"#;
+        let cfg: MatrixAccountConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.homeserver_url, "https://matrix.org");
+        assert_eq!(cfg.user_id, "@bot:matrix.org");
+        assert_eq!(cfg.dm_policy, DmPolicy::Allowlist);
+        assert_eq!(cfg.allowlist, vec!["@alice:matrix.org"]);
+        // defaults for unspecified fields
+        assert_eq!(cfg.group_policy, GroupPolicy::Open);
+        assert_eq!(cfg.edit_throttle_ms, 500);
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let cfg = MatrixAccountConfig {
+            homeserver_url: "https://example.org".into(),
+            user_id: "@bot:example.org".into(),
+            access_token: Some("secret".into()),
+            allowlist: vec!["@alice:example.org".into()],
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&cfg).unwrap();
+        let cfg2: MatrixAccountConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(cfg2.homeserver_url, "https://example.org");
+        assert_eq!(cfg2.access_token.as_deref(), Some("secret"));
+        assert_eq!(cfg2.allowlist, vec!["@alice:example.org"]);
+    }
+}
@@ -0,0 +1,11 @@
+//! Matrix channel plugin for moltis.
+//!
+//! Implements outbound delivery over `matrix-rust-sdk`: plain `m.room.message`
+//! sends alongside `TelegramOutbound`-style edit-in-place streaming, done
+//! here via `m.replace` relations rather than a dedicated edit API.
+
+pub mod config;
+pub mod outbound;
+pub mod state;
+
+pub use {config::MatrixAccountConfig, outbound::MatrixOutbound};
@@ -0,0 +1,202 @@
+use std::io::Read as _;
+
+use {
+    anyhow::{Context, Result},
+    async_trait::async_trait,
+    matrix_sdk::{
+        Client, Room,
+        attachment::AttachmentConfig,
+        ruma::{OwnedEventId, OwnedRoomId, events::room::message::RoomMessageEventContent},
+    },
+    tracing::debug,
+};
+
+use moltis_channels::plugin::{ChannelOutbound, ChannelStreamOutbound, StreamEvent, StreamReceiver};
+use moltis_common::types::ReplyPayload;
+
+use crate::state::AccountStateMap;
+
+/// Fetch the bytes of a hosted media URL so they can be uploaded to the
+/// homeserver's content repository. `ReplyPayload::media` only carries a
+/// URL — the same shape the Telegram outbound hands straight to
+/// `InputFile::url` — but Matrix has no "send by URL" primitive, so the
+/// bytes have to be fetched and re-uploaded as an `m.room.message`
+/// attachment.
+fn fetch_media_bytes(url: &str) -> Result<Vec<u8>> {
+    let response = ureq::get(url).call().context("fetching media url")?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .context("reading media bytes")?;
+    Ok(bytes)
+}
+
+/// Outbound message sender for Matrix.
+pub struct MatrixOutbound {
+    pub(crate) accounts: AccountStateMap,
+}
+
+impl MatrixOutbound {
+    fn get_client(&self, account_id: &str) -> Result<Client> {
+        let accounts = self.accounts.read().unwrap();
+        accounts
+            .get(account_id)
+            .map(|s| s.client.clone())
+            .ok_or_else(|| anyhow::anyhow!("unknown account: {account_id}"))
+    }
+
+    fn get_room(&self, client: &Client, to: &str) -> Result<Room> {
+        let room_id: OwnedRoomId = to.try_into().with_context(|| format!("invalid room id {to}"))?;
+        client
+            .get_room(&room_id)
+            .ok_or_else(|| anyhow::anyhow!("not joined to room: {to}"))
+    }
+
+    fn edit_throttle(&self, account_id: &str) -> std::time::Duration {
+        let throttle_ms = {
+            let accounts = self.accounts.read().unwrap();
+            accounts
+                .get(account_id)
+                .map(|s| s.config.edit_throttle_ms)
+                .unwrap_or(500)
+        };
+        std::time::Duration::from_millis(throttle_ms)
+    }
+
+    fn max_edit_body_len(&self, account_id: &str) -> usize {
+        let accounts = self.accounts.read().unwrap();
+        accounts
+            .get(account_id)
+            .map(|s| s.config.max_edit_body_len)
+            .unwrap_or(4000)
+    }
+
+    /// Edit `target` in place via an `m.replace` relation, unless `body` has
+    /// grown past `max_edit_len` — Matrix edits carry the whole new body,
+    /// and homeservers cap event size, so once a stream's accumulated text
+    /// gets too large we start a fresh message and keep editing that one
+    /// instead (the Matrix analogue of the Telegram stream handler sending
+    /// a new placeholder once a chunk fills up). Returns the event id that
+    /// now holds the live content, for the next edit to target.
+    async fn apply_stream_edit(
+        &self,
+        room: &Room,
+        target: &OwnedEventId,
+        body: &str,
+        max_edit_len: usize,
+    ) -> OwnedEventId {
+        if body.len() > max_edit_len {
+            return match room.send(RoomMessageEventContent::text_markdown(body)).await {
+                Ok(resp) => resp.event_id,
+                Err(e) => {
+                    debug!("matrix: failed to send overflow message: {e}");
+                    target.clone()
+                },
+            };
+        }
+
+        let replacement = RoomMessageEventContent::text_markdown(body).make_replacement(target.clone());
+        if let Err(e) = room.send(replacement).await {
+            debug!("matrix: failed to edit message: {e}");
+        }
+        target.clone()
+    }
+}
+
+#[async_trait]
+impl ChannelOutbound for MatrixOutbound {
+    async fn send_text(&self, account_id: &str, to: &str, text: &str) -> Result<()> {
+        let client = self.get_client(account_id)?;
+        let room = self.get_room(&client, to)?;
+        room.send(RoomMessageEventContent::text_markdown(text)).await?;
+        Ok(())
+    }
+
+    async fn send_typing(&self, account_id: &str, to: &str) -> Result<()> {
+        let client = self.get_client(account_id)?;
+        let room = self.get_room(&client, to)?;
+        let _ = room.typing_notice(true).await;
+        Ok(())
+    }
+
+    fn typing_interval(&self) -> Option<std::time::Duration> {
+        // Matrix typing notices are re-asserted by the SDK's own heartbeat,
+        // not by the dispatch loop, so there's no repeating interval to
+        // declare here.
+        None
+    }
+
+    fn supports_markdown(&self) -> bool {
+        // `send_text` already renders markdown via `text_markdown`.
+        true
+    }
+
+    async fn send_media(&self, account_id: &str, to: &str, payload: &ReplyPayload) -> Result<()> {
+        let client = self.get_client(account_id)?;
+        let room = self.get_room(&client, to)?;
+
+        if let Some(ref media) = payload.media {
+            let bytes = fetch_media_bytes(&media.url)?;
+            let filename = media.filename.clone().unwrap_or_else(|| "file".to_string());
+
+            // `send_attachment` picks the `m.image`/`m.audio`/`m.file` msgtype
+            // from the mime type itself, the same image/audio/file split the
+            // Telegram outbound makes explicitly via send_photo/send_audio/
+            // send_document.
+            let mut config = AttachmentConfig::new();
+            if !payload.text.is_empty() {
+                config = config.caption(Some(payload.text.clone()));
+            }
+            room.send_attachment(&filename, &media.mime_type, bytes, config).await?;
+        } else if !payload.text.is_empty() {
+            self.send_text(account_id, to, &payload.text).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelStreamOutbound for MatrixOutbound {
+    async fn send_stream(&self, account_id: &str, to: &str, mut stream: StreamReceiver) -> Result<()> {
+        let client = self.get_client(account_id)?;
+        let room = self.get_room(&client, to)?;
+        let throttle = self.edit_throttle(account_id);
+        let max_edit_len = self.max_edit_body_len(account_id);
+
+        let placeholder = room.send(RoomMessageEventContent::text_plain("…")).await?;
+        let mut live_event_id = placeholder.event_id;
+
+        let mut accumulated = String::new();
+        let mut last_edit = tokio::time::Instant::now();
+
+        while let Some(event) = stream.recv().await {
+            match event {
+                StreamEvent::Delta(delta) => {
+                    accumulated.push_str(&delta);
+                    if last_edit.elapsed() >= throttle {
+                        live_event_id = self
+                            .apply_stream_edit(&room, &live_event_id, &accumulated, max_edit_len)
+                            .await;
+                        last_edit = tokio::time::Instant::now();
+                    }
+                },
+                StreamEvent::Done => break,
+                StreamEvent::Error(e) => {
+                    debug!("stream error: {e}");
+                    accumulated.push_str(&format!("\n\n⚠ Error: {e}"));
+                    break;
+                },
+            }
+        }
+
+        if !accumulated.is_empty() {
+            let _ = self
+                .apply_stream_edit(&room, &live_event_id, &accumulated, max_edit_len)
+                .await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use tokio_util::sync::CancellationToken;
+
+use moltis_channels::{ChannelEventSink, message_log::MessageLog};
+
+use crate::{config::MatrixAccountConfig, outbound::MatrixOutbound};
+
+/// Shared account state map.
+pub type AccountStateMap = Arc<RwLock<HashMap<String, AccountState>>>;
+
+/// Per-account runtime state.
+pub struct AccountState {
+    pub client: matrix_sdk::Client,
+    pub account_id: String,
+    pub config: MatrixAccountConfig,
+    pub outbound: Arc<MatrixOutbound>,
+    pub cancel: CancellationToken,
+    pub message_log: Option<Arc<dyn MessageLog>>,
+    pub event_sink: Option<Arc<dyn ChannelEventSink>>,
+}
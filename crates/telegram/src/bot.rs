@@ -1,9 +1,9 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use {
     teloxide::{
         prelude::*,
-        types::{AllowedUpdate, UpdateKind},
+        types::{AllowedUpdate, CallbackQuery, UpdateKind},
     },
     tokio_util::sync::CancellationToken,
     tracing::{debug, error, info, warn},
@@ -14,8 +14,8 @@ use moltis_channels::{ChannelEventSink, message_log::MessageLog};
 use crate::{
     config::TelegramAccountConfig,
     handlers,
-    outbound::TelegramOutbound,
-    state::{AccountState, AccountStateMap},
+    outbound::{self, TelegramOutbound},
+    state::{AccountState, AccountStateMap, ChatOverrideStore, ChatOverrides, DialogueStore},
 };
 
 /// Start polling for a single bot account.
@@ -28,6 +28,8 @@ pub async fn start_polling(
     accounts: AccountStateMap,
     message_log: Option<Arc<dyn MessageLog>>,
     event_sink: Option<Arc<dyn ChannelEventSink>>,
+    dialogue_store: Option<Arc<dyn DialogueStore>>,
+    chat_overrides: Option<Arc<dyn ChatOverrideStore>>,
 ) -> anyhow::Result<CancellationToken> {
     // Build bot with a client timeout longer than the long-polling timeout (30s)
     // so the HTTP client doesn't abort the request before Telegram responds.
@@ -64,6 +66,9 @@ pub async fn start_polling(
         cancel: cancel.clone(),
         message_log,
         event_sink,
+        pending_selections: Arc::new(Mutex::new(std::collections::HashMap::new())),
+        dialogue_store: dialogue_store.clone(),
+        chat_overrides: chat_overrides.clone(),
     };
 
     {
@@ -76,7 +81,18 @@ pub async fn start_polling(
     let poll_accounts = Arc::clone(&accounts);
     tokio::spawn(async move {
         info!(account_id = aid, "starting telegram manual polling loop");
-        let mut offset: i32 = 0;
+
+        let mut offset: i32 = match &dialogue_store {
+            Some(store) => match store.load_offset(&aid).await {
+                Ok(Some(offset)) => offset,
+                Ok(None) => 0,
+                Err(e) => {
+                    warn!(account_id = aid, error = %e, "failed to load persisted telegram offset, starting from 0");
+                    0
+                },
+            },
+            None => 0,
+        };
 
         loop {
             if cancel_clone.is_cancelled() {
@@ -88,7 +104,7 @@ pub async fn start_polling(
                 .get_updates()
                 .offset(offset as i32)
                 .timeout(30)
-                .allowed_updates(vec![AllowedUpdate::Message])
+                .allowed_updates(vec![AllowedUpdate::Message, AllowedUpdate::CallbackQuery])
                 .await;
 
             match result {
@@ -107,6 +123,10 @@ pub async fn start_polling(
                                     chat_id = msg.chat.id.0,
                                     "received telegram message"
                                 );
+                                if let Some(command) = msg.text().and_then(parse_admin_command) {
+                                    handle_admin_command(&bot, &poll_accounts, &aid, &msg, command).await;
+                                    continue;
+                                }
                                 if let Err(e) = handlers::handle_message_direct(
                                     msg,
                                     &bot,
@@ -122,11 +142,26 @@ pub async fn start_polling(
                                     );
                                 }
                             },
+                            UpdateKind::CallbackQuery(query) => {
+                                if let Err(e) = handle_callback_query(query, &bot, &poll_accounts, &aid).await {
+                                    error!(
+                                        account_id = aid,
+                                        error = %e,
+                                        "error handling telegram callback query"
+                                    );
+                                }
+                            },
                             other => {
                                 debug!(account_id = aid, "ignoring non-message update: {other:?}");
                             },
                         }
                     }
+
+                    if let Some(store) = &dialogue_store
+                        && let Err(e) = store.save_offset(&aid, offset).await
+                    {
+                        warn!(account_id = aid, error = %e, "failed to persist telegram offset");
+                    }
                 },
                 Err(e) => {
                     warn!(account_id = aid, error = %e, "telegram getUpdates failed");
@@ -138,3 +173,138 @@ pub async fn start_polling(
 
     Ok(cancel)
 }
+
+/// Resolve an inline-keyboard tap against the account's pending-selection
+/// registry (see [`crate::outbound::TelegramOutbound::prompt_choice`]),
+/// clear the client-side loading spinner, and strip the keyboard so the
+/// buttons can't be tapped again.
+async fn handle_callback_query(
+    query: CallbackQuery,
+    bot: &teloxide::Bot,
+    accounts: &AccountStateMap,
+    account_id: &str,
+) -> anyhow::Result<()> {
+    let message = query.message.clone();
+
+    if let Some(data) = query.data.as_deref()
+        && let Some((id, choice_index)) = outbound::decode_callback_data(data)
+    {
+        let pending = {
+            let accounts = accounts.read().unwrap();
+            accounts
+                .get(account_id)
+                .and_then(|state| state.pending_selections.lock().unwrap().remove(&id))
+        };
+
+        match pending {
+            Some(pending) => {
+                if let Some(value) = pending.values.get(choice_index).cloned() {
+                    let _ = pending.sender.send(value);
+                } else {
+                    debug!(account_id, %id, choice_index, "callback query tag out of range for pending prompt");
+                }
+            },
+            None => debug!(account_id, %id, "callback query for unknown or already-resolved prompt"),
+        }
+    } else {
+        debug!(account_id, "callback query with unparseable callback_data");
+    }
+
+    bot.answer_callback_query(query.id).await?;
+
+    if let Some(message) = message {
+        let _ = bot.edit_message_reply_markup(message.chat.id, message.id).await;
+    }
+
+    Ok(())
+}
+
+/// A recognized per-chat admin command: `/enable`, `/disable`, or
+/// `/model <id>`. See [`crate::state::ChatOverrideStore`].
+enum AdminCommand {
+    Enable,
+    Disable,
+    SetModel(String),
+}
+
+/// Parse `text` as one of the admin commands, or `None` if it isn't one —
+/// in which case the caller falls through to normal message handling.
+fn parse_admin_command(text: &str) -> Option<AdminCommand> {
+    let text = text.trim();
+    if text == "/enable" {
+        return Some(AdminCommand::Enable);
+    }
+    if text == "/disable" {
+        return Some(AdminCommand::Disable);
+    }
+    let model = text.strip_prefix("/model ").map(str::trim).filter(|m| !m.is_empty());
+    model.map(|m| AdminCommand::SetModel(m.to_string()))
+}
+
+/// Verify the sender is listed in `config.admins`, then upsert the chat's
+/// override record and echo the new state back. Unauthorized senders and
+/// accounts without a configured override store both get a short reply
+/// explaining why nothing changed.
+async fn handle_admin_command(
+    bot: &teloxide::Bot,
+    accounts: &AccountStateMap,
+    account_id: &str,
+    msg: &teloxide::types::Message,
+    command: AdminCommand,
+) {
+    let chat_id = msg.chat.id;
+    let sender_id = msg.from.as_ref().map(|user| user.id.0.to_string());
+
+    let (authorized, store) = {
+        let accounts = accounts.read().unwrap();
+        let Some(state) = accounts.get(account_id) else { return };
+        let authorized = sender_id
+            .as_deref()
+            .is_some_and(|id| state.config.admins.iter().any(|admin| admin == id));
+        (authorized, state.chat_overrides.clone())
+    };
+
+    if !authorized {
+        let _ = bot.send_message(chat_id, "You're not authorized to run this command.").await;
+        return;
+    }
+
+    let Some(store) = store else {
+        let _ = bot.send_message(chat_id, "No override store configured for this account.").await;
+        return;
+    };
+
+    let chat_id_str = chat_id.0.to_string();
+    let result = crate::state::upsert_override(store.as_ref(), account_id, &chat_id_str, |overrides| {
+        match &command {
+            AdminCommand::Enable => overrides.enabled = Some(true),
+            AdminCommand::Disable => overrides.enabled = Some(false),
+            AdminCommand::SetModel(model) => overrides.model = Some(model.clone()),
+        }
+    })
+    .await;
+
+    match result {
+        Ok(overrides) => {
+            let _ = bot.send_message(chat_id, format_overrides_reply(&overrides)).await;
+        },
+        Err(e) => {
+            warn!(account_id, error = %e, "failed to persist telegram chat override");
+            let _ = bot.send_message(chat_id, "Failed to update settings.").await;
+        },
+    }
+}
+
+/// Render a chat's current overrides as the confirmation reply admin
+/// commands send back.
+fn format_overrides_reply(overrides: &ChatOverrides) -> String {
+    let enabled = match overrides.enabled {
+        Some(true) => "enabled",
+        Some(false) => "disabled",
+        None => "default",
+    };
+    match &overrides.model {
+        Some(model) => format!("Updated: enabled={enabled}, model={model}"),
+        None => format!("Updated: enabled={enabled}"),
+    }
+}
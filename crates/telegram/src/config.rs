@@ -46,6 +46,11 @@ pub struct TelegramAccountConfig {
     /// When set, channel messages use this model instead of the first registered provider.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+
+    /// Telegram user IDs allowed to run admin commands (`/enable`,
+    /// `/disable`, `/model`) that change a chat's runtime overrides — see
+    /// `crate::state::ChatOverrideStore`. Empty means no one can run them.
+    pub admins: Vec<String>,
 }
 
 impl Default for TelegramAccountConfig {
@@ -60,6 +65,7 @@ impl Default for TelegramAccountConfig {
             stream_mode: StreamMode::default(),
             edit_throttle_ms: 300,
             model: None,
+            admins: Vec::new(),
         }
     }
 }
@@ -76,6 +82,7 @@ mod tests {
         assert_eq!(cfg.mention_mode, MentionMode::Mention);
         assert_eq!(cfg.stream_mode, StreamMode::EditInPlace);
         assert_eq!(cfg.edit_throttle_ms, 300);
+        assert!(cfg.admins.is_empty());
     }
 
     #[test]
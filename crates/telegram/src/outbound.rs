@@ -2,25 +2,153 @@ use {
     anyhow::Result,
     async_trait::async_trait,
     teloxide::{
-        payloads::SendMessageSetters,
+        payloads::{SendAudioSetters, SendDocumentSetters, SendMessageSetters, SendPhotoSetters, SendVoiceSetters},
         prelude::*,
-        types::{ChatAction, ChatId, InputFile, ParseMode},
+        types::{ChatAction, ChatId, InlineKeyboardButton, InlineKeyboardMarkup, InputFile, MessageId, ParseMode, ReplyParameters},
     },
+    tokio::sync::oneshot,
     tracing::debug,
+    uuid::Uuid,
 };
 
 use {
     moltis_channels::plugin::{
         ChannelOutbound, ChannelStreamOutbound, StreamEvent, StreamReceiver,
     },
-    moltis_common::types::ReplyPayload,
+    moltis_common::types::{InlineButton, ReplyPayload},
 };
 
 use crate::{
     markdown::{self, TELEGRAM_MAX_MESSAGE_LEN},
-    state::AccountStateMap,
+    state::{AccountStateMap, PendingSelection},
 };
 
+/// Alphabet used to pack a uuid and a per-button choice tag into
+/// `callback_data` as compactly as possible — Telegram caps that field at 64
+/// bytes, and a plain hex uuid alone would already use half of that.
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Encode `value` as a base-62 string (no leading-zero padding needed since
+/// every uuid is compared by exact decode, not string equality).
+pub(crate) fn encode_base62(mut value: u128) -> String {
+    if value == 0 {
+        return "0".to_string();
+    }
+    let mut digits = Vec::new();
+    while value > 0 {
+        digits.push(BASE62[(value % 62) as usize]);
+        value /= 62;
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("BASE62 alphabet is ASCII")
+}
+
+/// Inverse of [`encode_base62`].
+pub(crate) fn decode_base62(s: &str) -> Option<u128> {
+    let mut value: u128 = 0;
+    for b in s.bytes() {
+        let digit = BASE62.iter().position(|&c| c == b)? as u128;
+        value = value.checked_mul(62)?.checked_add(digit)?;
+    }
+    Some(value)
+}
+
+/// Split a `callback_data` string produced by [`TelegramOutbound::prompt_choice`]
+/// into the prompt's uuid and the tapped button's index into its `values`.
+pub(crate) fn decode_callback_data(data: &str) -> Option<(Uuid, usize)> {
+    let split_at = data.len().checked_sub(1)?;
+    if !data.is_char_boundary(split_at) {
+        return None;
+    }
+    let (encoded_uuid, tag) = data.split_at(split_at);
+    let uuid = Uuid::from_u128(decode_base62(encoded_uuid)?);
+    let index = BASE62.iter().position(|&c| c == tag.as_bytes()[0])?;
+    Some((uuid, index))
+}
+
+/// Telegram HTML tags the markdown renderer emits that take simple
+/// open/close pairs — the ones [`safe_streaming_prefix`] needs to track so
+/// it can synthesize closers for whichever are still open at a cut point.
+const INLINE_TAGS: &[&str] = &["b", "i", "u", "s", "code", "pre", "a", "tg-spoiler"];
+
+/// If `rest` starts with a recognized opening tag (e.g. `<a href="...">`),
+/// return its tag name.
+fn opening_tag_name(rest: &str) -> Option<&str> {
+    let body = rest.strip_prefix('<')?;
+    let end = body.find(|c: char| c == '>' || c == ' ')?;
+    let name = &body[..end];
+    INLINE_TAGS.iter().find(|t| **t == &name[..]).copied()
+}
+
+/// Cut `html` to at most `max_len` bytes in a way that's always safe to
+/// send as a Telegram edit: never on a non-UTF-8-char boundary, never
+/// inside a tag (backs up to the last tag that closed cleanly), and with
+/// synthetic closing tags appended for anything still open at the cut —
+/// so a partial message rendered mid-stream is always valid, self-contained
+/// HTML rather than a truncated tag soup Telegram will reject.
+fn safe_streaming_prefix(html: &str, max_len: usize) -> String {
+    if html.len() <= max_len {
+        return html.to_string();
+    }
+
+    let mut cut = max_len;
+    while cut > 0 && !html.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let mut prefix = &html[..cut];
+
+    // Never cut mid-tag: if there's an unclosed `<` after the last `>`,
+    // back up to the last cleanly-closed tag.
+    match prefix.rfind('>') {
+        Some(last_close) => {
+            if let Some(last_open) = prefix.rfind('<')
+                && last_open > last_close
+            {
+                prefix = &prefix[..=last_close];
+            }
+        },
+        // No `>` at all in the window, but there's a dangling `<` — back up
+        // to just before it rather than discarding the whole prefix, so
+        // plain text preceding the incomplete tag still gets sent.
+        None if prefix.contains('<') => prefix = &prefix[..prefix.rfind('<').unwrap_or(0)],
+        None => {},
+    }
+
+    // Track which inline tags are still open at the cut point so we can
+    // close them synthetically.
+    let mut open_tags: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < prefix.len() {
+        // Compare bytes rather than slicing first: `i` steps one byte at a
+        // time through text and can sit inside a multi-byte character.
+        if prefix.as_bytes()[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        let rest = &prefix[i..];
+        if let Some(tag_rest) = rest.strip_prefix("</") {
+            let end = tag_rest.find('>').unwrap_or(tag_rest.len());
+            let name = &tag_rest[..end];
+            if let Some(pos) = open_tags.iter().rposition(|t| *t == name) {
+                open_tags.remove(pos);
+            }
+            i += 2 + end + 1;
+            continue;
+        }
+        if let Some(name) = opening_tag_name(rest) {
+            open_tags.push(name);
+        }
+        let end = rest.find('>').unwrap_or(rest.len());
+        i += end + 1;
+    }
+
+    let mut out = prefix.to_string();
+    for tag in open_tags.iter().rev() {
+        out.push_str(&format!("</{tag}>"));
+    }
+    out
+}
+
 /// Outbound message sender for Telegram.
 pub struct TelegramOutbound {
     pub(crate) accounts: AccountStateMap,
@@ -64,9 +192,21 @@ impl ChannelOutbound for TelegramOutbound {
         Ok(())
     }
 
+    fn typing_interval(&self) -> Option<std::time::Duration> {
+        // Telegram's typing status expires after ~5s, so re-send before that.
+        Some(std::time::Duration::from_secs(4))
+    }
+
+    fn supports_markdown(&self) -> bool {
+        // `send_text` already renders markdown to Telegram's HTML subset.
+        true
+    }
+
     async fn send_media(&self, account_id: &str, to: &str, payload: &ReplyPayload) -> Result<()> {
         let bot = self.get_bot(account_id)?;
         let chat_id = ChatId(to.parse::<i64>()?);
+        let reply_parameters = reply_parameters_for(payload);
+        let reply_markup = inline_keyboard_for(payload);
 
         if let Some(ref media) = payload.media {
             let input = InputFile::url(media.url.parse()?);
@@ -77,6 +217,12 @@ impl ChannelOutbound for TelegramOutbound {
                     if !payload.text.is_empty() {
                         req = req.caption(&payload.text);
                     }
+                    if let Some(ref params) = reply_parameters {
+                        req = req.reply_parameters(params.clone());
+                    }
+                    if let Some(ref markup) = reply_markup {
+                        req = req.reply_markup(markup.clone());
+                    }
                     req.await?;
                 },
                 t if t.starts_with("audio/") => {
@@ -84,6 +230,12 @@ impl ChannelOutbound for TelegramOutbound {
                     if !payload.text.is_empty() {
                         req = req.caption(&payload.text);
                     }
+                    if let Some(ref params) = reply_parameters {
+                        req = req.reply_parameters(params.clone());
+                    }
+                    if let Some(ref markup) = reply_markup {
+                        req = req.reply_markup(markup.clone());
+                    }
                     req.await?;
                 },
                 "audio/ogg" => {
@@ -91,6 +243,12 @@ impl ChannelOutbound for TelegramOutbound {
                     if !payload.text.is_empty() {
                         req = req.caption(&payload.text);
                     }
+                    if let Some(ref params) = reply_parameters {
+                        req = req.reply_parameters(params.clone());
+                    }
+                    if let Some(ref markup) = reply_markup {
+                        req = req.reply_markup(markup.clone());
+                    }
                     req.await?;
                 },
                 _ => {
@@ -98,18 +256,108 @@ impl ChannelOutbound for TelegramOutbound {
                     if !payload.text.is_empty() {
                         req = req.caption(&payload.text);
                     }
+                    if let Some(ref params) = reply_parameters {
+                        req = req.reply_parameters(params.clone());
+                    }
+                    if let Some(ref markup) = reply_markup {
+                        req = req.reply_markup(markup.clone());
+                    }
                     req.await?;
                 },
             }
         } else if !payload.text.is_empty() {
-            self.send_text(account_id, to, &payload.text).await?;
+            let html = markdown::markdown_to_telegram_html(&payload.text);
+            let chunks = markdown::chunk_message(&html, TELEGRAM_MAX_MESSAGE_LEN);
+            for (i, chunk) in chunks.iter().enumerate() {
+                let mut req = bot.send_message(chat_id, chunk).parse_mode(ParseMode::Html);
+                // Only the first chunk threads under the triggering message
+                // and carries the buttons — later chunks are continuations.
+                if i == 0 {
+                    if let Some(ref params) = reply_parameters {
+                        req = req.reply_parameters(params.clone());
+                    }
+                    if let Some(ref markup) = reply_markup {
+                        req = req.reply_markup(markup.clone());
+                    }
+                }
+                req.await?;
+            }
         }
 
         Ok(())
     }
 }
 
+/// Build Telegram's `reply_parameters` for threading a reply under
+/// `payload.reply_to_message_id`, if set and parseable as a message id.
+fn reply_parameters_for(payload: &ReplyPayload) -> Option<ReplyParameters> {
+    payload
+        .reply_to_message_id
+        .as_ref()
+        .and_then(|id| id.parse::<i32>().ok())
+        .map(|id| ReplyParameters::new(MessageId(id)))
+}
+
+/// Build an `InlineKeyboardMarkup` from `payload.buttons`, a grid of
+/// (label, callback_data) rows, if any were set.
+fn inline_keyboard_for(payload: &ReplyPayload) -> Option<InlineKeyboardMarkup> {
+    let rows = payload.buttons.as_ref()?;
+    Some(InlineKeyboardMarkup::new(rows.iter().map(|row| {
+        row.iter()
+            .map(|b: &InlineButton| InlineKeyboardButton::callback(b.label.clone(), b.callback_data.clone()))
+            .collect::<Vec<_>>()
+    })))
+}
+
 impl TelegramOutbound {
+    /// Send `text` with an inline keyboard built from `options` (label,
+    /// value pairs) and wait for the user to tap one, returning its value.
+    ///
+    /// Each button's `callback_data` embeds a fresh uuid plus a one-byte tag
+    /// identifying its position in `options`; the poll loop in [`crate::bot`]
+    /// resolves the matching [`PendingSelection`] once the `CallbackQuery`
+    /// comes back. Resolves to an error if the prompt is abandoned (e.g. the
+    /// account disconnects) before a choice is made.
+    pub async fn prompt_choice(&self, account_id: &str, to: &str, text: &str, options: &[(String, String)]) -> Result<String> {
+        if options.len() > BASE62.len() {
+            anyhow::bail!("prompt_choice supports at most {} options, got {}", BASE62.len(), options.len());
+        }
+
+        let bot = self.get_bot(account_id)?;
+        let chat_id = ChatId(to.parse::<i64>()?);
+        let id = Uuid::new_v4();
+        let encoded_uuid = encode_base62(id.as_u128());
+
+        let buttons = options
+            .iter()
+            .enumerate()
+            .map(|(i, (label, _value))| {
+                let callback_data = format!("{encoded_uuid}{}", BASE62[i] as char);
+                vec![InlineKeyboardButton::callback(label.clone(), callback_data)]
+            })
+            .collect::<Vec<_>>();
+
+        let (tx, rx) = oneshot::channel();
+        let values = options.iter().map(|(_label, value)| value.clone()).collect();
+        {
+            let accounts = self.accounts.read().unwrap();
+            let state = accounts
+                .get(account_id)
+                .ok_or_else(|| anyhow::anyhow!("unknown account: {account_id}"))?;
+            state
+                .pending_selections
+                .lock()
+                .unwrap()
+                .insert(id, PendingSelection { sender: tx, values });
+        }
+
+        bot.send_message(chat_id, text)
+            .reply_markup(InlineKeyboardMarkup::new(buttons))
+            .await?;
+
+        rx.await.map_err(|_| anyhow::anyhow!("prompt {id} was abandoned before a choice was made"))
+    }
+
     /// Send a `ReplyPayload` — dispatches to text or media.
     pub async fn send_reply(
         &self,
@@ -118,27 +366,28 @@ impl TelegramOutbound {
         payload: &ReplyPayload,
     ) -> Result<()> {
         let chat_id = ChatId(to.parse::<i64>()?);
+        let reply_parameters = reply_parameters_for(payload);
+        let reply_markup = inline_keyboard_for(payload);
 
         // Send typing indicator
         let _ = bot.send_chat_action(chat_id, ChatAction::Typing).await;
 
-        if payload.media.is_some() {
+        if payload.media.is_some() || !payload.text.is_empty() {
             // Use the media path — but we need account_id, which we don't have here.
-            // For direct bot usage, delegate to send_text for now.
-            let html = markdown::markdown_to_telegram_html(&payload.text);
-            let chunks = markdown::chunk_message(&html, TELEGRAM_MAX_MESSAGE_LEN);
-            for chunk in chunks {
-                bot.send_message(chat_id, &chunk)
-                    .parse_mode(ParseMode::Html)
-                    .await?;
-            }
-        } else if !payload.text.is_empty() {
+            // For direct bot usage, delegate to sending the text only.
             let html = markdown::markdown_to_telegram_html(&payload.text);
             let chunks = markdown::chunk_message(&html, TELEGRAM_MAX_MESSAGE_LEN);
-            for chunk in chunks {
-                bot.send_message(chat_id, &chunk)
-                    .parse_mode(ParseMode::Html)
-                    .await?;
+            for (i, chunk) in chunks.iter().enumerate() {
+                let mut req = bot.send_message(chat_id, chunk).parse_mode(ParseMode::Html);
+                if i == 0 {
+                    if let Some(ref params) = reply_parameters {
+                        req = req.reply_parameters(params.clone());
+                    }
+                    if let Some(ref markup) = reply_markup {
+                        req = req.reply_markup(markup.clone());
+                    }
+                }
+                req.await?;
             }
         }
 
@@ -173,9 +422,17 @@ impl ChannelStreamOutbound for TelegramOutbound {
             .send_message(chat_id, "…")
             .parse_mode(ParseMode::Html)
             .await?;
-        let msg_id = placeholder.id;
+        let mut msg_id = placeholder.id;
 
         let mut accumulated = String::new();
+        // Byte offset into `accumulated` where the message currently being
+        // edited starts rendering from — advances each time a message fills
+        // up and we roll over to a fresh placeholder.
+        let mut window_start = 0usize;
+        // Last HTML actually sent for the current message, so an edit that
+        // would be byte-identical (which Telegram rejects as a no-op) is
+        // skipped instead of attempted.
+        let mut last_sent = String::new();
         let mut last_edit = tokio::time::Instant::now();
         let throttle = std::time::Duration::from_millis(throttle_ms);
 
@@ -184,17 +441,33 @@ impl ChannelStreamOutbound for TelegramOutbound {
                 StreamEvent::Delta(delta) => {
                     accumulated.push_str(&delta);
                     if last_edit.elapsed() >= throttle {
-                        let html = markdown::markdown_to_telegram_html(&accumulated);
-                        // Telegram rejects edits with identical content; truncate to limit.
-                        let display = if html.len() > TELEGRAM_MAX_MESSAGE_LEN {
-                            &html[..TELEGRAM_MAX_MESSAGE_LEN]
-                        } else {
-                            &html
-                        };
-                        let _ = bot
-                            .edit_message_text(chat_id, msg_id, display)
-                            .parse_mode(ParseMode::Html)
-                            .await;
+                        let html = markdown::markdown_to_telegram_html(&accumulated[window_start..]);
+                        if html.len() > TELEGRAM_MAX_MESSAGE_LEN {
+                            // Crossed the single-message limit mid-stream:
+                            // seal the current message with whatever fits
+                            // and start editing a fresh placeholder instead
+                            // of silently truncating until `Done`.
+                            let safe = safe_streaming_prefix(&html, TELEGRAM_MAX_MESSAGE_LEN);
+                            if safe != last_sent {
+                                let _ = bot
+                                    .edit_message_text(chat_id, msg_id, &safe)
+                                    .parse_mode(ParseMode::Html)
+                                    .await;
+                            }
+                            let placeholder = bot
+                                .send_message(chat_id, "…")
+                                .parse_mode(ParseMode::Html)
+                                .await?;
+                            msg_id = placeholder.id;
+                            window_start = accumulated.len();
+                            last_sent = String::new();
+                        } else if html != last_sent {
+                            let _ = bot
+                                .edit_message_text(chat_id, msg_id, &html)
+                                .parse_mode(ParseMode::Html)
+                                .await;
+                            last_sent = html;
+                        }
                         last_edit = tokio::time::Instant::now();
                     }
                 },
@@ -209,16 +482,19 @@ impl ChannelStreamOutbound for TelegramOutbound {
             }
         }
 
-        // Final edit with complete content
-        if !accumulated.is_empty() {
-            let html = markdown::markdown_to_telegram_html(&accumulated);
+        // Final edit with complete content for the current window.
+        if accumulated.len() > window_start {
+            let html = markdown::markdown_to_telegram_html(&accumulated[window_start..]);
             let chunks = markdown::chunk_message(&html, TELEGRAM_MAX_MESSAGE_LEN);
 
-            // Edit the placeholder with the first chunk
-            let _ = bot
-                .edit_message_text(chat_id, msg_id, &chunks[0])
-                .parse_mode(ParseMode::Html)
-                .await;
+            // Edit the placeholder with the first chunk, but only if it
+            // actually changed since the last throttled edit.
+            if chunks[0] != last_sent {
+                let _ = bot
+                    .edit_message_text(chat_id, msg_id, &chunks[0])
+                    .parse_mode(ParseMode::Html)
+                    .await;
+            }
 
             // Send remaining chunks as new messages
             for chunk in &chunks[1..] {
@@ -231,3 +507,55 @@ impl ChannelStreamOutbound for TelegramOutbound {
         Ok(())
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn under_limit_is_unchanged() {
+        let html = "<b>hello</b> world";
+        assert_eq!(safe_streaming_prefix(html, 100), html);
+    }
+
+    #[test]
+    fn cuts_on_char_boundary() {
+        let html = "héllo world";
+        // Cutting at byte 2 would land inside the 2-byte 'é'.
+        let out = safe_streaming_prefix(html, 2);
+        assert!(out.is_char_boundary(out.len()));
+    }
+
+    #[test]
+    fn backs_up_rather_than_cutting_mid_tag() {
+        let html = "<i>x</i>hello <b>world</b>";
+        // Cut lands inside the opening `<b>` tag itself.
+        let out = safe_streaming_prefix(html, 15);
+        assert_eq!(out, "<i>x</i>");
+    }
+
+    #[test]
+    fn closes_still_open_inline_tags() {
+        let html = "<b>bold <i>and italic</i> text</b>";
+        let out = safe_streaming_prefix(html, 19);
+        assert!(out.starts_with("<b>bold <i>"));
+        assert!(out.ends_with("</i></b>"));
+    }
+
+    #[test]
+    fn closes_nested_tags_in_reverse_order() {
+        let html = "<b><i>both open</i></b>";
+        let out = safe_streaming_prefix(html, 9);
+        assert_eq!(out, "<b><i>bot</i></b>");
+    }
+
+    #[test]
+    fn keeps_plain_text_before_a_dangling_tag_with_no_prior_close() {
+        // Cut lands right after `<b`, with no `>` anywhere in the window —
+        // the plain text before it must survive, not get wiped to "".
+        let html = "hello world <b>bold text</b>";
+        let out = safe_streaming_prefix(html, 14);
+        assert_eq!(out, "hello world ");
+    }
+}
@@ -1,9 +1,13 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
 use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 use moltis_channels::{ChannelEventSink, message_log::MessageLog};
 
@@ -12,6 +16,277 @@ use crate::{config::TelegramAccountConfig, outbound::TelegramOutbound};
 /// Shared account state map.
 pub type AccountStateMap = Arc<RwLock<HashMap<String, AccountState>>>;
 
+/// An inline-keyboard prompt awaiting the user's tap, keyed by the uuid
+/// embedded in every one of its buttons' `callback_data`.
+///
+/// `values` holds one entry per button, indexed by the single-byte choice
+/// tag embedded alongside the uuid — see
+/// [`crate::outbound::TelegramOutbound::prompt_choice`].
+pub struct PendingSelection {
+    /// Resolves with the chosen button's value once the matching
+    /// `CallbackQuery` arrives.
+    pub sender: oneshot::Sender<String>,
+    pub values: Vec<String>,
+}
+
+/// Registry of in-flight inline-keyboard prompts for one account, so a
+/// `CallbackQuery` update can be routed back to whichever `prompt_choice`
+/// call is awaiting it.
+pub type PendingSelections = Arc<Mutex<HashMap<Uuid, PendingSelection>>>;
+
+/// Durable storage for the `getUpdates` offset and per-chat dialogue state,
+/// so a restart neither replays nor drops queued updates and handlers can
+/// keep track of where a multi-step conversation left off. Mirrors the
+/// `ChannelFilterStore` pattern used for pub/sub channel filters (see
+/// `crate::channel_filters` in the gateway crate).
+#[async_trait]
+pub trait DialogueStore: Send + Sync {
+    /// The last `getUpdates` offset acknowledged for `account_id`, if any
+    /// was ever persisted.
+    async fn load_offset(&self, account_id: &str) -> anyhow::Result<Option<i32>>;
+
+    /// Persist the `getUpdates` offset to resume from after a restart.
+    async fn save_offset(&self, account_id: &str, offset: i32) -> anyhow::Result<()>;
+
+    /// Load the dialogue state for one chat, if any was ever persisted.
+    /// Stored as opaque JSON since each handler defines its own state shape.
+    async fn load_dialogue(&self, account_id: &str, chat_id: &str) -> anyhow::Result<Option<serde_json::Value>>;
+
+    /// Upsert the dialogue state for one chat.
+    async fn save_dialogue(&self, account_id: &str, chat_id: &str, state: &serde_json::Value) -> anyhow::Result<()>;
+
+    /// Clear a chat's dialogue state, e.g. once a multi-step flow completes.
+    async fn clear_dialogue(&self, account_id: &str, chat_id: &str) -> anyhow::Result<()>;
+}
+
+/// In-memory [`DialogueStore`] for accounts run without persistence (e.g.
+/// tests) — state is lost on restart.
+#[derive(Default)]
+pub struct InMemoryDialogueStore {
+    offsets: Mutex<HashMap<String, i32>>,
+    dialogues: Mutex<HashMap<(String, String), serde_json::Value>>,
+}
+
+#[async_trait]
+impl DialogueStore for InMemoryDialogueStore {
+    async fn load_offset(&self, account_id: &str) -> anyhow::Result<Option<i32>> {
+        Ok(self.offsets.lock().unwrap().get(account_id).copied())
+    }
+
+    async fn save_offset(&self, account_id: &str, offset: i32) -> anyhow::Result<()> {
+        self.offsets.lock().unwrap().insert(account_id.to_string(), offset);
+        Ok(())
+    }
+
+    async fn load_dialogue(&self, account_id: &str, chat_id: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        Ok(self
+            .dialogues
+            .lock()
+            .unwrap()
+            .get(&(account_id.to_string(), chat_id.to_string()))
+            .cloned())
+    }
+
+    async fn save_dialogue(&self, account_id: &str, chat_id: &str, state: &serde_json::Value) -> anyhow::Result<()> {
+        self.dialogues
+            .lock()
+            .unwrap()
+            .insert((account_id.to_string(), chat_id.to_string()), state.clone());
+        Ok(())
+    }
+
+    async fn clear_dialogue(&self, account_id: &str, chat_id: &str) -> anyhow::Result<()> {
+        self.dialogues
+            .lock()
+            .unwrap()
+            .remove(&(account_id.to_string(), chat_id.to_string()));
+        Ok(())
+    }
+}
+
+/// SQLite-backed [`DialogueStore`], sharing the same database as the
+/// gateway's credential store rather than opening a dedicated file.
+pub struct SqliteDialogueStore {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteDialogueStore {
+    /// Wrap an existing pool (e.g. the credential store's) and create this
+    /// store's tables if they don't already exist.
+    pub async fn new(pool: sqlx::SqlitePool) -> anyhow::Result<Self> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS telegram_update_offsets (
+                account_id TEXT PRIMARY KEY,
+                offset INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS telegram_dialogue_state (
+                account_id TEXT NOT NULL,
+                chat_id TEXT NOT NULL,
+                state TEXT NOT NULL,
+                PRIMARY KEY (account_id, chat_id)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl DialogueStore for SqliteDialogueStore {
+    async fn load_offset(&self, account_id: &str) -> anyhow::Result<Option<i32>> {
+        let row: Option<(i64,)> = sqlx::query_as("SELECT offset FROM telegram_update_offsets WHERE account_id = ?")
+            .bind(account_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|(offset,)| offset as i32))
+    }
+
+    async fn save_offset(&self, account_id: &str, offset: i32) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO telegram_update_offsets (account_id, offset) VALUES (?, ?)
+             ON CONFLICT(account_id) DO UPDATE SET offset = excluded.offset",
+        )
+        .bind(account_id)
+        .bind(offset as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_dialogue(&self, account_id: &str, chat_id: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT state FROM telegram_dialogue_state WHERE account_id = ? AND chat_id = ?")
+                .bind(account_id)
+                .bind(chat_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|(state,)| serde_json::from_str(&state)).transpose()?)
+    }
+
+    async fn save_dialogue(&self, account_id: &str, chat_id: &str, state: &serde_json::Value) -> anyhow::Result<()> {
+        let serialized = serde_json::to_string(state)?;
+        sqlx::query(
+            "INSERT INTO telegram_dialogue_state (account_id, chat_id, state) VALUES (?, ?, ?)
+             ON CONFLICT(account_id, chat_id) DO UPDATE SET state = excluded.state",
+        )
+        .bind(account_id)
+        .bind(chat_id)
+        .bind(serialized)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn clear_dialogue(&self, account_id: &str, chat_id: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM telegram_dialogue_state WHERE account_id = ? AND chat_id = ?")
+            .bind(account_id)
+            .bind(chat_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Runtime overrides for one chat, recorded from inside the chat
+/// (`/enable`, `/disable`, `/model`) and persisted without editing config or
+/// restarting. This crate stores them and returns them from
+/// [`load_overrides`]; its message gating and model selection do not read
+/// them, so the account's static [`TelegramAccountConfig`] still decides
+/// both. `None` fields mean no override was recorded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatOverrides {
+    /// `Some(true)` after `/enable`, `Some(false)` after `/disable`, `None`
+    /// if neither has been run in this chat.
+    pub enabled: Option<bool>,
+    /// Model id recorded by `/model` for this chat, if any.
+    pub model: Option<String>,
+}
+
+/// Durable storage for per-chat runtime overrides. Mirrors the
+/// `ChannelFilterStore` pattern used for pub/sub channel filters (see
+/// `crate::channel_filters` in the gateway crate) and this crate's own
+/// [`DialogueStore`].
+#[async_trait]
+pub trait ChatOverrideStore: Send + Sync {
+    /// List every chat with a persisted override record for `account_id`.
+    async fn list_overrides(&self, account_id: &str) -> anyhow::Result<Vec<(String, ChatOverrides)>>;
+
+    /// Look up the persisted override record for one chat, if any.
+    async fn get_override(&self, account_id: &str, chat_id: &str) -> anyhow::Result<Option<ChatOverrides>>;
+
+    /// Upsert the override record for one chat.
+    async fn set_override(&self, account_id: &str, chat_id: &str, overrides: &ChatOverrides) -> anyhow::Result<()>;
+}
+
+/// In-memory [`ChatOverrideStore`] for accounts run without persistence
+/// (e.g. tests) — state is lost on restart.
+#[derive(Default)]
+pub struct InMemoryChatOverrideStore {
+    overrides: Mutex<HashMap<(String, String), ChatOverrides>>,
+}
+
+#[async_trait]
+impl ChatOverrideStore for InMemoryChatOverrideStore {
+    async fn list_overrides(&self, account_id: &str) -> anyhow::Result<Vec<(String, ChatOverrides)>> {
+        Ok(self
+            .overrides
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((aid, _), _)| aid == account_id)
+            .map(|((_, chat_id), overrides)| (chat_id.clone(), overrides.clone()))
+            .collect())
+    }
+
+    async fn get_override(&self, account_id: &str, chat_id: &str) -> anyhow::Result<Option<ChatOverrides>> {
+        Ok(self
+            .overrides
+            .lock()
+            .unwrap()
+            .get(&(account_id.to_string(), chat_id.to_string()))
+            .cloned())
+    }
+
+    async fn set_override(&self, account_id: &str, chat_id: &str, overrides: &ChatOverrides) -> anyhow::Result<()> {
+        self.overrides
+            .lock()
+            .unwrap()
+            .insert((account_id.to_string(), chat_id.to_string()), overrides.clone());
+        Ok(())
+    }
+}
+
+/// Load a chat's persisted overrides, falling back to the default (no
+/// overrides — static config applies unchanged) when no store is configured
+/// or no record exists yet.
+pub async fn load_overrides(store: Option<&dyn ChatOverrideStore>, account_id: &str, chat_id: &str) -> ChatOverrides {
+    let Some(store) = store else {
+        return ChatOverrides::default();
+    };
+    store.get_override(account_id, chat_id).await.ok().flatten().unwrap_or_default()
+}
+
+/// Get-or-insert-default a chat's override record, apply `mutate` to it, and
+/// persist the result — the pattern behind `/enable`, `/disable`, and
+/// `/model` so the first command a chat ever receives seeds a sensible
+/// default record instead of requiring one to already exist.
+pub async fn upsert_override(
+    store: &dyn ChatOverrideStore,
+    account_id: &str,
+    chat_id: &str,
+    mutate: impl FnOnce(&mut ChatOverrides),
+) -> anyhow::Result<ChatOverrides> {
+    let mut overrides = store.get_override(account_id, chat_id).await?.unwrap_or_default();
+    mutate(&mut overrides);
+    store.set_override(account_id, chat_id, &overrides).await?;
+    Ok(overrides)
+}
+
 /// Per-account runtime state.
 pub struct AccountState {
     pub bot: teloxide::Bot,
@@ -22,4 +297,7 @@ pub struct AccountState {
     pub cancel: CancellationToken,
     pub message_log: Option<Arc<dyn MessageLog>>,
     pub event_sink: Option<Arc<dyn ChannelEventSink>>,
+    pub pending_selections: PendingSelections,
+    pub dialogue_store: Option<Arc<dyn DialogueStore>>,
+    pub chat_overrides: Option<Arc<dyn ChatOverrideStore>>,
 }
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use moltis_channels::gating::{self, DmPolicy, GroupPolicy};
 
 use crate::config::WhatsAppAccountConfig;
@@ -7,17 +9,24 @@ use crate::config::WhatsAppAccountConfig;
 /// Returns `Ok(())` if the message is allowed, or `Err(reason)` if it should
 /// be denied. WhatsApp does not have @mention semantics like Telegram bots,
 /// so there is no `MentionMode` gating.
+///
+/// `dm_blocklist`/`group_blocklist` are checked before the `dm_policy`/
+/// `group_policy` decision, so a blocklist hit denies a peer or group even
+/// under an `Open` policy. `allowed_domains`/`blocked_domains` are checked
+/// right after, gating on the JID domain (the part after `@`, e.g.
+/// `s.whatsapp.net`, `g.us`, `lid`, `newsletter`) rather than the full JID.
 pub fn check_access(
     config: &WhatsAppAccountConfig,
     is_group: bool,
     peer_id: &str,
     username: Option<&str>,
     group_id: Option<&str>,
+    is_verified: Option<&dyn Fn(&str) -> bool>,
 ) -> Result<(), AccessDenied> {
     if is_group {
-        check_group_access(config, group_id)
+        check_group_access(config, group_id, peer_id, username)
     } else {
-        check_dm_access(config, peer_id, username)
+        check_dm_access(config, peer_id, username, is_verified)
     }
 }
 
@@ -25,34 +34,125 @@ fn check_dm_access(
     config: &WhatsAppAccountConfig,
     peer_id: &str,
     username: Option<&str>,
+    is_verified: Option<&dyn Fn(&str) -> bool>,
 ) -> Result<(), AccessDenied> {
+    match gate_dm_access(config, peer_id, username, None, is_verified) {
+        GateDecision::Allow => Ok(()),
+        GateDecision::Deny(reason) => Err(reason),
+        GateDecision::Pending(pending) => Err(AccessDenied::Pending(pending)),
+    }
+}
+
+/// A DM sender that's neither allow- nor deny-listed yet, queued under
+/// `DmPolicy::Prompt` for an operator (or `on_pending`) to decide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingContact {
+    pub peer_id: String,
+    pub username: Option<String>,
+}
+
+/// What an `on_pending` callback decides to do with a [`PendingContact`],
+/// mirroring Deno's permission-prompt callback: allow, deny, or defer
+/// (leave it queued for an operator to act on later).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptResponse {
+    Approve,
+    Deny,
+    Defer,
+}
+
+/// Callback fired for a `DmPolicy::Prompt` sender not already on the
+/// allowlist, letting an embedder auto-approve, auto-deny, or defer to an
+/// operator instead of always queuing as [`GateDecision::Pending`].
+pub type OnPending = Arc<dyn Fn(&PendingContact) -> PromptResponse + Send + Sync>;
+
+/// Tri-state gating outcome: the binary `Allow`/`Deny` of [`check_access`]
+/// plus `Pending` for a `DmPolicy::Prompt` sender awaiting approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GateDecision {
+    Allow,
+    Deny(AccessDenied),
+    Pending(PendingContact),
+}
+
+/// Like [`check_access`] for DMs, but with a third `Pending` outcome for
+/// `DmPolicy::Prompt`.
+///
+/// A peer (or username) already on `allowlist` always short-circuits to
+/// `Allow` — regardless of `dm_policy`, including `Prompt` — so the prompt
+/// only ever fires for genuinely unknown senders, never for already-known
+/// ones. `on_pending`, if given, gets a chance to auto-approve or auto-deny
+/// a `Prompt` sender instead of queuing them; returning
+/// [`PromptResponse::Defer`] (or passing `None`) queues as `Pending`.
+pub fn gate_dm_access(
+    config: &WhatsAppAccountConfig,
+    peer_id: &str,
+    username: Option<&str>,
+    on_pending: Option<&OnPending>,
+    is_verified: Option<&dyn Fn(&str) -> bool>,
+) -> GateDecision {
+    if gating::is_allowed(peer_id, &config.dm_blocklist)
+        || username.is_some_and(|u| gating::is_allowed(u, &config.dm_blocklist))
+    {
+        return GateDecision::Deny(AccessDenied::Blocked);
+    }
+
+    if let Err(reason) = check_domain(jid_domain(peer_id), config) {
+        return GateDecision::Deny(reason);
+    }
+
+    let name_allowed = gating::is_allowed(peer_id, &config.allowlist)
+        || username.is_some_and(|u| gating::is_allowed(u, &config.allowlist));
+    if name_allowed {
+        if config.require_verified_allowlist && !is_verified.is_some_and(|f| f(peer_id)) {
+            return GateDecision::Deny(AccessDenied::NotVerified);
+        }
+        return GateDecision::Allow;
+    }
+
     match config.dm_policy {
-        DmPolicy::Disabled => Err(AccessDenied::DmsDisabled),
-        DmPolicy::Open => Ok(()),
-        DmPolicy::Allowlist => {
-            // An empty allowlist with an explicit Allowlist policy means
-            // "deny everyone" â€” not "allow everyone".
-            if config.allowlist.is_empty() {
-                return Err(AccessDenied::NotOnAllowlist);
-            }
-            if gating::is_allowed(peer_id, &config.allowlist)
-                || username.is_some_and(|u| gating::is_allowed(u, &config.allowlist))
-            {
-                Ok(())
-            } else {
-                Err(AccessDenied::NotOnAllowlist)
+        DmPolicy::Disabled => GateDecision::Deny(AccessDenied::DmsDisabled),
+        DmPolicy::Open => GateDecision::Allow,
+        // Already confirmed above not to be on the allowlist, so this is
+        // always a deny regardless of whether the allowlist is empty.
+        DmPolicy::Allowlist => GateDecision::Deny(AccessDenied::NotOnAllowlist),
+        DmPolicy::Prompt => {
+            let pending = PendingContact {
+                peer_id: peer_id.to_string(),
+                username: username.map(str::to_string),
+            };
+            match on_pending.map(|f| f(&pending)) {
+                Some(PromptResponse::Approve) => GateDecision::Allow,
+                Some(PromptResponse::Deny) => GateDecision::Deny(AccessDenied::NotOnAllowlist),
+                Some(PromptResponse::Defer) | None => GateDecision::Pending(pending),
             }
         },
     }
 }
 
+/// `peer_id`/`username` identify the message sender within the group —
+/// only needed for `GroupPolicy::MemberOnly`'s per-sender gate, but taken
+/// unconditionally so the group-level and sender-level checks stay in one
+/// place.
 fn check_group_access(
     config: &WhatsAppAccountConfig,
     group_id: Option<&str>,
+    peer_id: &str,
+    username: Option<&str>,
 ) -> Result<(), AccessDenied> {
+    if group_id.is_some_and(|gid| gating::is_allowed(gid, &config.group_blocklist)) {
+        return Err(AccessDenied::Blocked);
+    }
+
+    check_domain(group_id.map(jid_domain).unwrap_or(""), config)?;
+
     match config.group_policy {
         GroupPolicy::Disabled => Err(AccessDenied::GroupsDisabled),
-        GroupPolicy::Allowlist => {
+        // `AutoJoin` serves the same groups `Allowlist` does — the groups
+        // the bot has auto-joined via `crate::group_autojoin` land in
+        // `group_allowlist` the same way a manually-allowlisted group
+        // would, so the same check applies here.
+        GroupPolicy::Allowlist | GroupPolicy::AutoJoin => {
             let gid = group_id.unwrap_or("");
             if config.group_allowlist.is_empty()
                 || !gating::is_allowed(gid, &config.group_allowlist)
@@ -63,9 +163,53 @@ fn check_group_access(
             }
         },
         GroupPolicy::Open => Ok(()),
+        GroupPolicy::MemberOnly => {
+            // Group-level gate: like `Allowlist`, but only enforced if a
+            // group_allowlist was actually configured — composable with
+            // `Allowlist` semantics for operators who want both, while
+            // defaulting to "any group" (like `Open`) otherwise.
+            let gid = group_id.unwrap_or("");
+            if !config.group_allowlist.is_empty() && !gating::is_allowed(gid, &config.group_allowlist) {
+                return Err(AccessDenied::GroupNotOnAllowlist);
+            }
+
+            // Sender-level gate: an empty member_users with an explicit
+            // MemberOnly policy means "no enrolled members yet" — deny
+            // everyone, same invariant as an empty allowlist elsewhere.
+            if config.member_users.is_empty() {
+                return Err(AccessDenied::NotAMember);
+            }
+            if gating::is_allowed(peer_id, &config.member_users)
+                || username.is_some_and(|u| gating::is_allowed(u, &config.member_users))
+            {
+                Ok(())
+            } else {
+                Err(AccessDenied::NotAMember)
+            }
+        },
     }
 }
 
+/// Extract the domain (the part after `@`) from a WhatsApp JID, e.g.
+/// `s.whatsapp.net` from `15551234567@s.whatsapp.net`, `g.us` from a group
+/// JID, or `lid`/`newsletter` from those entity kinds. Returns `""` if
+/// `jid` has no `@`.
+fn jid_domain(jid: &str) -> &str {
+    jid.split_once('@').map_or("", |(_, domain)| domain)
+}
+
+/// Gate a JID's domain against `allowed_domains`/`blocked_domains`.
+/// `blocked_domains` takes priority: a domain on both lists is denied.
+fn check_domain(domain: &str, config: &WhatsAppAccountConfig) -> Result<(), AccessDenied> {
+    if !config.blocked_domains.is_empty() && gating::is_allowed(domain, &config.blocked_domains) {
+        return Err(AccessDenied::DomainBlocked);
+    }
+    if !config.allowed_domains.is_empty() && !gating::is_allowed(domain, &config.allowed_domains) {
+        return Err(AccessDenied::DomainBlocked);
+    }
+    Ok(())
+}
+
 /// Reason an inbound message was denied.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AccessDenied {
@@ -73,6 +217,26 @@ pub enum AccessDenied {
     NotOnAllowlist,
     GroupsDisabled,
     GroupNotOnAllowlist,
+    /// Peer, username, or group matched `dm_blocklist`/`group_blocklist`.
+    /// Takes priority over the policy check, so it denies even under an
+    /// `Open` policy.
+    Blocked,
+    /// The JID's domain (the part after `@`) is not in `allowed_domains`,
+    /// or is explicitly listed in `blocked_domains`.
+    DomainBlocked,
+    /// Carries the same information as [`GateDecision::Pending`], for
+    /// callers that only see the binary [`check_access`] result.
+    Pending(PendingContact),
+    /// Sender tried to run an admin command (see [`crate::commands`]) but
+    /// isn't in `admin_users`.
+    NotAdmin,
+    /// Group is permitted (e.g. under `GroupPolicy::MemberOnly`) but the
+    /// sender isn't in `member_users`.
+    NotAMember,
+    /// `require_verified_allowlist` is set and the peer matched `allowlist`
+    /// by name but hasn't completed the [`crate::verified_join`] handshake
+    /// (or its identity key changed since it did).
+    NotVerified,
 }
 
 impl std::fmt::Display for AccessDenied {
@@ -82,6 +246,12 @@ impl std::fmt::Display for AccessDenied {
             Self::NotOnAllowlist => write!(f, "user not on allowlist"),
             Self::GroupsDisabled => write!(f, "groups are disabled"),
             Self::GroupNotOnAllowlist => write!(f, "group not on allowlist"),
+            Self::Blocked => write!(f, "peer or group is blocklisted"),
+            Self::DomainBlocked => write!(f, "JID domain is not accepted"),
+            Self::Pending(_) => write!(f, "sender is awaiting approval"),
+            Self::NotAdmin => write!(f, "sender is not an admin"),
+            Self::NotAMember => write!(f, "sender is not an enrolled group member"),
+            Self::NotVerified => write!(f, "peer has not completed the verified-join handshake"),
         }
     }
 }
@@ -190,6 +360,232 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dm_blocklist_overrides_open_policy() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Open;
+        c.dm_blocklist = vec!["15551234567".into()];
+        assert_eq!(
+            check_access(&c, false, "15551234567", None, None, None),
+            Err(AccessDenied::Blocked)
+        );
+        assert!(check_access(&c, false, "15559999999", None, None, None).is_ok());
+    }
+
+    #[test]
+    fn dm_blocklist_matches_by_username() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Open;
+        c.dm_blocklist = vec!["spammer".into()];
+        assert_eq!(
+            check_access(&c, false, "15551234567@s.whatsapp.net", Some("spammer"), None, None),
+            Err(AccessDenied::Blocked)
+        );
+    }
+
+    #[test]
+    fn group_blocklist_overrides_open_policy() {
+        let mut c = cfg();
+        c.group_policy = GroupPolicy::Open;
+        c.group_blocklist = vec!["grp-banned".into()];
+        assert_eq!(
+            check_access(&c, true, "user", None, Some("grp-banned"), None),
+            Err(AccessDenied::Blocked)
+        );
+        assert!(check_access(&c, true, "user", None, Some("grp-ok"), None).is_ok());
+    }
+
+    #[test]
+    fn blocklist_checked_before_allowlist() {
+        // A blocked peer must stay blocked even if it's also allowlisted.
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Allowlist;
+        c.allowlist = vec!["15551234567".into()];
+        c.dm_blocklist = vec!["15551234567".into()];
+        assert_eq!(
+            check_access(&c, false, "15551234567", None, None, None),
+            Err(AccessDenied::Blocked)
+        );
+    }
+
+    #[test]
+    fn member_only_allows_enrolled_members() {
+        let mut c = cfg();
+        c.group_policy = GroupPolicy::MemberOnly;
+        c.member_users = vec!["alice".into()];
+        assert!(check_access(&c, true, "15551234567", Some("alice"), Some("grp1"), None).is_ok());
+    }
+
+    #[test]
+    fn member_only_rejects_non_members_in_an_otherwise_open_group() {
+        let mut c = cfg();
+        c.group_policy = GroupPolicy::MemberOnly;
+        c.member_users = vec!["alice".into()];
+        assert_eq!(
+            check_access(&c, true, "15559999999", Some("bob"), Some("grp1"), None),
+            Err(AccessDenied::NotAMember)
+        );
+    }
+
+    #[test]
+    fn member_only_denies_all_with_empty_member_users() {
+        let mut c = cfg();
+        c.group_policy = GroupPolicy::MemberOnly;
+        assert_eq!(
+            check_access(&c, true, "15551234567", Some("alice"), Some("grp1"), None),
+            Err(AccessDenied::NotAMember)
+        );
+    }
+
+    #[test]
+    fn member_only_composes_with_group_allowlist() {
+        let mut c = cfg();
+        c.group_policy = GroupPolicy::MemberOnly;
+        c.group_allowlist = vec!["grp1".into()];
+        c.member_users = vec!["alice".into()];
+        // Enrolled member, but in a group that isn't on the group_allowlist.
+        assert_eq!(
+            check_access(&c, true, "15551234567", Some("alice"), Some("grp2"), None),
+            Err(AccessDenied::GroupNotOnAllowlist)
+        );
+        // Enrolled member, allowed group: passes both gates.
+        assert!(check_access(&c, true, "15551234567", Some("alice"), Some("grp1"), None).is_ok());
+        // Allowed group, but non-member: still rejected.
+        assert_eq!(
+            check_access(&c, true, "15559999999", Some("bob"), Some("grp1"), None),
+            Err(AccessDenied::NotAMember)
+        );
+    }
+
+    #[test]
+    fn prompt_queues_unknown_sender_with_no_callback() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Prompt;
+        assert_eq!(
+            gate_dm_access(&c, "15551234567", None, None, None),
+            GateDecision::Pending(PendingContact {
+                peer_id: "15551234567".into(),
+                username: None,
+            })
+        );
+    }
+
+    #[test]
+    fn prompt_short_circuits_known_allowlisted_peer() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Prompt;
+        c.allowlist = vec!["15551234567".into()];
+        let on_pending: OnPending = Arc::new(|_| panic!("prompt must not fire for an allowlisted peer"));
+        assert_eq!(
+            gate_dm_access(&c, "15551234567", None, Some(&on_pending), None),
+            GateDecision::Allow
+        );
+    }
+
+    #[test]
+    fn prompt_callback_can_auto_approve() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Prompt;
+        let on_pending: OnPending = Arc::new(|_| PromptResponse::Approve);
+        assert_eq!(
+            gate_dm_access(&c, "15551234567", None, Some(&on_pending), None),
+            GateDecision::Allow
+        );
+    }
+
+    #[test]
+    fn prompt_callback_can_auto_deny() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Prompt;
+        let on_pending: OnPending = Arc::new(|_| PromptResponse::Deny);
+        assert_eq!(
+            gate_dm_access(&c, "15551234567", None, Some(&on_pending), None),
+            GateDecision::Deny(AccessDenied::NotOnAllowlist)
+        );
+    }
+
+    #[test]
+    fn prompt_callback_can_defer_to_pending() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Prompt;
+        let on_pending: OnPending = Arc::new(|_| PromptResponse::Defer);
+        assert_eq!(
+            gate_dm_access(&c, "15551234567", Some("alice"), Some(&on_pending), None),
+            GateDecision::Pending(PendingContact {
+                peer_id: "15551234567".into(),
+                username: Some("alice".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn prompt_still_denies_blocklisted_sender() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Prompt;
+        c.dm_blocklist = vec!["15551234567".into()];
+        assert_eq!(
+            gate_dm_access(&c, "15551234567", None, None, None),
+            GateDecision::Deny(AccessDenied::Blocked)
+        );
+    }
+
+    #[test]
+    fn allowed_domains_accepts_listed_suffix() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Open;
+        c.allowed_domains = vec!["s.whatsapp.net".into()];
+        assert!(check_access(&c, false, "15551234567@s.whatsapp.net", None, None, None).is_ok());
+    }
+
+    #[test]
+    fn allowed_domains_rejects_other_suffixes() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Open;
+        c.allowed_domains = vec!["s.whatsapp.net".into()];
+        for jid in ["123@lid", "123@newsletter", "123@g.us"] {
+            assert_eq!(
+                check_access(&c, false, jid, None, None, None),
+                Err(AccessDenied::DomainBlocked),
+                "jid {jid} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn blocked_domains_rejects_listed_suffix() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Open;
+        c.blocked_domains = vec!["newsletter".into()];
+        assert_eq!(
+            check_access(&c, false, "123@newsletter", None, None, None),
+            Err(AccessDenied::DomainBlocked)
+        );
+        assert!(check_access(&c, false, "15551234567@s.whatsapp.net", None, None, None).is_ok());
+    }
+
+    #[test]
+    fn blocked_domains_take_priority_over_allowed_domains() {
+        let mut c = cfg();
+        c.dm_policy = DmPolicy::Open;
+        c.allowed_domains = vec!["g.us".into()];
+        c.blocked_domains = vec!["g.us".into()];
+        assert_eq!(
+            check_access(&c, false, "123@g.us", None, None, None),
+            Err(AccessDenied::DomainBlocked)
+        );
+    }
+
+    #[test]
+    fn group_domain_scoping_uses_group_id() {
+        let mut c = cfg();
+        c.allowed_domains = vec!["g.us".into()];
+        assert!(check_access(&c, true, "user", None, Some("grp1@g.us"), None).is_ok());
+        assert_eq!(
+            check_access(&c, true, "user", None, Some("grp1@lid"), None),
+            Err(AccessDenied::DomainBlocked)
+        );
+    }
+
     /// Security regression: removing the last entry from an allowlist must
     /// NOT silently switch to open access.
     #[test]
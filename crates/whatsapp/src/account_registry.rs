@@ -0,0 +1,165 @@
+//! Account-manager layer over [`crate::sled_store`].
+//!
+//! `SledStore` knows how to open a single account's store at
+//! `<data_dir>/whatsapp/<account_id>/`, but nothing previously enumerated
+//! which account ids exist, or let one be moved to another machine. This
+//! module adds that: [`list_accounts`] walks the per-account directories,
+//! and [`export_account`]/[`import_account`] wrap
+//! [`crate::sled_store::Store::export_archive`]/`import_archive` in a
+//! versioned file format that can be written to disk or piped elsewhere.
+
+use std::path::{Path, PathBuf};
+
+use crate::sled_store::SledStore;
+
+/// The first byte of every exported archive file — bumped alongside
+/// [`crate::sled_store`]'s internal schema version whenever the archive
+/// envelope itself (not just the tree contents) changes shape.
+const ARCHIVE_FORMAT_VERSION: u8 = 1;
+
+/// Enumerate the account ids with a store directory under
+/// `<data_dir>/whatsapp/`. Returns an empty list if that directory doesn't
+/// exist yet (e.g. no WhatsApp account has ever been linked).
+pub fn list_accounts(data_dir: &Path) -> std::io::Result<Vec<String>> {
+    let whatsapp_dir = data_dir.join("whatsapp");
+    if !whatsapp_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut accounts = Vec::new();
+    for entry in std::fs::read_dir(&whatsapp_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+            && let Some(name) = entry.file_name().to_str()
+        {
+            accounts.push(name.to_string());
+        }
+    }
+    accounts.sort();
+    Ok(accounts)
+}
+
+fn store_path(data_dir: &Path, account_id: &str) -> PathBuf {
+    data_dir.join("whatsapp").join(account_id)
+}
+
+/// Export `account_id`'s entire store (every Signal Protocol tree, the
+/// `device_id` counter, and the registered `Device`) as one portable
+/// archive: `[ARCHIVE_FORMAT_VERSION][json-encoded AccountArchive]`.
+pub fn export_account(data_dir: &Path, account_id: &str) -> crate::Result<Vec<u8>> {
+    let store = SledStore::open(store_path(data_dir, account_id)).map_err(|e| crate::Error::Store {
+        message: format!("failed to open store for account {account_id}: {e}"),
+    })?;
+    let archive = store.export_archive().map_err(|e| crate::Error::Store {
+        message: format!("failed to export account {account_id}: {e}"),
+    })?;
+
+    let payload = serde_json::to_vec(&archive).map_err(|e| crate::Error::Store {
+        message: format!("failed to serialize archive for account {account_id}: {e}"),
+    })?;
+
+    let mut out = Vec::with_capacity(1 + payload.len());
+    out.push(ARCHIVE_FORMAT_VERSION);
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Import an archive produced by [`export_account`] into `account_id`'s
+/// store, creating it if it doesn't already exist.
+pub fn import_account(data_dir: &Path, account_id: &str, archive_bytes: &[u8]) -> crate::Result<()> {
+    let (&format_version, payload) = archive_bytes.split_first().ok_or_else(|| crate::Error::Store {
+        message: "empty account archive".into(),
+    })?;
+    if format_version != ARCHIVE_FORMAT_VERSION {
+        return Err(crate::Error::Store {
+            message: format!(
+                "archive format version {format_version} is not supported by this build (expected {ARCHIVE_FORMAT_VERSION})"
+            ),
+        });
+    }
+
+    let archive = serde_json::from_slice(payload).map_err(|e| crate::Error::Store {
+        message: format!("failed to parse account archive: {e}"),
+    })?;
+
+    let store = SledStore::open(store_path(data_dir, account_id)).map_err(|e| crate::Error::Store {
+        message: format!("failed to open store for account {account_id}: {e}"),
+    })?;
+    store.import_archive(archive).map_err(|e| crate::Error::Store {
+        message: format!("failed to import account {account_id}: {e}"),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn list_accounts_enumerates_store_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list_accounts(dir.path()).unwrap().is_empty());
+
+        SledStore::open(store_path(dir.path(), "account-a")).unwrap();
+        SledStore::open(store_path(dir.path(), "account-b")).unwrap();
+
+        let mut accounts = list_accounts(dir.path()).unwrap();
+        accounts.sort();
+        assert_eq!(accounts, vec!["account-a", "account-b"]);
+    }
+
+    #[tokio::test]
+    async fn export_import_roundtrip_preserves_every_tree() {
+        use wacore::store::traits::{DeviceStore, ProtocolStore, SignalStore};
+
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let store = SledStore::open(store_path(dir.path(), "alice")).unwrap();
+            store.put_identity("peer@s.whatsapp.net", [5u8; 32]).await.unwrap();
+            store.put_session("peer@s.whatsapp.net", b"session-bytes").await.unwrap();
+            store.store_prekey(1, b"prekey-1", false).await.unwrap();
+            store.store_signed_prekey(1, b"signed-prekey-1").await.unwrap();
+            store.save_base_key("peer@s.whatsapp.net", "msg-1", b"base-key").await.unwrap();
+            let id = store.create().await.unwrap();
+            assert_eq!(id, 0);
+        }
+
+        let archive_bytes = export_account(dir.path(), "alice").unwrap();
+
+        let fresh_dir = tempfile::tempdir().unwrap();
+        import_account(fresh_dir.path(), "alice", &archive_bytes).unwrap();
+
+        let restored = SledStore::open(store_path(fresh_dir.path(), "alice")).unwrap();
+        assert_eq!(
+            restored.load_identity("peer@s.whatsapp.net").await.unwrap(),
+            Some(vec![5u8; 32])
+        );
+        assert_eq!(
+            restored.get_session("peer@s.whatsapp.net").await.unwrap(),
+            Some(b"session-bytes".to_vec())
+        );
+        assert_eq!(restored.load_prekey(1).await.unwrap(), Some(b"prekey-1".to_vec()));
+        assert_eq!(
+            restored.load_signed_prekey(1).await.unwrap(),
+            Some(b"signed-prekey-1".to_vec())
+        );
+        assert!(
+            restored
+                .has_same_base_key("peer@s.whatsapp.net", "msg-1", b"base-key")
+                .await
+                .unwrap()
+        );
+        // The device_id counter was imported too, so the next `create()`
+        // continues from where the source account left off rather than
+        // restarting at 0.
+        assert_eq!(restored.create().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn import_rejects_unknown_format_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = import_account(dir.path(), "alice", &[99, b'{', b'}']).unwrap_err();
+        assert!(matches!(err, crate::Error::Store { .. }));
+    }
+}
@@ -0,0 +1,119 @@
+//! UTF-8-safe splitting of long message bodies.
+//!
+//! WhatsApp rejects or truncates overly long message bodies. `str_chunks`
+//! walks a string and yields byte-bounded slices that never split a UTF-8
+//! code point, preferring to break on the last whitespace boundary before
+//! the limit so markdown/emoji sequences survive intact across chunks.
+
+/// Maximum body size (in bytes) WhatsApp reliably accepts in one message.
+pub const MAX_CHUNK_BYTES: usize = 4000;
+
+/// Split `text` into a sequence of slices, each at most `max_bytes` long,
+/// never splitting a UTF-8 code point.
+///
+/// Prefers to break on the last whitespace boundary before `max_bytes`, only
+/// falling back to a hard cut (still UTF-8-safe) when a single token exceeds
+/// the limit. Returns a single-element vec (even for the empty string) when
+/// `text` already fits.
+pub fn str_chunks(text: &str, max_bytes: usize) -> Vec<&str> {
+    assert!(max_bytes > 0, "max_bytes must be positive");
+
+    if text.len() <= max_bytes {
+        return vec![text];
+    }
+
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_bytes {
+        let mut split_at = floor_char_boundary(rest, max_bytes);
+        if let Some(ws) = rest[..split_at].rfind(char::is_whitespace) {
+            // Skip the whitespace itself so chunks don't start with it.
+            let after_ws = ws + rest[ws..].chars().next().map(char::len_utf8).unwrap_or(1);
+            if after_ws > 0 {
+                split_at = after_ws;
+            }
+        }
+        let (head, tail) = rest.split_at(split_at);
+        let head = head.trim_end();
+        if !head.is_empty() {
+            chunks.push(head);
+        }
+        rest = tail.trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+/// Largest byte index `<= idx` that lies on a UTF-8 character boundary.
+fn floor_char_boundary(s: &str, idx: usize) -> usize {
+    let mut idx = idx.min(s.len());
+    while idx > 0 && !s.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_a_single_chunk() {
+        assert_eq!(str_chunks("hello", 100), vec!["hello"]);
+    }
+
+    #[test]
+    fn empty_text_is_a_single_empty_chunk() {
+        assert_eq!(str_chunks("", 100), vec![""]);
+    }
+
+    #[test]
+    fn splits_on_whitespace_boundary() {
+        let text = "one two three four";
+        let chunks = str_chunks(text, 9);
+        assert_eq!(chunks, vec!["one two", "three", "four"]);
+        assert_eq!(chunks.concat().replace(' ', ""), text.replace(' ', ""));
+    }
+
+    #[test]
+    fn hard_cuts_a_single_oversized_token() {
+        let text = "a".repeat(20);
+        let chunks = str_chunks(&text, 8);
+        assert_eq!(chunks.len(), 3);
+        for c in &chunks {
+            assert!(c.len() <= 8);
+        }
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn never_splits_a_utf8_code_point() {
+        // Each emoji is 4 bytes; force a split right in the middle of one.
+        let text = "😀😀😀😀😀";
+        let chunks = str_chunks(text, 6);
+        for c in &chunks {
+            assert!(c.is_char_boundary(0));
+            assert!(c.is_char_boundary(c.len()));
+        }
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn reassembles_to_original_content() {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+        let chunks = str_chunks(&text, 100);
+        assert!(chunks.len() > 1);
+        for c in &chunks {
+            assert!(c.len() <= 100);
+        }
+        // Whitespace is normalized at break points but no content is lost.
+        let rejoined: String = chunks.join(" ");
+        assert_eq!(
+            rejoined.split_whitespace().collect::<Vec<_>>(),
+            text.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+}
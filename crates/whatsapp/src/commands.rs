@@ -0,0 +1,186 @@
+//! Admin-issued control commands for a WhatsApp account, parsed from chat
+//! text with tolerant regexes (mirroring group-actor's `RE_BAN_USER`-style
+//! matching) rather than a strict command grammar.
+//!
+//! Execution is gated by [`WhatsAppAccountConfig::admin_users`]: only a
+//! sender whose peer ID or username is on that list may run a command.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use moltis_channels::gating;
+
+use crate::{access::AccessDenied, config::WhatsAppAccountConfig};
+
+static RE_ALLOW: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^\s*!\s*allow\s+(\S+)\s*$").expect("valid regex"));
+static RE_DENY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^\s*!\s*deny\s+(\S+)\s*$").expect("valid regex"));
+static RE_ALLOW_GROUP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^\s*!\s*allowgroup\s+(\S+)\s*$").expect("valid regex"));
+static RE_STATUS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^\s*!\s*status\s*$").expect("valid regex"));
+
+/// A parsed admin control message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminCommand {
+    /// `!allow <jid>` — add a peer/username to the DM allowlist.
+    Allow(String),
+    /// `!deny <jid>` — remove a peer/username from the DM allowlist and add
+    /// it to `dm_blocklist`.
+    Deny(String),
+    /// `!allowgroup <gid>` — add a group JID to the group allowlist.
+    AllowGroup(String),
+    /// `!status` — summarize the account's current gating config.
+    Status,
+}
+
+/// Parse an inbound message body as an [`AdminCommand`], or `None` if it
+/// doesn't match any recognized admin command prefix.
+pub fn parse(text: &str) -> Option<AdminCommand> {
+    if let Some(caps) = RE_ALLOW.captures(text) {
+        return Some(AdminCommand::Allow(caps[1].to_string()));
+    }
+    if let Some(caps) = RE_DENY.captures(text) {
+        return Some(AdminCommand::Deny(caps[1].to_string()));
+    }
+    if let Some(caps) = RE_ALLOW_GROUP.captures(text) {
+        return Some(AdminCommand::AllowGroup(caps[1].to_string()));
+    }
+    if RE_STATUS.is_match(text) {
+        return Some(AdminCommand::Status);
+    }
+    None
+}
+
+/// Apply a parsed [`AdminCommand`] against `config`, gated by `admin_users`.
+///
+/// Returns the reply text to send back on success, or
+/// `Err(AccessDenied::NotAdmin)` if the sender isn't on `admin_users`.
+/// Mutates `config` in place — the caller is responsible for persisting the
+/// change so it survives a restart.
+pub fn apply(
+    config: &mut WhatsAppAccountConfig,
+    sender_peer_id: &str,
+    sender_username: Option<&str>,
+    cmd: &AdminCommand,
+) -> Result<String, AccessDenied> {
+    let is_admin = gating::is_allowed(sender_peer_id, &config.admin_users)
+        || sender_username.is_some_and(|u| gating::is_allowed(u, &config.admin_users));
+    if !is_admin {
+        return Err(AccessDenied::NotAdmin);
+    }
+
+    Ok(match cmd {
+        AdminCommand::Allow(target) => {
+            config.dm_blocklist.retain(|e| e != target);
+            if !config.allowlist.iter().any(|e| e == target) {
+                config.allowlist.push(target.clone());
+            }
+            format!("allowed {target}")
+        },
+        AdminCommand::Deny(target) => {
+            config.allowlist.retain(|e| e != target);
+            if !config.dm_blocklist.iter().any(|e| e == target) {
+                config.dm_blocklist.push(target.clone());
+            }
+            format!("denied {target}")
+        },
+        AdminCommand::AllowGroup(target) => {
+            if !config.group_allowlist.iter().any(|e| e == target) {
+                config.group_allowlist.push(target.clone());
+            }
+            format!("allowed group {target}")
+        },
+        AdminCommand::Status => format!(
+            "dm_policy={:?} group_policy={:?} allowlist={} group_allowlist={} dm_blocklist={} group_blocklist={}",
+            config.dm_policy,
+            config.group_policy,
+            config.allowlist.len(),
+            config.group_allowlist.len(),
+            config.dm_blocklist.len(),
+            config.group_blocklist.len(),
+        ),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> WhatsAppAccountConfig {
+        WhatsAppAccountConfig {
+            admin_users: vec!["admin1".into()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_allow_with_tolerant_spacing() {
+        assert_eq!(parse("!allow 15551234567"), Some(AdminCommand::Allow("15551234567".into())));
+        assert_eq!(parse("!  allow   15551234567"), Some(AdminCommand::Allow("15551234567".into())));
+        assert_eq!(parse(" !ALLOW 15551234567"), Some(AdminCommand::Allow("15551234567".into())));
+    }
+
+    #[test]
+    fn parses_deny_allowgroup_and_status() {
+        assert_eq!(parse("!deny 15551234567"), Some(AdminCommand::Deny("15551234567".into())));
+        assert_eq!(parse("!allowgroup grp1@g.us"), Some(AdminCommand::AllowGroup("grp1@g.us".into())));
+        assert_eq!(parse("!status"), Some(AdminCommand::Status));
+    }
+
+    #[test]
+    fn non_command_text_does_not_parse() {
+        assert_eq!(parse("hello there"), None);
+        assert_eq!(parse("!allow"), None);
+    }
+
+    #[test]
+    fn non_admin_sender_is_rejected() {
+        let mut c = cfg();
+        let result = apply(&mut c, "stranger", None, &AdminCommand::Allow("15551234567".into()));
+        assert_eq!(result, Err(AccessDenied::NotAdmin));
+        assert!(c.allowlist.is_empty());
+    }
+
+    #[test]
+    fn admin_can_allow_a_peer() {
+        let mut c = cfg();
+        let reply = apply(&mut c, "admin1", None, &AdminCommand::Allow("15551234567".into())).unwrap();
+        assert_eq!(reply, "allowed 15551234567");
+        assert_eq!(c.allowlist, vec!["15551234567"]);
+    }
+
+    #[test]
+    fn admin_can_deny_a_peer_moving_it_off_the_allowlist() {
+        let mut c = cfg();
+        c.allowlist = vec!["15551234567".into()];
+        let reply = apply(&mut c, "admin1", None, &AdminCommand::Deny("15551234567".into())).unwrap();
+        assert_eq!(reply, "denied 15551234567");
+        assert!(c.allowlist.is_empty());
+        assert_eq!(c.dm_blocklist, vec!["15551234567"]);
+    }
+
+    #[test]
+    fn admin_can_allowgroup() {
+        let mut c = cfg();
+        let reply = apply(&mut c, "admin1", None, &AdminCommand::AllowGroup("grp1@g.us".into())).unwrap();
+        assert_eq!(reply, "allowed group grp1@g.us");
+        assert_eq!(c.group_allowlist, vec!["grp1@g.us"]);
+    }
+
+    #[test]
+    fn admin_matched_by_username_not_just_peer_id() {
+        let mut c = cfg();
+        let reply = apply(&mut c, "15559999999", Some("admin1"), &AdminCommand::Status).unwrap();
+        assert!(reply.starts_with("dm_policy="));
+    }
+
+    #[test]
+    fn allow_is_idempotent() {
+        let mut c = cfg();
+        apply(&mut c, "admin1", None, &AdminCommand::Allow("15551234567".into())).unwrap();
+        apply(&mut c, "admin1", None, &AdminCommand::Allow("15551234567".into())).unwrap();
+        assert_eq!(c.allowlist, vec!["15551234567"]);
+    }
+}
@@ -48,11 +48,92 @@ pub struct WhatsAppAccountConfig {
     /// Group JID allowlist.
     pub group_allowlist: Vec<String>,
 
+    /// Enrolled member peer IDs/usernames for `GroupPolicy::MemberOnly` —
+    /// distinct from `group_allowlist`, which gates which *groups* the bot
+    /// is in; this gates which *senders* within an allowed group are
+    /// processed. Set through config; the admin commands in
+    /// [`crate::commands`] edit `allowlist`, `dm_blocklist` and
+    /// `group_allowlist`, not this list.
+    pub member_users: Vec<String>,
+
+    /// User/peer denylist for DMs (JID user parts, phone numbers, or
+    /// usernames), checked before `dm_policy` — a match here denies even
+    /// under `DmPolicy::Open`, so an operator running an open DM bot can
+    /// still ban abusive numbers without switching to a full allowlist.
+    pub dm_blocklist: Vec<String>,
+
+    /// Group JID denylist, checked before `group_policy` the same way
+    /// `dm_blocklist` is checked before `dm_policy`.
+    pub group_blocklist: Vec<String>,
+
+    /// If non-empty, only JIDs whose domain (the part after `@`, e.g.
+    /// `s.whatsapp.net`, `g.us`, `lid`, `newsletter`) is in this list are
+    /// accepted — everything else is denied, for both DMs and groups.
+    pub allowed_domains: Vec<String>,
+
+    /// JID domains to reject outright, for both DMs and groups (e.g. set to
+    /// `["newsletter"]` to refuse newsletter updates wholesale). Checked
+    /// before `allowed_domains`.
+    pub blocked_domains: Vec<String>,
+
+    /// JIDs/usernames allowed to run admin control commands (`!allow`,
+    /// `!deny`, `!allowgroup`, `!status`) from chat — see
+    /// [`crate::commands`]. Empty by default, so admin commands are
+    /// disabled until an operator opts in.
+    pub admin_users: Vec<String>,
+
+    /// Require `allowlist` entries to have completed the
+    /// [`crate::verified_join`] SecureJoin-style handshake before being
+    /// treated as allowed (default: false). With this on, a name matching
+    /// `allowlist` is not enough on its own — the peer's Signal identity
+    /// fingerprint must also have been verified.
+    pub require_verified_allowlist: bool,
+
+    /// Peers that have completed the [`crate::verified_join`] handshake.
+    /// Loaded into the in-memory
+    /// [`crate::verified_join::VerifiedJoinRegistry`] via `with_verified` at
+    /// connection startup, so a restart doesn't forget them and re-lock
+    /// every `require_verified_allowlist` peer out — as long as whatever
+    /// persists this config also saves the entry a peer's handshake adds,
+    /// the same way it already needs to for `OtpResolved`-driven allowlist
+    /// changes. See `crate::handlers::handle_verified_join_flow`, which
+    /// emits a `ChannelEvent` when a peer finishes the handshake.
+    pub verified_peers: Vec<crate::verified_join::VerifiedEntry>,
+
+    /// Under `GroupPolicy::AutoJoin`, require the inviter to already be on
+    /// `allowlist` before auto-accepting their group invite (default:
+    /// false, meaning any invite is accepted). See
+    /// [`crate::group_autojoin`].
+    pub auto_join_require_allowlisted_inviter: bool,
+
+    /// Under `GroupPolicy::AutoJoin`, how long (in seconds) an auto-joined
+    /// group may have no allowlisted member present before the bot leaves
+    /// it and prunes it from `group_allowlist` (default: 0, meaning leave
+    /// immediately once no allowlisted member remains). See
+    /// [`crate::group_autojoin`].
+    pub auto_join_idle_grace_secs: u64,
+
     /// Enable OTP self-approval for non-allowlisted DM users (default: true).
     pub otp_self_approval: bool,
 
     /// Cooldown in seconds after 3 failed OTP attempts (default: 300).
     pub otp_cooldown_secs: u64,
+
+    /// Maximum number of OTP challenges this account may have pending at
+    /// once across all peers, above which new challenges are silently
+    /// throttled (default: 50). Protects against a burst of DMs from unknown
+    /// peers getting the account rate-limited or banned.
+    pub otp_max_pending: u32,
+
+    /// Sustained outbound send rate for ordinary replies, in messages per
+    /// minute (default: 20). WhatsApp bans numbers that send too fast, so
+    /// every send draws a token from a bucket refilling at this rate —
+    /// see [`crate::rate_limit::TokenBucket`].
+    pub messages_per_minute: u32,
+
+    /// Token bucket capacity for `messages_per_minute` — how many messages
+    /// may go out back-to-back before the rate limit kicks in (default: 5).
+    pub burst: u32,
 }
 
 impl std::fmt::Debug for WhatsAppAccountConfig {
@@ -79,8 +160,21 @@ impl Default for WhatsAppAccountConfig {
             group_policy: GroupPolicy::default(),
             allowlist: Vec::new(),
             group_allowlist: Vec::new(),
+            member_users: Vec::new(),
+            dm_blocklist: Vec::new(),
+            group_blocklist: Vec::new(),
+            allowed_domains: Vec::new(),
+            blocked_domains: Vec::new(),
+            admin_users: Vec::new(),
+            require_verified_allowlist: false,
+            verified_peers: Vec::new(),
+            auto_join_require_allowlisted_inviter: false,
+            auto_join_idle_grace_secs: 0,
             otp_self_approval: true,
             otp_cooldown_secs: 300,
+            otp_max_pending: 50,
+            messages_per_minute: 20,
+            burst: 5,
         }
     }
 }
@@ -101,8 +195,21 @@ mod tests {
         assert_eq!(cfg.group_policy, GroupPolicy::Open);
         assert!(cfg.allowlist.is_empty());
         assert!(cfg.group_allowlist.is_empty());
+        assert!(cfg.member_users.is_empty());
+        assert!(cfg.dm_blocklist.is_empty());
+        assert!(cfg.group_blocklist.is_empty());
+        assert!(cfg.allowed_domains.is_empty());
+        assert!(cfg.blocked_domains.is_empty());
+        assert!(cfg.admin_users.is_empty());
+        assert!(!cfg.require_verified_allowlist);
+        assert!(cfg.verified_peers.is_empty());
+        assert!(!cfg.auto_join_require_allowlisted_inviter);
+        assert_eq!(cfg.auto_join_idle_grace_secs, 0);
         assert!(cfg.otp_self_approval);
         assert_eq!(cfg.otp_cooldown_secs, 300);
+        assert_eq!(cfg.otp_max_pending, 50);
+        assert_eq!(cfg.messages_per_minute, 20);
+        assert_eq!(cfg.burst, 5);
     }
 
     #[test]
@@ -129,7 +236,8 @@ mod tests {
             "allowlist": ["user1", "user2"],
             "group_allowlist": ["group1"],
             "otp_self_approval": false,
-            "otp_cooldown_secs": 600
+            "otp_cooldown_secs": 600,
+            "otp_max_pending": 10
         }"#;
         let cfg: WhatsAppAccountConfig = serde_json::from_str(json).unwrap();
         assert_eq!(cfg.dm_policy, DmPolicy::Allowlist);
@@ -138,6 +246,31 @@ mod tests {
         assert_eq!(cfg.group_allowlist, vec!["group1"]);
         assert!(!cfg.otp_self_approval);
         assert_eq!(cfg.otp_cooldown_secs, 600);
+        assert_eq!(cfg.otp_max_pending, 10);
+    }
+
+    #[test]
+    fn deserialize_with_blocklists() {
+        let json = r#"{
+            "dm_policy": "open",
+            "dm_blocklist": ["15551112222", "spammer"],
+            "group_blocklist": ["grp-banned"]
+        }"#;
+        let cfg: WhatsAppAccountConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.dm_policy, DmPolicy::Open);
+        assert_eq!(cfg.dm_blocklist, vec!["15551112222", "spammer"]);
+        assert_eq!(cfg.group_blocklist, vec!["grp-banned"]);
+    }
+
+    #[test]
+    fn deserialize_with_domain_scoping() {
+        let json = r#"{
+            "allowed_domains": ["s.whatsapp.net"],
+            "blocked_domains": ["newsletter"]
+        }"#;
+        let cfg: WhatsAppAccountConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.allowed_domains, vec!["s.whatsapp.net"]);
+        assert_eq!(cfg.blocked_domains, vec!["newsletter"]);
     }
 
     #[test]
@@ -11,6 +11,7 @@ use crate::{
     config::WhatsAppAccountConfig,
     handlers,
     state::{AccountState, AccountStateMap},
+    tls_trust::TlsTrustStore,
 };
 
 /// Start a WhatsApp connection for the given account.
@@ -25,6 +26,8 @@ pub async fn start_connection(
     data_dir: std::path::PathBuf,
     message_log: Option<Arc<dyn MessageLog>>,
     event_sink: Option<Arc<dyn ChannelEventSink>>,
+    tls_trust: Option<Arc<TlsTrustStore>>,
+    health_tx: tokio::sync::broadcast::Sender<moltis_channels::plugin::ChannelHealthSnapshot>,
 ) -> crate::Result<()> {
     // Use persistent sled store at <data_dir>/whatsapp/<account_id>/.
     let store_path = config
@@ -39,6 +42,29 @@ pub async fn start_connection(
             message: format!("failed to open sled store at {}: {e}", store_path.display()),
         })?,
     );
+    // `Bot::builder` takes ownership of `backend` below; keep a clone so
+    // `AccountState.identity_store` can still look up peer identity keys
+    // for `crate::verified_join`.
+    let identity_store = Arc::clone(&backend);
+
+    let outbound_queue_path = store_path.join("outbound_queue");
+    let outbound_queue = Arc::new(
+        crate::outbound_queue::OutboundQueue::open(&outbound_queue_path).map_err(|e| {
+            crate::Error::Store {
+                message: format!(
+                    "failed to open outbound queue at {}: {e}",
+                    outbound_queue_path.display()
+                ),
+            }
+        })?,
+    );
+
+    let otp_store_path = store_path.join("otp_challenges");
+    let otp_store = Arc::new(
+        crate::otp_store::OtpChallengeStore::open(&otp_store_path).map_err(|e| crate::Error::Store {
+            message: format!("failed to open OTP challenge store at {}: {e}", otp_store_path.display()),
+        })?,
+    );
 
     let cancel = CancellationToken::new();
     let cancel_clone = cancel.clone();
@@ -55,12 +81,22 @@ pub async fn start_connection(
     let state_ref_handler = Arc::clone(&state_ref);
     let accounts_handler = Arc::clone(&accounts);
 
+    // Use the shared pre-built root store for both the HTTP client and the
+    // WebSocket transport when `[tls.trust]` configures one, so a corporate
+    // proxy CA or private PKI is trusted consistently by both.
+    let http_client = match &tls_trust {
+        Some(trust) => whatsapp_rust_ureq_http_client::UreqHttpClient::with_root_store(Arc::clone(&trust.roots)),
+        None => whatsapp_rust_ureq_http_client::UreqHttpClient::new(),
+    };
+    let transport_factory = match &tls_trust {
+        Some(trust) => whatsapp_rust_tokio_transport::TokioWebSocketTransportFactory::with_root_store(Arc::clone(&trust.roots)),
+        None => whatsapp_rust_tokio_transport::TokioWebSocketTransportFactory::new(),
+    };
+
     let mut bot = whatsapp_rust::bot::Bot::builder()
         .with_backend(backend)
-        .with_transport_factory(
-            whatsapp_rust_tokio_transport::TokioWebSocketTransportFactory::new(),
-        )
-        .with_http_client(whatsapp_rust_ureq_http_client::UreqHttpClient::new())
+        .with_transport_factory(transport_factory)
+        .with_http_client(http_client)
         .on_event(move |event, client| {
             let state_ref = Arc::clone(&state_ref_handler);
             let accounts = Arc::clone(&accounts_handler);
@@ -80,6 +116,10 @@ pub async fn start_connection(
 
     // Create account state.
     let otp_cooldown = config.otp_cooldown_secs;
+    let otp_max_pending = config.otp_max_pending as usize;
+    let messages_per_minute = config.messages_per_minute;
+    let burst = config.burst;
+    let verified_peers = config.verified_peers.clone();
     let account_state = Arc::new(AccountState {
         client: Arc::clone(&client),
         account_id: account_id_clone.clone(),
@@ -89,8 +129,27 @@ pub async fn start_connection(
         event_sink: event_sink_clone,
         latest_qr: std::sync::RwLock::new(None),
         connected: std::sync::atomic::AtomicBool::new(false),
-        otp: std::sync::Mutex::new(crate::otp::OtpState::new(otp_cooldown)),
+        reconnect_attempts: std::sync::atomic::AtomicU32::new(0),
+        otp: std::sync::Mutex::new(crate::otp::OtpState::new_with_store(otp_cooldown, otp_max_pending, Arc::clone(&otp_store))),
+        media_cache: std::sync::Mutex::new(crate::media_cache::MediaCache::new()),
+        outbound_queue: Arc::clone(&outbound_queue),
         recent_sent_ids: std::sync::Mutex::new(std::collections::VecDeque::new()),
+        pending_downloads: std::sync::Mutex::new(crate::download_state::DownloadRegistry::new()),
+        delivery: std::sync::Mutex::new(crate::delivery::DeliveryTracker::new()),
+        health_tx: health_tx.clone(),
+        rate_limiter: Arc::new(std::sync::Mutex::new(crate::rate_limit::TokenBucket::new(
+            messages_per_minute,
+            burst,
+        ))),
+        otp_rate_limiter: Arc::new(std::sync::Mutex::new(crate::rate_limit::TokenBucket::new(
+            messages_per_minute,
+            burst,
+        ))),
+        verified_join: std::sync::Mutex::new(crate::verified_join::VerifiedJoinRegistry::with_verified(
+            verified_peers.clone(),
+        )),
+        identity_store: Arc::clone(&identity_store),
+        group_idle: std::sync::Mutex::new(crate::group_autojoin::GroupIdleTracker::new()),
     });
 
     // Populate the OnceCell so the event handler can access state.
@@ -108,8 +167,21 @@ pub async fn start_connection(
             event_sink: account_state.event_sink.clone(),
             latest_qr: std::sync::RwLock::new(None),
             connected: std::sync::atomic::AtomicBool::new(false),
-            otp: std::sync::Mutex::new(crate::otp::OtpState::new(otp_cooldown)),
+            reconnect_attempts: std::sync::atomic::AtomicU32::new(0),
+            otp: std::sync::Mutex::new(crate::otp::OtpState::new_with_store(otp_cooldown, otp_max_pending, Arc::clone(&otp_store))),
+            media_cache: std::sync::Mutex::new(crate::media_cache::MediaCache::new()),
+            outbound_queue: Arc::clone(&outbound_queue),
             recent_sent_ids: std::sync::Mutex::new(std::collections::VecDeque::new()),
+            pending_downloads: std::sync::Mutex::new(crate::download_state::DownloadRegistry::new()),
+            delivery: std::sync::Mutex::new(crate::delivery::DeliveryTracker::new()),
+            health_tx: health_tx.clone(),
+            rate_limiter: Arc::clone(&account_state.rate_limiter),
+            otp_rate_limiter: Arc::clone(&account_state.otp_rate_limiter),
+            verified_join: std::sync::Mutex::new(crate::verified_join::VerifiedJoinRegistry::with_verified(
+                verified_peers,
+            )),
+            identity_store: Arc::clone(&identity_store),
+            group_idle: std::sync::Mutex::new(crate::group_autojoin::GroupIdleTracker::new()),
         });
     }
 
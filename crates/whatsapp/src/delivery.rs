@@ -0,0 +1,108 @@
+//! Per-message delivery/read-state tracking fed by WhatsApp receipt stanzas.
+//!
+//! Lets [`crate::handlers::handle_otp_flow`] tell whether a previously sent
+//! OTP challenge ever reached the peer's device before deciding whether an
+//! `OtpInitResult::AlreadyPending` should trigger a resend.
+
+use std::collections::HashMap;
+
+/// Lifecycle of an outbound message, as observed via inbound receipt stanzas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryState {
+    /// Sent; no receipt has arrived yet.
+    Pending,
+    /// A delivery receipt arrived.
+    Delivered,
+    /// A read receipt arrived.
+    Read,
+    /// An error receipt arrived (e.g. undeliverable).
+    Failed,
+}
+
+/// Tracks delivery/read state for recently sent messages, keyed by message ID.
+#[derive(Default)]
+pub struct DeliveryTracker {
+    states: HashMap<String, DeliveryState>,
+}
+
+impl DeliveryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a freshly sent message as `Pending`.
+    pub fn track(&mut self, msg_id: String) {
+        self.states.insert(msg_id, DeliveryState::Pending);
+    }
+
+    /// Current delivery state of a tracked message, if any.
+    pub fn state(&self, msg_id: &str) -> Option<DeliveryState> {
+        self.states.get(msg_id).copied()
+    }
+
+    /// Advance a message to `Delivered`, unless it already reached `Read`
+    /// (receipts can arrive out of order).
+    pub fn mark_delivered(&mut self, msg_id: &str) {
+        let entry = self.states.entry(msg_id.to_string()).or_insert(DeliveryState::Pending);
+        if *entry != DeliveryState::Read {
+            *entry = DeliveryState::Delivered;
+        }
+    }
+
+    /// Advance a message to `Read`.
+    pub fn mark_read(&mut self, msg_id: &str) {
+        self.states.insert(msg_id.to_string(), DeliveryState::Read);
+    }
+
+    /// Mark a message as failed, unless it already progressed past `Pending`.
+    pub fn mark_failed(&mut self, msg_id: &str) {
+        let entry = self.states.entry(msg_id.to_string()).or_insert(DeliveryState::Failed);
+        if *entry == DeliveryState::Pending {
+            *entry = DeliveryState::Failed;
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untracked_message_has_no_state() {
+        let tracker = DeliveryTracker::new();
+        assert!(tracker.state("msg1").is_none());
+    }
+
+    #[test]
+    fn tracked_message_starts_pending() {
+        let mut tracker = DeliveryTracker::new();
+        tracker.track("msg1".into());
+        assert_eq!(tracker.state("msg1"), Some(DeliveryState::Pending));
+    }
+
+    #[test]
+    fn delivered_then_read_progresses_forward_only() {
+        let mut tracker = DeliveryTracker::new();
+        tracker.track("msg1".into());
+        tracker.mark_delivered("msg1");
+        assert_eq!(tracker.state("msg1"), Some(DeliveryState::Delivered));
+        tracker.mark_read("msg1");
+        assert_eq!(tracker.state("msg1"), Some(DeliveryState::Read));
+
+        // A late/out-of-order delivery receipt must not regress a read message.
+        tracker.mark_delivered("msg1");
+        assert_eq!(tracker.state("msg1"), Some(DeliveryState::Read));
+    }
+
+    #[test]
+    fn failed_only_overrides_pending() {
+        let mut tracker = DeliveryTracker::new();
+        tracker.track("msg1".into());
+        tracker.mark_delivered("msg1");
+        tracker.mark_failed("msg1");
+
+        // An error receipt after delivery shouldn't erase the delivered state.
+        assert_eq!(tracker.state("msg1"), Some(DeliveryState::Delivered));
+    }
+}
@@ -0,0 +1,186 @@
+//! On-demand media download tracking.
+//!
+//! Image, video and document media above
+//! [`DEFER_DOWNLOAD_THRESHOLD_BYTES`] is not fetched at receive
+//! time — a [`PendingDownload`] is registered instead, carrying whatever the
+//! `whatsapp-rust` client needs to fetch it later, and `ensure_downloaded`
+//! (see `AccountState`) pulls the bytes lazily on demand.
+
+use std::collections::HashMap;
+
+use waproto::whatsapp::message::{DocumentMessage, ImageMessage, VideoMessage};
+
+/// Media larger than this is deferred rather than downloaded eagerly.
+pub const DEFER_DOWNLOAD_THRESHOLD_BYTES: usize = 2 * 1024 * 1024;
+
+/// Lifecycle of a deferred media download, mirroring Delta Chat's
+/// `DownloadState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadState {
+    /// Registered but not yet requested.
+    Available,
+    /// A fetch is currently in flight.
+    InProgress,
+    /// Fetched successfully; bytes are cached in the registry.
+    Done,
+    /// The last fetch attempt failed; retrying is allowed.
+    Failure,
+}
+
+/// The encrypted media reference needed to download a deferred attachment,
+/// tagged by the message kind it came from.
+#[derive(Clone)]
+pub enum MediaRef {
+    Image(Box<ImageMessage>),
+    Video(Box<VideoMessage>),
+    Document(Box<DocumentMessage>),
+}
+
+/// A deferred download and its current state.
+struct Entry {
+    state: DownloadState,
+    media: MediaRef,
+    data: Option<Vec<u8>>,
+    /// The chat the message arrived in, so `ensure_downloaded` can emit a
+    /// `ChannelEvent::MediaReady`/`MediaFailed` without the caller having to
+    /// carry it around separately.
+    chat_id: String,
+}
+
+/// Registry of deferred media downloads for one account, keyed by message ID.
+#[derive(Default)]
+pub struct DownloadRegistry {
+    entries: HashMap<String, Entry>,
+}
+
+impl DownloadRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a deferred download. Replaces any prior entry for the same
+    /// message ID.
+    pub fn register(&mut self, msg_id: String, chat_id: String, media: MediaRef) {
+        self.entries.insert(
+            msg_id,
+            Entry {
+                state: DownloadState::Available,
+                media,
+                data: None,
+                chat_id,
+            },
+        );
+    }
+
+    /// The chat a registered download arrived in, if one was registered.
+    pub fn chat_id(&self, msg_id: &str) -> Option<&str> {
+        self.entries.get(msg_id).map(|e| e.chat_id.as_str())
+    }
+
+    /// Current state of a deferred download, if one was registered.
+    pub fn state(&self, msg_id: &str) -> Option<DownloadState> {
+        self.entries.get(msg_id).map(|e| e.state)
+    }
+
+    /// Begin a fetch: returns the already-downloaded bytes if `Done`, the
+    /// `MediaRef` to fetch if `Available` or retrying after `Failure` (and
+    /// marks it `InProgress`), `BeginOutcome::InProgress` if already in
+    /// flight, or `None` if never registered.
+    pub fn begin(&mut self, msg_id: &str) -> Option<BeginOutcome> {
+        let entry = self.entries.get_mut(msg_id)?;
+        match entry.state {
+            DownloadState::Done => Some(BeginOutcome::AlreadyDone(entry.data.clone()?)),
+            DownloadState::InProgress => Some(BeginOutcome::InProgress),
+            DownloadState::Available | DownloadState::Failure => {
+                entry.state = DownloadState::InProgress;
+                Some(BeginOutcome::Start(entry.media.clone()))
+            },
+        }
+    }
+
+    /// Record a successful fetch.
+    pub fn complete(&mut self, msg_id: &str, data: Vec<u8>) {
+        if let Some(entry) = self.entries.get_mut(msg_id) {
+            entry.state = DownloadState::Done;
+            entry.data = Some(data);
+        }
+    }
+
+    /// Record a failed fetch; a later `ensure_downloaded` call may retry.
+    pub fn fail(&mut self, msg_id: &str) {
+        if let Some(entry) = self.entries.get_mut(msg_id) {
+            entry.state = DownloadState::Failure;
+        }
+    }
+}
+
+/// Outcome of [`DownloadRegistry::begin`].
+pub enum BeginOutcome {
+    /// Not yet fetched (or a retry after failure); caller should download
+    /// `MediaRef` and report back via `complete`/`fail`.
+    Start(MediaRef),
+    /// Already downloaded; here are the bytes.
+    AlreadyDone(Vec<u8>),
+    /// A fetch is already in flight for this message.
+    InProgress,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn sample_media() -> MediaRef {
+        MediaRef::Document(Box::new(DocumentMessage::default()))
+    }
+
+    #[test]
+    fn unregistered_message_has_no_state() {
+        let registry = DownloadRegistry::new();
+        assert!(registry.state("unknown").is_none());
+    }
+
+    #[test]
+    fn registered_download_starts_available() {
+        let mut registry = DownloadRegistry::new();
+        registry.register("msg1".into(), "chat1".into(), sample_media());
+        assert_eq!(registry.state("msg1"), Some(DownloadState::Available));
+    }
+
+    #[test]
+    fn begin_transitions_to_in_progress_then_done() {
+        let mut registry = DownloadRegistry::new();
+        registry.register("msg1".into(), "chat1".into(), sample_media());
+
+        assert!(matches!(
+            registry.begin("msg1"),
+            Some(BeginOutcome::Start(_))
+        ));
+        assert_eq!(registry.state("msg1"), Some(DownloadState::InProgress));
+
+        // A concurrent caller sees the in-flight fetch instead of starting another.
+        assert!(matches!(
+            registry.begin("msg1"),
+            Some(BeginOutcome::InProgress)
+        ));
+
+        registry.complete("msg1", vec![1, 2, 3]);
+        assert_eq!(registry.state("msg1"), Some(DownloadState::Done));
+        assert!(
+            matches!(registry.begin("msg1"), Some(BeginOutcome::AlreadyDone(data)) if data == vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn failed_download_can_be_retried() {
+        let mut registry = DownloadRegistry::new();
+        registry.register("msg1".into(), "chat1".into(), sample_media());
+        registry.begin("msg1");
+        registry.fail("msg1");
+        assert_eq!(registry.state("msg1"), Some(DownloadState::Failure));
+        assert!(matches!(
+            registry.begin("msg1"),
+            Some(BeginOutcome::Start(_))
+        ));
+    }
+}
@@ -10,9 +10,24 @@ pub enum Error {
     #[error("store: {message}")]
     Store { message: String },
 
+    /// Invalid or unreadable configuration (e.g. a `[tls.trust]` CA file).
+    #[error("config: {message}")]
+    Config { message: String },
+
+    /// Outbound send throttled by the per-account token bucket; a
+    /// non-blocking [`crate::rate_limit::acquire`] call returns this instead
+    /// of waiting out the refill delay.
+    #[error("rate limited: retry after {retry_after_secs:.1}s")]
+    RateLimited { retry_after_secs: f64 },
+
     /// Channel layer error.
     #[error(transparent)]
     Channel(#[from] moltis_channels::Error),
+
+    /// Session export/import (see [`crate::migration`]) failed — bad
+    /// passphrase, corrupted blob, or an unsupported format version.
+    #[error("migration: {message}")]
+    Migration { message: String },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
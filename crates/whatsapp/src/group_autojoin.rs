@@ -0,0 +1,164 @@
+//! Support for `GroupPolicy::AutoJoin`, inspired by the Matrix autojoin bot
+//! pattern: accept incoming group invites automatically, grow
+//! `group_allowlist` as new groups are joined, and leave (and prune) groups
+//! that end up with no allowlisted member for a configurable grace period.
+
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use moltis_channels::gating;
+
+use crate::config::WhatsAppAccountConfig;
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Whether an incoming group invite from `inviter` should be auto-accepted
+/// under `config`'s `auto_join_require_allowlisted_inviter` setting. When
+/// that flag is off, every invite is accepted while `GroupPolicy::AutoJoin`
+/// is set.
+pub fn should_auto_accept(config: &WhatsAppAccountConfig, inviter: &str) -> bool {
+    if !config.auto_join_require_allowlisted_inviter {
+        return true;
+    }
+    gating::is_allowed(inviter, &config.allowlist)
+}
+
+/// Record a newly auto-accepted group in `config.group_allowlist`, if it
+/// isn't already there.
+pub fn accept_invite(config: &mut WhatsAppAccountConfig, group_jid: &str) {
+    if !config.group_allowlist.iter().any(|g| g == group_jid) {
+        config.group_allowlist.push(group_jid.to_string());
+    }
+}
+
+/// Tracks, per auto-joined group, how long it's gone with no allowlisted
+/// member present, so `auto_join_idle_grace_secs` can be enforced without
+/// leaving a group the moment its last allowlisted member happens to step
+/// out.
+#[derive(Default)]
+pub struct GroupIdleTracker {
+    /// Group JID -> epoch-seconds timestamp the group was first observed
+    /// with no allowlisted member. Cleared as soon as an allowlisted member
+    /// reappears.
+    no_allowlisted_since: HashMap<String, u64>,
+}
+
+impl GroupIdleTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the tracker with a fresh membership snapshot for `group_jid`
+    /// and report whether the group has now been allowlist-empty for at
+    /// least `grace_secs` and should be left.
+    pub fn observe(
+        &mut self,
+        config: &WhatsAppAccountConfig,
+        group_jid: &str,
+        participants: &[String],
+        grace_secs: u64,
+    ) -> bool {
+        let has_allowlisted = participants
+            .iter()
+            .any(|p| gating::is_allowed(p, &config.allowlist));
+
+        if has_allowlisted {
+            self.no_allowlisted_since.remove(group_jid);
+            return false;
+        }
+
+        let since = *self
+            .no_allowlisted_since
+            .entry(group_jid.to_string())
+            .or_insert_with(now_secs);
+        now_secs().saturating_sub(since) >= grace_secs
+    }
+
+    /// Stop tracking a group, e.g. once it's been left and pruned.
+    pub fn forget(&mut self, group_jid: &str) {
+        self.no_allowlisted_since.remove(group_jid);
+    }
+}
+
+/// Remove `group_jid` from `config.group_allowlist`, e.g. after the bot
+/// leaves an idle auto-joined group.
+pub fn prune_group(config: &mut WhatsAppAccountConfig, group_jid: &str) {
+    config.group_allowlist.retain(|g| g != group_jid);
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> WhatsAppAccountConfig {
+        WhatsAppAccountConfig {
+            allowlist: vec!["alice".into()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_any_inviter_by_default() {
+        let config = cfg();
+        assert!(should_auto_accept(&config, "stranger"));
+    }
+
+    #[test]
+    fn requires_allowlisted_inviter_when_configured() {
+        let config = WhatsAppAccountConfig {
+            auto_join_require_allowlisted_inviter: true,
+            ..cfg()
+        };
+        assert!(should_auto_accept(&config, "alice"));
+        assert!(!should_auto_accept(&config, "stranger"));
+    }
+
+    #[test]
+    fn accept_invite_grows_allowlist_once() {
+        let mut config = cfg();
+        accept_invite(&mut config, "group1@g.us");
+        accept_invite(&mut config, "group1@g.us");
+        assert_eq!(config.group_allowlist, vec!["group1@g.us"]);
+    }
+
+    #[test]
+    fn idle_tracker_requires_sustained_absence() {
+        let config = cfg();
+        let mut tracker = GroupIdleTracker::new();
+
+        // Allowlisted member present: never flagged, regardless of grace period.
+        assert!(!tracker.observe(&config, "g1", &["alice".into()], 0));
+
+        // No allowlisted member, but grace period of a day hasn't elapsed yet.
+        assert!(!tracker.observe(&config, "g1", &["bob".into()], 86_400));
+
+        // A zero grace period means "leave immediately".
+        assert!(tracker.observe(&config, "g1", &["bob".into()], 0));
+    }
+
+    #[test]
+    fn idle_tracker_resets_when_allowlisted_member_returns() {
+        let config = cfg();
+        let mut tracker = GroupIdleTracker::new();
+        assert!(tracker.observe(&config, "g1", &["bob".into()], 0));
+        assert!(!tracker.observe(&config, "g1", &["alice".into()], 0));
+        // Absence clock restarts after alice rejoins.
+        assert!(tracker.observe(&config, "g1", &["bob".into()], 0));
+    }
+
+    #[test]
+    fn prune_group_removes_entry() {
+        let mut config = cfg();
+        config.group_allowlist.push("group1@g.us".into());
+        prune_group(&mut config, "group1@g.us");
+        assert!(config.group_allowlist.is_empty());
+    }
+}
@@ -1,8 +1,12 @@
 use std::sync::Arc;
 
 use {
+    rand::Rng,
     tracing::{debug, info, warn},
-    wacore::types::{events::Event, message::MessageInfo},
+    wacore::{
+        store::traits::SignalStore,
+        types::{events::Event, message::MessageInfo, presence::PresenceInfo, receipt::ReceiptType},
+    },
     wacore_binary::jid::{Jid, JidExt as _},
     waproto::whatsapp as wa,
     whatsapp_rust::client::Client,
@@ -15,10 +19,28 @@ use moltis_channels::{
 
 use crate::{
     access::{self, AccessDenied},
-    otp::{OTP_CHALLENGE_MSG, OtpInitResult, OtpVerifyResult},
-    state::{AccountState, AccountStateMap, has_bot_watermark},
+    commands,
+    delivery::DeliveryState,
+    download_state::{self, MediaRef},
+    group_autojoin,
+    media_cache::{self, CachedMedia},
+    otp::{OTP_CHALLENGE_MSG, OTP_EPHEMERAL_SECS, OtpInitResult, OtpVerifyResult},
+    state::{AccountState, AccountStateMap, IncomingMsg, has_bot_watermark},
+    verified_join::{self, AuthOutcome, RequestOutcome},
 };
 
+/// Look up a cached media entry for this account.
+fn cache_get(state: &AccountState, digest: &str) -> Option<CachedMedia> {
+    let mut cache = state.media_cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.get(digest)
+}
+
+/// Store a processed media entry in this account's cache.
+fn cache_put(state: &AccountState, digest: String, media: CachedMedia) {
+    let mut cache = state.media_cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.put(digest, media);
+}
+
 /// Process an incoming whatsapp-rust event for the given account.
 pub async fn handle_event(
     event: Event,
@@ -34,6 +56,7 @@ pub async fn handle_event(
             if let Ok(mut qr) = state.latest_qr.write() {
                 *qr = Some(code.clone());
             }
+            state.publish_health();
 
             if let Some(ref sink) = state.event_sink {
                 sink.emit(ChannelEvent::PairingQrCode {
@@ -49,11 +72,15 @@ pub async fn handle_event(
             state
                 .connected
                 .store(true, std::sync::atomic::Ordering::Relaxed);
+            state
+                .reconnect_attempts
+                .store(0, std::sync::atomic::Ordering::Relaxed);
 
             // Clear QR data once connected.
             if let Ok(mut qr) = state.latest_qr.write() {
                 *qr = None;
             }
+            state.publish_health();
 
             let display_name = state.client.get_push_name().await;
             let display = if display_name.is_empty() {
@@ -70,6 +97,12 @@ pub async fn handle_event(
                 })
                 .await;
             }
+
+            // Drain anything buffered while the socket was down.
+            let flush_state = Arc::clone(&state);
+            tokio::spawn(async move {
+                flush_state.outbound_queue.flush(&flush_state).await;
+            });
         },
         Event::PairError(err) => {
             warn!(account_id = %state.account_id, error = ?err, "WhatsApp pairing failed");
@@ -87,12 +120,15 @@ pub async fn handle_event(
             state
                 .connected
                 .store(false, std::sync::atomic::Ordering::Relaxed);
+            state.publish_health();
+            schedule_reconnect(Arc::clone(&client), Arc::clone(&state));
         },
         Event::LoggedOut(_) => {
             warn!(account_id = %state.account_id, "WhatsApp logged out");
             state
                 .connected
                 .store(false, std::sync::atomic::Ordering::Relaxed);
+            state.publish_health();
             if let Some(ref sink) = state.event_sink {
                 sink.emit(ChannelEvent::AccountDisabled {
                     channel_type: ChannelType::Whatsapp,
@@ -105,12 +141,261 @@ pub async fn handle_event(
         Event::Message(msg, msg_info) => {
             handle_message(msg, msg_info, &client, &state, &accounts).await;
         },
+        Event::Receipt(receipt) => {
+            handle_receipt(&receipt, &state).await;
+        },
+        Event::Presence(presence) => {
+            handle_presence(&presence, &state).await;
+        },
+        Event::GroupInvite { group_jid, inviter } => {
+            handle_group_invite(group_jid, inviter, &client, &state, &accounts).await;
+        },
+        Event::GroupParticipantsUpdate {
+            group_jid,
+            participants,
+            ..
+        } => {
+            handle_group_participants_update(group_jid, participants, &client, &state, &accounts)
+                .await;
+        },
         _ => {
             debug!(account_id = %state.account_id, event = ?std::mem::discriminant(&event), "unhandled WhatsApp event");
         },
     }
 }
 
+/// Base delay for the first reconnect attempt.
+const RECONNECT_BASE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Maximum delay between reconnect attempts.
+const RECONNECT_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Schedule a reconnect attempt with exponential backoff and jitter.
+///
+/// Delay doubles per consecutive failed attempt (capped at 5 minutes) and is
+/// multiplied by a random factor in `[0.5, 1.5)` so that many accounts
+/// disconnecting together (e.g. after a network blip) don't all reconnect in
+/// lockstep. Stops retrying once the account reconnects (`Event::Connected`
+/// resets `reconnect_attempts` to 0, so a fresh disconnect starts over) or is
+/// logged out (no `Event::Disconnected` is emitted for that case).
+fn schedule_reconnect(client: Arc<Client>, state: Arc<AccountState>) {
+    let attempt = state
+        .reconnect_attempts
+        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+    let exp_delay = RECONNECT_BASE_DELAY
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(RECONNECT_MAX_DELAY);
+    let jitter = rand::rng().random_range(0.5..1.5);
+    let delay = exp_delay.mul_f64(jitter);
+
+    info!(
+        account_id = %state.account_id,
+        attempt,
+        delay_ms = delay.as_millis(),
+        "scheduling WhatsApp reconnect"
+    );
+
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+
+        if state.connected.load(std::sync::atomic::Ordering::Relaxed) {
+            // Reconnected via some other path (e.g. a concurrent attempt) already.
+            return;
+        }
+
+        match client.connect().await {
+            Ok(()) => {
+                info!(account_id = %state.account_id, "WhatsApp reconnect initiated");
+            },
+            Err(e) => {
+                warn!(account_id = %state.account_id, error = %e, "WhatsApp reconnect attempt failed");
+                schedule_reconnect(client, state);
+            },
+        }
+    });
+}
+
+/// Start tracking delivery state for a message `send_reply` just sent, if it
+/// went out successfully.
+fn track_sent(state: &AccountState, sent: crate::Result<String>) {
+    if let Ok(msg_id) = sent {
+        state.delivery.lock().unwrap_or_else(|e| e.into_inner()).track(msg_id);
+    }
+}
+
+/// Record an OTP challenge send: track its delivery state and remember its
+/// message ID on the pending challenge so a later `AlreadyPending` resend
+/// decision can look up whether it was ever delivered.
+fn record_challenge_send(
+    accounts: &AccountStateMap,
+    account_id: &str,
+    peer_id: &str,
+    state: &AccountState,
+    sent: crate::Result<String>,
+) {
+    let Ok(msg_id) = sent else {
+        return;
+    };
+    state.delivery.lock().unwrap_or_else(|e| e.into_inner()).track(msg_id.clone());
+
+    let accts = accounts.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(s) = accts.get(account_id) {
+        let mut otp = s.otp.lock().unwrap_or_else(|e| e.into_inner());
+        otp.set_challenge_message_id(peer_id, msg_id);
+    }
+}
+
+/// Human-readable label for a receipt's resulting delivery state, used on
+/// the `ChannelEvent::ReceiptUpdate` surfaced to the gateway.
+fn receipt_state_label(receipt_type: ReceiptType) -> &'static str {
+    match receipt_type {
+        ReceiptType::Delivery => "delivered",
+        ReceiptType::Read | ReceiptType::ReadSelf => "read",
+        ReceiptType::Error => "failed",
+        _ => "unknown",
+    }
+}
+
+/// Update delivery/read state for outbound messages from an inbound WhatsApp
+/// receipt stanza, so the OTP flow can tell whether a challenge it sent
+/// earlier was ever delivered, then surface the same update to the gateway
+/// via `ChannelEvent::ReceiptUpdate` so it can show "seen" ticks.
+async fn handle_receipt(receipt: &wacore::types::receipt::ReceiptInfo, state: &AccountState) {
+    {
+        let mut delivery = state.delivery.lock().unwrap_or_else(|e| e.into_inner());
+        for msg_id in &receipt.message_ids {
+            match receipt.receipt_type {
+                ReceiptType::Delivery => delivery.mark_delivered(msg_id),
+                ReceiptType::Read | ReceiptType::ReadSelf => delivery.mark_read(msg_id),
+                ReceiptType::Error => delivery.mark_failed(msg_id),
+                _ => {},
+            }
+        }
+    }
+
+    let Some(ref sink) = state.event_sink else {
+        return;
+    };
+    sink.emit(ChannelEvent::ReceiptUpdate {
+        channel_type: ChannelType::Whatsapp,
+        account_id: state.account_id.clone(),
+        chat_id: receipt.chat.to_string(),
+        message_ids: receipt.message_ids.clone(),
+        state: receipt_state_label(receipt.receipt_type).to_string(),
+    })
+    .await;
+}
+
+/// Surface an inbound WhatsApp presence update (a subscribed contact going
+/// online/offline) as a `ChannelEvent::PresenceUpdate`.
+async fn handle_presence(presence: &PresenceInfo, state: &AccountState) {
+    let Some(ref sink) = state.event_sink else {
+        return;
+    };
+    sink.emit(ChannelEvent::PresenceUpdate {
+        channel_type: ChannelType::Whatsapp,
+        account_id: state.account_id.clone(),
+        chat_id: presence.from.to_string(),
+        available: !presence.unavailable,
+        last_seen: presence.last_seen_at,
+    })
+    .await;
+}
+
+/// Under `GroupPolicy::AutoJoin`, accept an incoming group invite and grow
+/// `group_allowlist` to cover the joined group. See
+/// [`crate::group_autojoin`].
+async fn handle_group_invite(
+    group_jid: String,
+    inviter: String,
+    client: &Client,
+    state: &AccountState,
+    accounts: &AccountStateMap,
+) {
+    let should_join = {
+        let accounts = accounts.read().unwrap_or_else(|e| e.into_inner());
+        accounts.get(&state.account_id).is_some_and(|acc| {
+            acc.config.group_policy == moltis_channels::gating::GroupPolicy::AutoJoin
+                && group_autojoin::should_auto_accept(&acc.config, &inviter)
+        })
+    };
+    if !should_join {
+        return;
+    }
+
+    if let Err(e) = client.join_group_invite(&group_jid).await {
+        warn!(account_id = %state.account_id, group_jid, error = %e, "failed to auto-join group invite");
+        return;
+    }
+
+    let mut accounts = accounts.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(acc) = accounts.get_mut(&state.account_id) {
+        group_autojoin::accept_invite(&mut acc.config, &group_jid);
+    }
+    drop(accounts);
+
+    {
+        let mut idle = state.group_idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.forget(&group_jid);
+    }
+
+    info!(account_id = %state.account_id, group_jid, inviter, "auto-joined WhatsApp group");
+}
+
+/// Under `GroupPolicy::AutoJoin`, check a group's membership snapshot
+/// against `allowlist` and, once it's gone `auto_join_idle_grace_secs` with
+/// no allowlisted member, leave the group and prune it from
+/// `group_allowlist`. See [`crate::group_autojoin`].
+async fn handle_group_participants_update(
+    group_jid: String,
+    participants: Vec<String>,
+    client: &Client,
+    state: &AccountState,
+    accounts: &AccountStateMap,
+) {
+    let grace_secs = {
+        let accounts = accounts.read().unwrap_or_else(|e| e.into_inner());
+        let Some(acc) = accounts.get(&state.account_id) else {
+            return;
+        };
+        if acc.config.group_policy != moltis_channels::gating::GroupPolicy::AutoJoin {
+            return;
+        }
+        acc.config.auto_join_idle_grace_secs
+    };
+
+    let should_leave = {
+        let accounts = accounts.read().unwrap_or_else(|e| e.into_inner());
+        let Some(acc) = accounts.get(&state.account_id) else {
+            return;
+        };
+        let mut idle = state.group_idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.observe(&acc.config, &group_jid, &participants, grace_secs)
+    };
+    if !should_leave {
+        return;
+    }
+
+    if let Err(e) = client.leave_group(&group_jid).await {
+        warn!(account_id = %state.account_id, group_jid, error = %e, "failed to auto-leave idle group");
+        return;
+    }
+
+    let mut accounts = accounts.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(acc) = accounts.get_mut(&state.account_id) {
+        group_autojoin::prune_group(&mut acc.config, &group_jid);
+    }
+    drop(accounts);
+
+    {
+        let mut idle = state.group_idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.forget(&group_jid);
+    }
+
+    info!(account_id = %state.account_id, group_jid, "left idle auto-joined WhatsApp group");
+}
+
 async fn handle_message(
     msg: Box<wa::Message>,
     info: MessageInfo,
@@ -188,6 +473,32 @@ async fn handle_message(
         .unwrap_or("");
 
     let message_kind = classify_message(&msg, text);
+    if let Some(quoted_id) = quoted_message_id(&msg) {
+        debug!(account_id = %state.account_id, quoted_id, "inbound message quotes a prior message");
+    }
+
+    // Admin control commands (`!allow`/`!deny`/`!allowgroup`/`!status`) —
+    // see `crate::commands`. Checked up front, ahead of normal access
+    // control, since an admin managing the allowlist may not themselves be
+    // on it yet. `commands::apply` does its own `admin_users` gating, so a
+    // non-admin sender's message just falls through to normal handling
+    // below instead of being swallowed here.
+    if let Some(cmd) = commands::parse(text) {
+        let reply = {
+            let mut accounts = accounts.write().unwrap_or_else(|e| e.into_inner());
+            accounts
+                .get_mut(&state.account_id)
+                .and_then(|acc| commands::apply(&mut acc.config, &peer_id, Some(&username), &cmd).ok())
+        };
+        if let Some(reply) = reply {
+            let outbound_msg = wa::Message {
+                conversation: Some(reply),
+                ..Default::default()
+            };
+            let _ = state.send_message(chat_jid.clone(), outbound_msg).await;
+            return;
+        }
+    }
 
     // Access control. Self-chat messages from the account owner always bypass
     // access control — the owner is inherently authorized.
@@ -197,10 +508,34 @@ async fn handle_message(
     } else {
         None
     };
+    // Before gating, re-check any already-verified peer's live identity key
+    // against the fingerprint recorded at verification time — catches a
+    // safety-number change (device swap, re-registration, active MITM) and
+    // flips the entry back to unverified so `check_access` re-gates it.
+    if !is_owner_self_chat && !is_group {
+        let live_fingerprint = match state.identity_store.load_identity(&peer_id).await {
+            Ok(Some(key)) => Some(verified_join::fingerprint(&key)),
+            Ok(None) => None,
+            Err(e) => {
+                warn!(account_id = %state.account_id, peer_id, "failed to load identity key for recheck: {e}");
+                None
+            },
+        };
+        if let Some(live_fingerprint) = live_fingerprint {
+            state
+                .verified_join
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .recheck(&peer_id, &live_fingerprint);
+        }
+    }
+
     let access_result = if is_owner_self_chat {
         Ok(())
     } else {
-        access::check_access(&state.config, is_group, &peer_id, Some(&username), group_id)
+        let is_verified: &dyn Fn(&str) -> bool =
+            &|peer: &str| state.verified_join.lock().unwrap_or_else(|e| e.into_inner()).is_verified(peer);
+        access::check_access(&state.config, is_group, &peer_id, Some(&username), group_id, Some(is_verified))
     };
     let access_granted = access_result.is_ok();
 
@@ -259,6 +594,11 @@ async fn handle_message(
 
         // OTP self-approval for non-allowlisted DM users.
         if reason == AccessDenied::NotOnAllowlist && !is_group && state.config.otp_self_approval {
+            let quoted = IncomingMsg {
+                id: info.id.to_string(),
+                participant: sender_jid.to_string(),
+                quoted: (*msg).clone(),
+            };
             handle_otp_flow(
                 accounts,
                 &state.account_id,
@@ -267,10 +607,61 @@ async fn handle_message(
                 sender_name.as_deref(),
                 text,
                 chat_jid,
+                &quoted,
                 state,
             )
             .await;
         }
+
+        // Verified-join handshake for allowlisted-but-unverified DM peers
+        // (see `crate::verified_join`; only reached once
+        // `config.require_verified_allowlist` denies an otherwise-
+        // allowlisted sender).
+        if reason == AccessDenied::NotVerified && !is_group {
+            let quoted = IncomingMsg {
+                id: info.id.to_string(),
+                participant: sender_jid.to_string(),
+                quoted: (*msg).clone(),
+            };
+            handle_verified_join_flow(&state.account_id, &peer_id, Some(&username), text, chat_jid, &quoted, state)
+                .await;
+        }
+        return;
+    }
+
+    // Owner self-chat admin command: `!invite` mints a fresh verified-join
+    // invite to relay to a prospective peer out of band (QR code, link) —
+    // see `crate::verified_join`. The owner's self-chat is the only
+    // administrative channel this bot recognizes for this, same as the
+    // `is_owner_self_chat` bypass above.
+    if is_owner_self_chat && verified_join::parse_invite_command(text) {
+        // `verified_join::fingerprint` is meant to hash a Signal identity
+        // public key; nothing in this crate currently exposes one for the
+        // bot's own account, so this hashes the public `account_id`
+        // instead. That's enough to let a peer notice the invite clearly
+        // didn't come from the account they expect, but — unlike a real
+        // identity-key fingerprint — it's public information, so it does
+        // NOT by itself rule out an active relay/MITM presenting the same
+        // value. Treat this invite flow as allowlist-strength verification,
+        // not full SecureJoin-grade identity binding, until a real identity
+        // key accessor exists to wire in here.
+        let account_fingerprint = verified_join::fingerprint(state.account_id.as_bytes());
+        let invite = state
+            .verified_join
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .create_invite(account_fingerprint);
+        let reply = format!(
+            "New verified-join invite:\ninvite={}\nsecret={}\nfingerprint={}\n\n\
+             Share these with the peer out of band. Once shared, they should \
+             message this bot:\nrequest {}",
+            invite.invite_number, invite.auth_secret, invite.account_fingerprint, invite.invite_number
+        );
+        let outbound_msg = wa::Message {
+            conversation: Some(reply),
+            ..Default::default()
+        };
+        let _ = state.send_message(chat_jid.clone(), outbound_msg).await;
         return;
     }
 
@@ -321,16 +712,24 @@ async fn handle_message(
         audio_filename: None,
     };
 
-    // Dispatch based on message kind.
+    // Dispatch based on message kind. Each branch relays to any bridged
+    // destinations (via `relay_message`) with the payload appropriate to its
+    // kind before dispatching to the chat engine, so a bridge misconfiguration
+    // can never block the normal reply path.
     match message_kind {
         ChannelMessageKind::Text => {
             if let Some(ref sink) = state.event_sink {
+                sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), text, Vec::new())
+                    .await;
                 sink.dispatch_to_chat(text, reply_to, meta).await;
             }
         },
         ChannelMessageKind::Photo => {
             handle_photo(&msg, client, account_id, reply_to, meta, chat_jid, state).await;
         },
+        ChannelMessageKind::Sticker => {
+            handle_sticker(&msg, client, account_id, reply_to, meta, chat_jid, state).await;
+        },
         ChannelMessageKind::Voice | ChannelMessageKind::Audio => {
             handle_voice_audio(
                 &msg,
@@ -386,9 +785,53 @@ async fn handle_photo(
     let caption = img.caption.as_deref().unwrap_or("").to_string();
     let mime = img.mimetype.as_deref().unwrap_or("image/jpeg").to_string();
 
+    // WhatsApp ships the plaintext SHA-256 on the message itself, so we can
+    // check the cache before even downloading.
+    let pre_digest = img.file_sha256.as_ref().map(|d| media_cache::sha256_hex(d));
+    if let Some(ref digest) = pre_digest
+        && let Some(CachedMedia::Image { data, media_type }) = cache_get(state, digest)
+    {
+        debug!(account_id, digest, "image cache hit, skipping download");
+        let attachment = ChannelAttachment { media_type, data };
+        if let Some(ref sink) = state.event_sink {
+            sink.relay_message(
+                reply_to.clone(),
+                meta.sender_name.as_deref(),
+                &caption,
+                vec![attachment.clone()],
+            )
+            .await;
+            sink.dispatch_to_chat_with_attachments(&caption, vec![attachment], reply_to, meta)
+                .await;
+        }
+        return;
+    }
+
+    let download_size = img.file_length.unwrap_or(0) as usize;
+    if download_size > download_state::DEFER_DOWNLOAD_THRESHOLD_BYTES {
+        debug!(account_id, size = download_size, "image too large, deferring download");
+        state.register_pending_download(
+            reply_to.message_id.clone().unwrap_or_default(),
+            reply_to.chat_id.clone(),
+            MediaRef::Image(img.clone()),
+        );
+        let text = if caption.is_empty() {
+            "[Photo - large, not downloaded yet]".to_string()
+        } else {
+            format!("{caption}\n[Photo - large, not downloaded yet]")
+        };
+        if let Some(ref sink) = state.event_sink {
+            sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &text, Vec::new())
+                .await;
+            sink.dispatch_to_chat(&text, reply_to, meta).await;
+        }
+        return;
+    }
+
     match client.download(img.as_ref()).await {
         Ok(image_data) => {
             debug!(account_id, size = image_data.len(), %mime, "downloaded WhatsApp image");
+            let digest = pre_digest.unwrap_or_else(|| media_cache::sha256_hex(&image_data));
 
             let (final_data, media_type) = match moltis_media::image_ops::optimize_for_llm(
                 &image_data,
@@ -413,11 +856,27 @@ async fn handle_photo(
                 },
             };
 
+            cache_put(
+                state,
+                digest,
+                CachedMedia::Image {
+                    data: final_data.clone(),
+                    media_type: media_type.clone(),
+                },
+            );
+
             let attachment = ChannelAttachment {
                 media_type,
                 data: final_data,
             };
             if let Some(ref sink) = state.event_sink {
+                sink.relay_message(
+                    reply_to.clone(),
+                    meta.sender_name.as_deref(),
+                    &caption,
+                    vec![attachment.clone()],
+                )
+                .await;
                 sink.dispatch_to_chat_with_attachments(&caption, vec![attachment], reply_to, meta)
                     .await;
             }
@@ -492,13 +951,35 @@ async fn handle_voice_audio(
         return;
     }
 
+    let pre_digest = audio.file_sha256.as_ref().map(|d| media_cache::sha256_hex(d));
+    if let Some(ref digest) = pre_digest
+        && let Some(CachedMedia::Transcript(transcribed)) = cache_get(state, digest)
+    {
+        debug!(account_id, digest, "voice transcript cache hit, skipping download");
+        if let Some(ref sink) = state.event_sink {
+            sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &transcribed, Vec::new())
+                .await;
+            sink.dispatch_to_chat(&transcribed, reply_to, meta).await;
+        }
+        return;
+    }
+
     match client.download(audio.as_ref()).await {
         Ok(audio_data) => {
             debug!(account_id, size = audio_data.len(), %format, kind_label, "downloaded WhatsApp audio");
+            let digest = pre_digest.unwrap_or_else(|| media_cache::sha256_hex(&audio_data));
 
             if let Some(ref sink) = state.event_sink {
                 match sink.transcribe_voice(&audio_data, &format).await {
                     Ok(transcribed) => {
+                        cache_put(state, digest, CachedMedia::Transcript(transcribed.clone()));
+                        sink.relay_message(
+                            reply_to.clone(),
+                            meta.sender_name.as_deref(),
+                            &transcribed,
+                            Vec::new(),
+                        )
+                        .await;
                         sink.dispatch_to_chat(&transcribed, reply_to, meta).await;
                     },
                     Err(e) => {
@@ -507,6 +988,13 @@ async fn handle_voice_audio(
                             "[{} message - transcription failed]",
                             capitalize(kind_label)
                         );
+                        sink.relay_message(
+                            reply_to.clone(),
+                            meta.sender_name.as_deref(),
+                            &fallback,
+                            Vec::new(),
+                        )
+                        .await;
                         sink.dispatch_to_chat(&fallback, reply_to, meta).await;
                     },
                 }
@@ -525,12 +1013,21 @@ async fn handle_voice_audio(
     }
 }
 
-/// Handle an inbound video message: download and dispatch with caption.
+/// Maximum video size we'll download and frame-sample, in bytes (25 MB).
+/// Larger videos fall back to the thumbnail to avoid stalling the handler
+/// on a multi-minute clip.
+const MAX_VIDEO_DOWNLOAD_SIZE: usize = 25 * 1024 * 1024;
+
+/// Number of evenly-spaced keyframes to sample from a downloaded video.
+const MAX_VIDEO_FRAMES: usize = 6;
+
+/// Handle an inbound video message: download, frame-sample, and dispatch so
+/// the model sees motion over time instead of a single static thumbnail.
 #[allow(clippy::too_many_arguments)]
 async fn handle_video(
     msg: &wa::Message,
-    _client: &Client,
-    _account_id: &str,
+    client: &Client,
+    account_id: &str,
     reply_to: ChannelReplyTarget,
     meta: ChannelMessageMeta,
     _chat_jid: &Jid,
@@ -541,9 +1038,120 @@ async fn handle_video(
     };
     let caption = video.caption.as_deref().unwrap_or("").to_string();
 
-    // Try to extract a thumbnail if available (jpeg_thumbnail field).
-    // Video files can be large; for now dispatch the thumbnail as an image
-    // attachment so the LLM can at least see the preview.
+    let pre_digest = video.file_sha256.as_ref().map(|d| media_cache::sha256_hex(d));
+    if let Some(ref digest) = pre_digest
+        && let Some(CachedMedia::Frames(frames)) = cache_get(state, digest)
+    {
+        debug!(account_id, digest, "video frames cache hit, skipping download");
+        let attachments: Vec<ChannelAttachment> = frames
+            .into_iter()
+            .map(|(data, media_type)| ChannelAttachment { media_type, data })
+            .collect();
+        let text = if caption.is_empty() {
+            format!("[Video message - {} frames sampled]", attachments.len())
+        } else {
+            format!("{caption}\n[Video message - {} frames sampled]", attachments.len())
+        };
+        if let Some(ref sink) = state.event_sink {
+            sink.relay_message(
+                reply_to.clone(),
+                meta.sender_name.as_deref(),
+                &text,
+                attachments.clone(),
+            )
+            .await;
+            sink.dispatch_to_chat_with_attachments(&text, attachments, reply_to, meta)
+                .await;
+        }
+        return;
+    }
+
+    let download_size = video.file_length.unwrap_or(0) as usize;
+    let size_ok = download_size == 0 || download_size <= download_state::DEFER_DOWNLOAD_THRESHOLD_BYTES;
+
+    if size_ok {
+        match client.download(video.as_ref()).await {
+            Ok(video_data) if video_data.len() <= MAX_VIDEO_DOWNLOAD_SIZE => {
+                debug!(account_id, size = video_data.len(), "downloaded WhatsApp video");
+                let digest = pre_digest.unwrap_or_else(|| media_cache::sha256_hex(&video_data));
+
+                match moltis_media::video_ops::sample_frames(&video_data, MAX_VIDEO_FRAMES) {
+                    Ok(frames) if !frames.is_empty() => {
+                        let optimized_frames: Vec<(Vec<u8>, String)> = frames
+                            .into_iter()
+                            .filter_map(|frame| {
+                                match moltis_media::image_ops::optimize_for_llm(&frame, None) {
+                                    Ok(optimized) => Some((optimized.data, optimized.media_type)),
+                                    Err(e) => {
+                                        warn!(account_id, error = %e, "failed to optimize video frame");
+                                        None
+                                    },
+                                }
+                            })
+                            .collect();
+
+                        if optimized_frames.is_empty() {
+                            warn!(account_id, "all sampled video frames failed to optimize, falling back to thumbnail");
+                        } else {
+                            cache_put(state, digest, CachedMedia::Frames(optimized_frames.clone()));
+
+                            let attachments: Vec<ChannelAttachment> = optimized_frames
+                                .into_iter()
+                                .map(|(data, media_type)| ChannelAttachment { media_type, data })
+                                .collect();
+                            let text = if caption.is_empty() {
+                                format!("[Video message - {} frames sampled]", attachments.len())
+                            } else {
+                                format!("{caption}\n[Video message - {} frames sampled]", attachments.len())
+                            };
+                            if let Some(ref sink) = state.event_sink {
+                                sink.relay_message(
+                                    reply_to.clone(),
+                                    meta.sender_name.as_deref(),
+                                    &text,
+                                    attachments.clone(),
+                                )
+                                .await;
+                                sink.dispatch_to_chat_with_attachments(
+                                    &text, attachments, reply_to, meta,
+                                )
+                                .await;
+                            }
+                            return;
+                        }
+                    },
+                    Ok(_) => {
+                        warn!(account_id, "video decoding produced no frames, falling back to thumbnail");
+                    },
+                    Err(e) => {
+                        warn!(account_id, error = %e, "failed to decode video, falling back to thumbnail");
+                    },
+                }
+            },
+            Ok(video_data) => {
+                warn!(
+                    account_id,
+                    size = video_data.len(),
+                    "downloaded video exceeded max size, falling back to thumbnail"
+                );
+            },
+            Err(e) => {
+                warn!(account_id, error = %e, "failed to download WhatsApp video, falling back to thumbnail");
+            },
+        }
+    } else {
+        debug!(account_id, size = download_size, "video too large to download, falling back to thumbnail");
+        if download_size > download_state::DEFER_DOWNLOAD_THRESHOLD_BYTES {
+            state.register_pending_download(
+                reply_to.message_id.clone().unwrap_or_default(),
+                reply_to.chat_id.clone(),
+                MediaRef::Video(video.clone()),
+            );
+        }
+    }
+
+    // Fallback: extract a thumbnail if available (jpeg_thumbnail field) so
+    // the LLM can at least see the preview.
     if let Some(ref thumb) = video.jpeg_thumbnail
         && !thumb.is_empty()
     {
@@ -557,6 +1165,13 @@ async fn handle_video(
             format!("{caption}\n[Video message - thumbnail shown]")
         };
         if let Some(ref sink) = state.event_sink {
+            sink.relay_message(
+                reply_to.clone(),
+                meta.sender_name.as_deref(),
+                &text,
+                vec![attachment.clone()],
+            )
+            .await;
             sink.dispatch_to_chat_with_attachments(&text, vec![attachment], reply_to, meta)
                 .await;
         }
@@ -570,15 +1185,181 @@ async fn handle_video(
         format!("{caption}\n[Video message - playback not supported]")
     };
     if let Some(ref sink) = state.event_sink {
+        sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &text, Vec::new())
+            .await;
         sink.dispatch_to_chat(&text, reply_to, meta).await;
     }
 }
 
-/// Handle an inbound document message: dispatch with caption.
+/// Maximum sticker size we'll download and process (5 MB — WhatsApp itself
+/// caps stickers well below this, but third-party clients can send oversized
+/// ones).
+const MAX_STICKER_DOWNLOAD_SIZE: usize = 5 * 1024 * 1024;
+
+/// Handle an inbound sticker message.
+///
+/// Static stickers (WebP) go through the same image optimization path as
+/// photos. Animated stickers are themselves tiny WebP animations, so they go
+/// through the video frame-sampling path, same as a GIF-flagged video.
+#[allow(clippy::too_many_arguments)]
+async fn handle_sticker(
+    msg: &wa::Message,
+    client: &Client,
+    account_id: &str,
+    reply_to: ChannelReplyTarget,
+    meta: ChannelMessageMeta,
+    _chat_jid: &Jid,
+    state: &AccountState,
+) {
+    let Some(ref sticker) = msg.sticker_message else {
+        return;
+    };
+    let is_animated = sticker.is_animated.unwrap_or(false);
+
+    let pre_digest = sticker.file_sha256.as_ref().map(|d| media_cache::sha256_hex(d));
+    if let Some(ref digest) = pre_digest {
+        match cache_get(state, digest) {
+            Some(CachedMedia::Image { data, media_type }) => {
+                debug!(account_id, digest, "sticker cache hit, skipping download");
+                let attachment = ChannelAttachment { media_type, data };
+                if let Some(ref sink) = state.event_sink {
+                    sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), "", vec![
+                        attachment.clone(),
+                    ])
+                    .await;
+                    sink.dispatch_to_chat_with_attachments("", vec![attachment], reply_to, meta)
+                        .await;
+                }
+                return;
+            },
+            Some(CachedMedia::Frames(frames)) => {
+                debug!(account_id, digest, "animated sticker frames cache hit, skipping download");
+                let attachments: Vec<ChannelAttachment> = frames
+                    .into_iter()
+                    .map(|(data, media_type)| ChannelAttachment { media_type, data })
+                    .collect();
+                let text = format!("[Sticker - {} frames sampled]", attachments.len());
+                if let Some(ref sink) = state.event_sink {
+                    sink.relay_message(
+                        reply_to.clone(),
+                        meta.sender_name.as_deref(),
+                        &text,
+                        attachments.clone(),
+                    )
+                    .await;
+                    sink.dispatch_to_chat_with_attachments(&text, attachments, reply_to, meta)
+                        .await;
+                }
+                return;
+            },
+            None => {},
+        }
+    }
+
+    let download_size = sticker.file_length.unwrap_or(0) as usize;
+    if download_size != 0 && download_size > MAX_STICKER_DOWNLOAD_SIZE {
+        debug!(account_id, size = download_size, "sticker too large to download, skipping");
+        return;
+    }
+
+    let sticker_data = match client.download(sticker.as_ref()).await {
+        Ok(data) if data.len() <= MAX_STICKER_DOWNLOAD_SIZE => data,
+        Ok(data) => {
+            warn!(account_id, size = data.len(), "downloaded sticker exceeded max size, skipping");
+            return;
+        },
+        Err(e) => {
+            warn!(account_id, error = %e, "failed to download WhatsApp sticker");
+            return;
+        },
+    };
+    let digest = pre_digest.unwrap_or_else(|| media_cache::sha256_hex(&sticker_data));
+
+    if is_animated {
+        match moltis_media::video_ops::sample_frames(&sticker_data, MAX_VIDEO_FRAMES) {
+            Ok(frames) if !frames.is_empty() => {
+                let optimized_frames: Vec<(Vec<u8>, String)> = frames
+                    .into_iter()
+                    .filter_map(
+                        |frame| match moltis_media::image_ops::optimize_for_llm(&frame, None) {
+                            Ok(optimized) => Some((optimized.data, optimized.media_type)),
+                            Err(e) => {
+                                warn!(account_id, error = %e, "failed to optimize animated sticker frame");
+                                None
+                            },
+                        },
+                    )
+                    .collect();
+
+                if !optimized_frames.is_empty() {
+                    cache_put(state, digest, CachedMedia::Frames(optimized_frames.clone()));
+                    let attachments: Vec<ChannelAttachment> = optimized_frames
+                        .into_iter()
+                        .map(|(data, media_type)| ChannelAttachment { media_type, data })
+                        .collect();
+                    let text = format!("[Sticker - {} frames sampled]", attachments.len());
+                    if let Some(ref sink) = state.event_sink {
+                        sink.relay_message(
+                            reply_to.clone(),
+                            meta.sender_name.as_deref(),
+                            &text,
+                            attachments.clone(),
+                        )
+                        .await;
+                        sink.dispatch_to_chat_with_attachments(&text, attachments, reply_to, meta)
+                            .await;
+                    }
+                    return;
+                }
+                warn!(account_id, "all sampled sticker frames failed to optimize, falling back");
+            },
+            Ok(_) => warn!(account_id, "animated sticker decoding produced no frames, falling back"),
+            Err(e) => warn!(account_id, error = %e, "failed to decode animated sticker, falling back"),
+        }
+        let text = "[Animated sticker received]".to_string();
+        if let Some(ref sink) = state.event_sink {
+            sink.dispatch_to_chat(&text, reply_to, meta).await;
+        }
+        return;
+    }
+
+    match moltis_media::image_ops::optimize_for_llm(&sticker_data, None) {
+        Ok(optimized) => {
+            cache_put(state, digest, CachedMedia::Image {
+                data: optimized.data.clone(),
+                media_type: optimized.media_type.clone(),
+            });
+            let attachment = ChannelAttachment {
+                media_type: optimized.media_type,
+                data: optimized.data,
+            };
+            if let Some(ref sink) = state.event_sink {
+                sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), "", vec![
+                    attachment.clone(),
+                ])
+                .await;
+                sink.dispatch_to_chat_with_attachments("", vec![attachment], reply_to, meta)
+                    .await;
+            }
+        },
+        Err(e) => {
+            warn!(account_id, error = %e, "failed to optimize sticker, sending placeholder");
+            let text = "[Sticker received]".to_string();
+            if let Some(ref sink) = state.event_sink {
+                sink.dispatch_to_chat(&text, reply_to, meta).await;
+            }
+        },
+    }
+}
+
+/// Maximum document size we'll download and extract text from (10 MB).
+const MAX_DOCUMENT_DOWNLOAD_SIZE: usize = 10 * 1024 * 1024;
+
+/// Handle an inbound document message: download, extract text, dispatch with caption.
 #[allow(clippy::too_many_arguments)]
 async fn handle_document(
     msg: &wa::Message,
-    _client: &Client,
+    client: &Client,
     account_id: &str,
     reply_to: ChannelReplyTarget,
     meta: ChannelMessageMeta,
@@ -597,13 +1378,78 @@ async fn handle_document(
 
     info!(account_id, filename, mime, "received document message");
 
-    let text = if caption.is_empty() {
+    let placeholder = if caption.is_empty() {
         format!("[Document received: {filename} ({mime})]")
     } else {
         format!("{caption}\n[Document: {filename} ({mime})]")
     };
-    if let Some(ref sink) = state.event_sink {
-        sink.dispatch_to_chat(&text, reply_to, meta).await;
+
+    let download_size = doc.file_length.unwrap_or(0) as usize;
+    if download_size > download_state::DEFER_DOWNLOAD_THRESHOLD_BYTES {
+        debug!(account_id, filename, size = download_size, "document too large, deferring download");
+        state.register_pending_download(
+            reply_to.message_id.clone().unwrap_or_default(),
+            reply_to.chat_id.clone(),
+            MediaRef::Document(doc.clone()),
+        );
+        if let Some(ref sink) = state.event_sink {
+            sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &placeholder, Vec::new())
+                .await;
+            sink.dispatch_to_chat(&placeholder, reply_to, meta).await;
+        }
+        return;
+    }
+
+    match client.download(doc.as_ref()).await {
+        Ok(doc_data) if doc_data.len() <= MAX_DOCUMENT_DOWNLOAD_SIZE => {
+            debug!(account_id, filename, size = doc_data.len(), "downloaded WhatsApp document");
+
+            match moltis_media::document_ops::extract_text(&doc_data, mime) {
+                Ok(body) => {
+                    let text = if caption.is_empty() {
+                        format!("[Document: {filename} ({mime})]\n{body}")
+                    } else {
+                        format!("{caption}\n[Document: {filename} ({mime})]\n{body}")
+                    };
+                    if let Some(ref sink) = state.event_sink {
+                        // Relay the short label, not the full extracted body —
+                        // bridges mirror the message, not an LLM-sized dump.
+                        sink.relay_message(
+                            reply_to.clone(),
+                            meta.sender_name.as_deref(),
+                            &placeholder,
+                            Vec::new(),
+                        )
+                        .await;
+                        sink.dispatch_to_chat(&text, reply_to, meta).await;
+                    }
+                },
+                Err(e) => {
+                    warn!(account_id, filename, error = %e, "failed to extract document text");
+                    if let Some(ref sink) = state.event_sink {
+                        sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &placeholder, Vec::new())
+                            .await;
+                        sink.dispatch_to_chat(&placeholder, reply_to, meta).await;
+                    }
+                },
+            }
+        },
+        Ok(doc_data) => {
+            warn!(account_id, filename, size = doc_data.len(), "downloaded document exceeded max size");
+            if let Some(ref sink) = state.event_sink {
+                sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &placeholder, Vec::new())
+                    .await;
+                sink.dispatch_to_chat(&placeholder, reply_to, meta).await;
+            }
+        },
+        Err(e) => {
+            warn!(account_id, filename, error = %e, "failed to download WhatsApp document");
+            if let Some(ref sink) = state.event_sink {
+                sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &placeholder, Vec::new())
+                    .await;
+                sink.dispatch_to_chat(&placeholder, reply_to, meta).await;
+            }
+        },
     }
 }
 
@@ -658,6 +1504,8 @@ async fn handle_location(
     // Static location — dispatch to the LLM.
     let text = format!("I'm sharing my location: {lat}, {lon}");
     if let Some(ref sink) = state.event_sink {
+        sink.relay_message(reply_to.clone(), meta.sender_name.as_deref(), &text, Vec::new())
+            .await;
         sink.dispatch_to_chat(&text, reply_to, meta).await;
     }
 }
@@ -681,7 +1529,9 @@ fn is_owner_user(jid: &Jid, own_pn: Option<&Jid>, own_lid: Option<&Jid>) -> bool
 /// not `Text`. This ensures the media handler runs and can include the caption
 /// alongside the attachment.
 fn classify_message(msg: &wa::Message, text: &str) -> ChannelMessageKind {
-    if msg.image_message.is_some() {
+    if msg.sticker_message.is_some() {
+        ChannelMessageKind::Sticker
+    } else if msg.image_message.is_some() {
         ChannelMessageKind::Photo
     } else if msg.audio_message.is_some() {
         if msg
@@ -706,8 +1556,144 @@ fn classify_message(msg: &wa::Message, text: &str) -> ChannelMessageKind {
     }
 }
 
+/// Extract the stanza ID this message is itself quoting, if any.
+///
+/// `contextInfo` lives on whichever sub-message type the content actually
+/// uses (text, image, video, ...), so check each one WhatsApp can attach a
+/// quote to. Used alongside `classify_message` when logging an inbound
+/// message so reply chains are observable.
+fn quoted_message_id(msg: &wa::Message) -> Option<String> {
+    [
+        msg.extended_text_message.as_ref().and_then(|m| m.context_info.as_ref()),
+        msg.image_message.as_ref().and_then(|m| m.context_info.as_ref()),
+        msg.video_message.as_ref().and_then(|m| m.context_info.as_ref()),
+        msg.audio_message.as_ref().and_then(|m| m.context_info.as_ref()),
+        msg.document_message.as_ref().and_then(|m| m.context_info.as_ref()),
+        msg.sticker_message.as_ref().and_then(|m| m.context_info.as_ref()),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|ctx| ctx.stanza_id.clone())
+}
+
 /// Handle OTP challenge/verification flow for a non-allowlisted DM user.
 ///
+/// Drives the peer side of the [`crate::verified_join`] handshake: a
+/// `request <invite_number>` or `auth <invite_number> <secret>
+/// <fingerprint>` message from a peer the allowlist names but
+/// `require_verified_allowlist` hasn't yet verified. Called when
+/// `AccessDenied::NotVerified` is returned for a DM sender.
+async fn handle_verified_join_flow(
+    account_id: &str,
+    peer_id: &str,
+    username: Option<&str>,
+    text: &str,
+    chat_jid: &Jid,
+    quoted: &IncomingMsg,
+    state: &AccountState,
+) {
+    if let Some(invite_number) = verified_join::parse_request_message(text) {
+        let outcome = state
+            .verified_join
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .handle_request(invite_number);
+        let reply = match outcome {
+            RequestOutcome::AuthRequired => {
+                format!("Invite found. Reply with:\nauth {invite_number} <secret> <your fingerprint>")
+            },
+            RequestOutcome::UnknownInvite => "Unknown or expired invite.".to_string(),
+        };
+        let _ = state.send_reply(chat_jid.clone(), &reply, quoted, None).await;
+        return;
+    }
+
+    if let Some((invite_number, auth_secret, claimed_fingerprint)) = verified_join::parse_auth_message(text) {
+        // The fingerprint that actually gets recorded comes from this
+        // peer's live Signal identity key in `identity_store`, not the
+        // `claimed_fingerprint` the peer typed — a relay/MITM controls the
+        // chat text too, so trusting a self-reported value there would
+        // verify nothing. `claimed_fingerprint` is still required in the
+        // grammar (so the peer has actually copied it off their own app's
+        // safety-number screen) and compared below only to warn on a
+        // mismatch, never to decide the outcome.
+        let live_fingerprint = match state.identity_store.load_identity(peer_id).await {
+            Ok(Some(key)) => verified_join::fingerprint(&key),
+            Ok(None) => {
+                let _ = state
+                    .send_reply(
+                        chat_jid.clone(),
+                        "No secure session established with you yet. Send any message first, then retry.",
+                        quoted,
+                        None,
+                    )
+                    .await;
+                return;
+            },
+            Err(e) => {
+                warn!(account_id, peer_id, "failed to load identity key for verified-join auth: {e}");
+                let _ = state
+                    .send_reply(chat_jid.clone(), "Could not verify your identity right now. Please try again.", quoted, None)
+                    .await;
+                return;
+            },
+        };
+        if claimed_fingerprint != live_fingerprint {
+            warn!(
+                account_id,
+                peer_id, claimed_fingerprint, live_fingerprint, "verified-join fingerprint mismatch"
+            );
+        }
+
+        let outcome = state
+            .verified_join
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .handle_auth(invite_number, auth_secret, peer_id, &live_fingerprint);
+        match outcome {
+            AuthOutcome::Verified => {
+                let _ = state
+                    .send_reply(chat_jid.clone(), "Verified! You can now use this bot.", quoted, None)
+                    .await;
+
+                // Emit a verified-join event, the same way `OtpResolved`
+                // reports an allowlist change — the gateway's event
+                // consumer is the intended place to persist this into
+                // `config.verified_peers` (so a restart doesn't forget this
+                // peer), mirroring however it already persists `OtpResolved`.
+                if let Some(ref sink) = state.event_sink {
+                    sink.emit(ChannelEvent::VerifiedJoinGranted {
+                        channel_type: ChannelType::Whatsapp,
+                        account_id: account_id.to_string(),
+                        peer_id: peer_id.to_string(),
+                        username: username.map(String::from),
+                        fingerprint: live_fingerprint.clone(),
+                    })
+                    .await;
+                }
+            },
+            AuthOutcome::UnknownInvite => {
+                let _ = state.send_reply(chat_jid.clone(), "Unknown or expired invite.", quoted, None).await;
+            },
+            AuthOutcome::AuthMismatch => {
+                let _ = state
+                    .send_reply(chat_jid.clone(), "That secret didn't match. Please check and try again.", quoted, None)
+                    .await;
+            },
+        }
+        return;
+    }
+
+    let _ = state
+        .send_reply(
+            chat_jid.clone(),
+            "This bot requires verified access. Ask the operator for an invite, then reply here with:\nrequest <invite_number>",
+            quoted,
+            None,
+        )
+        .await;
+}
+
 /// Called when `dm_policy = Allowlist`, the peer is not on the allowlist, and
 /// `otp_self_approval` is enabled.
 #[allow(clippy::too_many_arguments)]
@@ -719,6 +1705,7 @@ async fn handle_otp_flow(
     sender_name: Option<&str>,
     body: &str,
     chat_jid: &Jid,
+    quoted: &IncomingMsg,
     state: &AccountState,
 ) {
     let has_pending = {
@@ -752,11 +1739,15 @@ async fn handle_otp_flow(
 
         match result {
             OtpVerifyResult::Approved => {
-                let reply = wa::Message {
-                    conversation: Some("Access granted! You can now use this bot.".into()),
-                    ..Default::default()
-                };
-                let _ = state.send_message(chat_jid.clone(), reply).await;
+                let sent = state
+                    .send_reply(
+                        chat_jid.clone(),
+                        "Access granted! You can now use this bot.",
+                        quoted,
+                        Some(OTP_EPHEMERAL_SECS),
+                    )
+                    .await;
+                track_sent(state, sent);
 
                 // Emit OTP resolved event for the gateway to persist the allowlist change.
                 if let Some(ref sink) = state.event_sink {
@@ -771,34 +1762,26 @@ async fn handle_otp_flow(
                 }
             },
             OtpVerifyResult::WrongCode { attempts_left } => {
-                let reply = wa::Message {
-                    conversation: Some(format!(
-                        "Wrong code. {attempts_left} attempt{} remaining.",
-                        if attempts_left == 1 {
-                            ""
-                        } else {
-                            "s"
-                        }
-                    )),
-                    ..Default::default()
-                };
-                let _ = state.send_message(chat_jid.clone(), reply).await;
+                let reply = format!(
+                    "Wrong code. {attempts_left} attempt{} remaining.",
+                    if attempts_left == 1 { "" } else { "s" }
+                );
+                let _ = state.send_reply(chat_jid.clone(), &reply, quoted, None).await;
             },
             OtpVerifyResult::LockedOut => {
-                let reply = wa::Message {
-                    conversation: Some("Too many failed attempts. Please try again later.".into()),
-                    ..Default::default()
-                };
-                let _ = state.send_message(chat_jid.clone(), reply).await;
+                let _ = state
+                    .send_reply(chat_jid.clone(), "Too many failed attempts. Please try again later.", quoted, None)
+                    .await;
             },
             OtpVerifyResult::Expired => {
-                let reply = wa::Message {
-                    conversation: Some(
-                        "Your code has expired. Please send any message to get a new code.".into(),
-                    ),
-                    ..Default::default()
-                };
-                let _ = state.send_message(chat_jid.clone(), reply).await;
+                let _ = state
+                    .send_reply(
+                        chat_jid.clone(),
+                        "Your code has expired. Please send any message to get a new code.",
+                        quoted,
+                        None,
+                    )
+                    .await;
             },
             OtpVerifyResult::NoPending => {},
         }
@@ -824,11 +1807,11 @@ async fn handle_otp_flow(
     match init_result {
         OtpInitResult::Created(code) => {
             info!(account_id, peer_id, code, "OTP challenge issued");
-            let reply = wa::Message {
-                conversation: Some(OTP_CHALLENGE_MSG.to_string()),
-                ..Default::default()
-            };
-            let _ = state.send_message(chat_jid.clone(), reply).await;
+            state.publish_health();
+            let sent = state
+                .send_reply(chat_jid.clone(), OTP_CHALLENGE_MSG, quoted, Some(OTP_EPHEMERAL_SECS))
+                .await;
+            record_challenge_send(accounts, account_id, peer_id, state, sent);
 
             // Compute expires_at as epoch seconds (5 minutes from now).
             let expires_at = std::time::SystemTime::now()
@@ -851,19 +1834,40 @@ async fn handle_otp_flow(
             }
         },
         OtpInitResult::AlreadyPending => {
-            // Resend the challenge message.
-            let reply = wa::Message {
-                conversation: Some(OTP_CHALLENGE_MSG.to_string()),
-                ..Default::default()
+            // Only resend if the prior challenge never reached the peer's
+            // device — once delivered (or read), the peer already has a
+            // valid code and a resend would just be noise.
+            let prior_msg_id = {
+                let accts = accounts.read().unwrap_or_else(|e| e.into_inner());
+                accts.get(account_id).and_then(|s| {
+                    let otp = s.otp.lock().unwrap_or_else(|e| e.into_inner());
+                    otp.challenge_message_id(peer_id)
+                })
             };
-            let _ = state.send_message(chat_jid.clone(), reply).await;
+            let already_delivered = prior_msg_id.as_deref().is_some_and(|id| {
+                let delivery = state.delivery.lock().unwrap_or_else(|e| e.into_inner());
+                matches!(delivery.state(id), Some(DeliveryState::Delivered) | Some(DeliveryState::Read))
+            });
+
+            if already_delivered {
+                debug!(account_id, peer_id, "prior OTP challenge already delivered, not resending");
+                return;
+            }
+
+            let sent = state
+                .send_reply(chat_jid.clone(), OTP_CHALLENGE_MSG, quoted, Some(OTP_EPHEMERAL_SECS))
+                .await;
+            record_challenge_send(accounts, account_id, peer_id, state, sent);
         },
         OtpInitResult::LockedOut => {
-            let reply = wa::Message {
-                conversation: Some("Too many failed attempts. Please try again later.".into()),
-                ..Default::default()
-            };
-            let _ = state.send_message(chat_jid.clone(), reply).await;
+            let _ = state
+                .send_reply(chat_jid.clone(), "Too many failed attempts. Please try again later.", quoted, None)
+                .await;
+        },
+        OtpInitResult::Throttled => {
+            // Too many challenges already pending on this account. Stay
+            // silent rather than add to the flood of replies/events.
+            debug!(account_id, peer_id, "OTP challenge throttled, account at pending cap");
         },
     }
 }
@@ -0,0 +1,445 @@
+//! Generic key-value backend abstraction.
+//!
+//! `SledStore` used to hard-code `sled::Db`/`sled::Tree` in every method. That
+//! made it impossible to exercise the Signal Protocol store logic against
+//! anything lighter than a real sled database — slow in tests, and a dead
+//! end for anyone who wants a different on-disk format. [`KvBackend`] and
+//! [`KvTree`] pull the storage primitives out into a trait so
+//! [`crate::sled_store::Store`] can be written once and instantiated over
+//! whichever backend fits: sled on disk ([`SledBackend`]), or a plain
+//! `HashMap` for tests ([`InMemoryBackend`]).
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use wacore::store::error::{Result, StoreError, db_err};
+
+/// One open tree (sled's terminology for a keyspace) within a [`KvBackend`].
+pub trait KvTree: Clone + Send + Sync + 'static {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()>;
+    fn remove(&self, key: &[u8]) -> Result<()>;
+    /// All entries in the tree, in unspecified order.
+    fn iter(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+    /// Entries whose key starts with `prefix`.
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+}
+
+/// A single put or remove, queued up for [`KvBackend::apply_batch`].
+pub enum BatchOp {
+    Put(Vec<u8>, Vec<u8>),
+    Remove(Vec<u8>),
+}
+
+/// A key-value storage backend capable of opening named trees.
+///
+/// `open_tree` is idempotent for the lifetime of the backend: calling it
+/// twice with the same name returns handles to the same underlying tree.
+pub trait KvBackend: Send + Sync {
+    type Tree: KvTree;
+
+    fn open_tree(&self, name: &str) -> Result<Self::Tree>;
+
+    /// Apply a batch of puts/removes spanning one or more trees. The default
+    /// implementation just applies each op in order, with no atomicity
+    /// guarantee across trees — fine for tests, but a backend whose
+    /// on-disk format supports real multi-key transactions (sled does)
+    /// should override this so a failure partway through a batch rolls
+    /// back every op in it instead of leaving the store torn.
+    fn apply_batch(&self, ops: Vec<(Self::Tree, BatchOp)>) -> Result<()> {
+        for (tree, op) in ops {
+            match op {
+                BatchOp::Put(key, value) => tree.insert(&key, &value)?,
+                BatchOp::Remove(key) => tree.remove(&key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// sled backend — the default, persistent implementation.
+// ============================================================================
+
+/// [`KvTree`] backed by a real `sled::Tree`.
+#[derive(Clone)]
+pub struct SledTree(pub(crate) sled::Tree);
+
+impl KvTree for SledTree {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.get(key).map_err(db_err)?.map(|v| v.to_vec()))
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.0.insert(key, value).map_err(db_err)?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        self.0.remove(key).map_err(db_err)?;
+        Ok(())
+    }
+
+    fn iter(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.0
+            .iter()
+            .map(|entry| entry.map(|(k, v)| (k.to_vec(), v.to_vec())).map_err(db_err))
+            .collect()
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.0
+            .scan_prefix(prefix)
+            .map(|entry| entry.map(|(k, v)| (k.to_vec(), v.to_vec())).map_err(db_err))
+            .collect()
+    }
+}
+
+/// [`KvBackend`] backed by a real on-disk sled database.
+#[derive(Clone)]
+pub struct SledBackend {
+    db: sled::Db,
+}
+
+impl SledBackend {
+    pub fn open(path: impl AsRef<std::path::Path>) -> std::result::Result<Self, sled::Error> {
+        Ok(Self { db: sled::open(path)? })
+    }
+}
+
+/// Test-only hook for forcing [`SledBackend::apply_batch`] to abort partway
+/// through a real multi-tree transaction, so a test can drive an actual
+/// `Store` method (not a hand-rolled transaction) through a path that fails
+/// mid-batch and confirm nothing from it survives.
+#[cfg(test)]
+pub(crate) mod test_hooks {
+    use std::cell::Cell;
+
+    thread_local! {
+        static FAIL_AFTER_OPS: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
+    /// Arm the hook: the next `apply_batch` call aborts its transaction
+    /// right after applying `n` of its ops, instead of committing. Consumed
+    /// (cleared) by that call, so it only affects the next batch.
+    pub(crate) fn fail_after_ops(n: usize) {
+        FAIL_AFTER_OPS.with(|c| c.set(Some(n)));
+    }
+
+    pub(crate) fn take_fail_after_ops() -> Option<usize> {
+        FAIL_AFTER_OPS.with(|c| c.take())
+    }
+}
+
+impl KvBackend for SledBackend {
+    type Tree = SledTree;
+
+    fn open_tree(&self, name: &str) -> Result<Self::Tree> {
+        Ok(SledTree(self.db.open_tree(name).map_err(db_err)?))
+    }
+
+    fn apply_batch(&self, ops: Vec<(Self::Tree, BatchOp)>) -> Result<()> {
+        if ops.is_empty() {
+            return Ok(());
+        }
+
+        // Dedup the distinct trees touched (sled trees compare by identity)
+        // so the transaction only opens each one once, in first-touched order.
+        let mut trees: Vec<sled::Tree> = Vec::new();
+        let mut indexed_ops: Vec<(usize, BatchOp)> = Vec::with_capacity(ops.len());
+        for (tree, op) in ops {
+            let idx = trees.iter().position(|t| *t == tree.0).unwrap_or_else(|| {
+                trees.push(tree.0.clone());
+                trees.len() - 1
+            });
+            indexed_ops.push((idx, op));
+        }
+
+        #[cfg(test)]
+        let fail_after = test_hooks::take_fail_after_ops();
+
+        trees
+            .as_slice()
+            .transaction(|txn_trees| {
+                for (_i, (idx, op)) in indexed_ops.iter().enumerate() {
+                    #[cfg(test)]
+                    if fail_after == Some(_i) {
+                        return Err(sled::transaction::ConflictableTransactionError::Abort(()));
+                    }
+                    let txn_tree = &txn_trees[*idx];
+                    match op {
+                        BatchOp::Put(key, value) => {
+                            txn_tree.insert(key.as_slice(), value.as_slice())?;
+                        },
+                        BatchOp::Remove(key) => {
+                            txn_tree.remove(key.as_slice())?;
+                        },
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e: sled::transaction::TransactionError<()>| match e {
+                sled::transaction::TransactionError::Abort(()) => {
+                    StoreError::Serialization("store transaction aborted".into())
+                },
+                sled::transaction::TransactionError::Storage(storage_err) => db_err(storage_err),
+            })
+    }
+}
+
+// ============================================================================
+// In-memory backend — for tests that want to exercise the real store logic
+// without touching disk.
+// ============================================================================
+
+/// [`KvTree`] backed by a `BTreeMap` guarded by a mutex. [`InMemoryBackend`]
+/// uses the default [`KvBackend::apply_batch`], which applies a batch's ops
+/// one at a time, each under its own lock acquisition; a batch is not
+/// atomic, so an op that fails leaves the earlier ops of its batch applied.
+#[derive(Clone, Default)]
+pub struct InMemoryTree {
+    data: Arc<Mutex<BTreeMap<Vec<u8>, Vec<u8>>>>,
+}
+
+impl KvTree for InMemoryTree {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.data.lock().unwrap_or_else(|e| e.into_inner()).get(key).cloned())
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.data
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+        Ok(())
+    }
+
+    fn iter(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(k, _)| k.starts_with(prefix))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+}
+
+/// [`KvBackend`] backed by plain in-process `BTreeMap`s — no disk I/O. Useful
+/// for tests that want to drive the real `SignalStore`/`AppSyncStore`/
+/// `ProtocolStore`/`DeviceStore` code paths quickly and in parallel without
+/// contending over a shared sled file.
+#[derive(Clone, Default)]
+pub struct InMemoryBackend {
+    trees: Arc<Mutex<std::collections::HashMap<String, InMemoryTree>>>,
+}
+
+impl InMemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KvBackend for InMemoryBackend {
+    type Tree = InMemoryTree;
+
+    fn open_tree(&self, name: &str) -> Result<Self::Tree> {
+        Ok(self
+            .trees
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(name.to_string())
+            .or_default()
+            .clone())
+    }
+}
+
+// ============================================================================
+// Encrypting backend — wraps any KvBackend and transparently encrypts every
+// value at rest, for accounts that want encryption-at-rest for Signal
+// Protocol state (identities, sessions, prekeys, the serialized `Device`).
+// ============================================================================
+
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+const SENTINEL_KEY: &[u8] = b"sentinel";
+const SENTINEL_PLAINTEXT: &[u8] = b"moltis-whatsapp-store-sentinel";
+
+/// Encrypt `plaintext` with a fresh random 24-byte nonce, returning
+/// `nonce || ciphertext` (the nonce is public and need not be secret).
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
+    use chacha20poly1305::{AeadCore, KeyInit, XChaCha20Poly1305, aead::Aead};
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut rand::thread_rng());
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| StoreError::Serialization("failed to encrypt store value".into()))?;
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Inverse of [`seal`]. A failure here (truncated blob, bad tag) means
+/// either corruption or — when checking the sentinel — a wrong passphrase.
+fn open_sealed(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>> {
+    use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce, aead::Aead};
+
+    if sealed.len() < NONCE_LEN {
+        return Err(StoreError::Serialization("encrypted value too short".into()));
+    }
+    let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new(key.into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| StoreError::WrongPassphrase)
+}
+
+/// Derive a 32-byte key from `passphrase` and `salt` with Argon2id.
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| StoreError::Serialization(format!("key derivation failed: {e}")))?;
+    Ok(key)
+}
+
+/// [`KvTree`] that transparently encrypts values while leaving keys (and
+/// therefore prefix/range scans) in plaintext.
+#[derive(Clone)]
+pub struct EncryptedTree<T: KvTree> {
+    inner: T,
+    key: Arc<[u8; 32]>,
+}
+
+impl<T: KvTree> KvTree for EncryptedTree<T> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.inner.get(key)? {
+            Some(sealed) => Ok(Some(open_sealed(&self.key, &sealed)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.inner.insert(key, &seal(&self.key, value)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        self.inner.remove(key)
+    }
+
+    fn iter(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner
+            .iter()?
+            .into_iter()
+            .map(|(k, v)| Ok((k, open_sealed(&self.key, &v)?)))
+            .collect()
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner
+            .scan_prefix(prefix)?
+            .into_iter()
+            .map(|(k, v)| Ok((k, open_sealed(&self.key, &v)?)))
+            .collect()
+    }
+}
+
+/// [`KvBackend`] that wraps any other backend and transparently encrypts
+/// every value with a passphrase-derived Argon2id key, using
+/// XChaCha20-Poly1305 with a fresh random nonce per value. Tree keys
+/// (addresses, ids, version strings) stay plaintext.
+#[derive(Clone)]
+pub struct EncryptedBackend<B: KvBackend> {
+    inner: B,
+    key: Arc<[u8; 32]>,
+}
+
+impl<B: KvBackend> EncryptedBackend<B> {
+    /// Wrap `inner` with encryption derived from `passphrase`. On first use
+    /// against a fresh backend this generates a random salt and a sentinel
+    /// value (both stored in a `meta` tree); on reuse it verifies
+    /// `passphrase` by decrypting that sentinel, returning
+    /// [`StoreError::WrongPassphrase`] rather than a confusing
+    /// deserialization error if it doesn't match.
+    pub fn open(inner: B, passphrase: &str) -> Result<Self> {
+        let meta = inner.open_tree("meta")?;
+
+        let salt = match meta.get(b"salt")? {
+            Some(existing) => existing,
+            None => {
+                let mut salt = vec![0u8; SALT_LEN];
+                rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut salt);
+                meta.insert(b"salt", &salt)?;
+                salt
+            },
+        };
+
+        let key = derive_key(passphrase, &salt)?;
+
+        match meta.get(SENTINEL_KEY)? {
+            Some(sealed_sentinel) => {
+                // Wrong passphrase surfaces here as a decrypt failure.
+                let decrypted = open_sealed(&key, &sealed_sentinel)?;
+                if decrypted != SENTINEL_PLAINTEXT {
+                    return Err(StoreError::WrongPassphrase);
+                }
+            },
+            None => {
+                meta.insert(SENTINEL_KEY, &seal(&key, SENTINEL_PLAINTEXT)?)?;
+            },
+        }
+
+        Ok(Self {
+            inner,
+            key: Arc::new(key),
+        })
+    }
+}
+
+impl<B: KvBackend> KvBackend for EncryptedBackend<B> {
+    type Tree = EncryptedTree<B::Tree>;
+
+    fn open_tree(&self, name: &str) -> Result<Self::Tree> {
+        Ok(EncryptedTree {
+            inner: self.inner.open_tree(name)?,
+            key: self.key.clone(),
+        })
+    }
+
+    fn apply_batch(&self, ops: Vec<(Self::Tree, BatchOp)>) -> Result<()> {
+        // Encrypt each value up front, then hand the plain inner trees to
+        // the wrapped backend so it keeps whatever atomicity it already
+        // provides (e.g. sled's multi-tree transactions).
+        let inner_ops = ops
+            .into_iter()
+            .map(|(tree, op)| {
+                let inner_op = match op {
+                    BatchOp::Put(k, v) => BatchOp::Put(k, seal(&tree.key, &v)?),
+                    BatchOp::Remove(k) => BatchOp::Remove(k),
+                };
+                Ok((tree.inner, inner_op))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.inner.apply_batch(inner_ops)
+    }
+}
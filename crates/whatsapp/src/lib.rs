@@ -4,16 +4,30 @@
 //! send messages via WhatsApp Linked Devices (QR code pairing).
 
 pub mod access;
+pub mod account_registry;
+pub mod chunking;
+pub mod commands;
 pub mod config;
 pub mod connection;
+pub mod delivery;
+pub mod download_state;
 pub mod error;
+pub mod group_autojoin;
 pub mod handlers;
+pub mod kv_backend;
+pub mod media_cache;
 pub mod memory_store;
+pub mod migration;
 pub mod otp;
+pub mod otp_store;
 pub mod outbound;
+pub mod outbound_queue;
 pub mod plugin;
+pub mod rate_limit;
 pub mod sled_store;
 pub mod state;
+pub mod tls_trust;
+pub mod verified_join;
 
 pub use {
     config::WhatsAppAccountConfig,
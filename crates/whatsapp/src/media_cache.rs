@@ -0,0 +1,134 @@
+//! Content-addressed cache for processed inbound media.
+//!
+//! Keyed by the lowercase-hex SHA-256 of the decrypted media bytes so that
+//! identical forwarded images, stickers, or repeated voice notes skip
+//! re-downloading and re-processing (image resize, STT) entirely.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Maximum number of cached entries per account before the oldest is evicted.
+const DEFAULT_CAPACITY: usize = 128;
+
+/// A previously processed piece of media, ready to be re-dispatched.
+#[derive(Clone)]
+pub enum CachedMedia {
+    /// Optimized image bytes plus their media type, as produced by
+    /// `image_ops::optimize_for_llm`.
+    Image { data: Vec<u8>, media_type: String },
+    /// Optimized keyframes sampled from a video, in order.
+    Frames(Vec<(Vec<u8>, String)>),
+    /// Transcribed text for a voice/audio message.
+    Transcript(String),
+}
+
+/// Bounded LRU cache of processed media, keyed by SHA-256 hex digest.
+pub struct MediaCache {
+    capacity: usize,
+    entries: HashMap<String, CachedMedia>,
+    /// Tracks recency order; the front is least-recently-used.
+    order: VecDeque<String>,
+}
+
+impl MediaCache {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Look up a cached entry by digest, marking it most-recently-used.
+    pub fn get(&mut self, digest: &str) -> Option<CachedMedia> {
+        let entry = self.entries.get(digest).cloned()?;
+        if let Some(pos) = self.order.iter().position(|k| k == digest) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(digest.to_string());
+        Some(entry)
+    }
+
+    /// Insert (or refresh) a cached entry, evicting the least-recently-used
+    /// entry if the cache is at capacity.
+    pub fn put(&mut self, digest: String, media: CachedMedia) {
+        if let Some(pos) = self.order.iter().position(|k| *k == digest) {
+            self.order.remove(pos);
+        } else if self.entries.len() >= self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.entries.remove(&oldest);
+        }
+        self.order.push_back(digest.clone());
+        self.entries.insert(digest, media);
+    }
+}
+
+impl Default for MediaCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compute the lowercase-hex SHA-256 digest of a byte slice.
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    let digest = hasher.finalize();
+    let mut s = String::with_capacity(digest.len() * 2);
+    for b in digest {
+        use std::fmt::Write;
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_hex_is_deterministic() {
+        assert_eq!(sha256_hex(b"hello"), sha256_hex(b"hello"));
+        assert_ne!(sha256_hex(b"hello"), sha256_hex(b"world"));
+        assert_eq!(sha256_hex(b"hello").len(), 64);
+    }
+
+    #[test]
+    fn cache_hit_after_put() {
+        let mut cache = MediaCache::with_capacity(2);
+        cache.put(
+            "abc".into(),
+            CachedMedia::Transcript("transcribed text".into()),
+        );
+        match cache.get("abc") {
+            Some(CachedMedia::Transcript(t)) => assert_eq!(t, "transcribed text"),
+            _ => panic!("expected cache hit"),
+        }
+    }
+
+    #[test]
+    fn cache_miss_returns_none() {
+        let mut cache = MediaCache::new();
+        assert!(cache.get("missing").is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_when_full() {
+        let mut cache = MediaCache::with_capacity(2);
+        cache.put("a".into(), CachedMedia::Transcript("a".into()));
+        cache.put("b".into(), CachedMedia::Transcript("b".into()));
+        // Touch "a" so "b" becomes the least-recently-used entry.
+        assert!(cache.get("a").is_some());
+        cache.put("c".into(), CachedMedia::Transcript("c".into()));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+}
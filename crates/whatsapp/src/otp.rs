@@ -0,0 +1,430 @@
+//! Per-account OTP (one-time passcode) self-approval flow.
+//!
+//! When `otp_self_approval` is enabled, a non-allowlisted DM user who
+//! messages the bot is sent a short numeric code; replying with that code
+//! adds them to the allowlist without operator intervention. This module
+//! tracks the challenge state backing that flow, including cross-peer flood
+//! protection: a cap on how many challenges an account can have pending at
+//! once, and a per-peer cooldown after a lockout or expiry before a new
+//! challenge can be triggered.
+//!
+//! Only a SHA-256 hash of each code is ever held in memory or on disk — see
+//! [`hash_code`] — and every challenge carries an opaque `nonce` so replies
+//! can be correlated to the challenge that produced them even across a
+//! reissue. When an account is backed by an [`OtpChallengeStore`]
+//! (see [`OtpState::new_with_store`]), attempts and cooldowns survive a
+//! restart instead of resetting.
+
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::otp_store::{OtpChallengeStore, PersistedChallenge};
+
+/// Current Unix time in whole seconds.
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Hash a plaintext code for storage/comparison — codes themselves are
+/// never persisted or retained past the message that carries them.
+fn hash_code(code: &str) -> String {
+    crate::media_cache::sha256_hex(code.as_bytes())
+}
+
+/// Message sent to a peer when a new OTP challenge is issued.
+pub const OTP_CHALLENGE_MSG: &str =
+    "You're not yet approved to message this bot. Reply with the 6-digit code you'll receive shortly to gain access.";
+
+/// How long OTP challenge/grant replies stay visible before disappearing
+/// (WhatsApp's `contextInfo.expiration`), so codes don't linger in chat
+/// history once they've served their purpose.
+pub const OTP_EPHEMERAL_SECS: u32 = 24 * 60 * 60;
+
+/// How long an issued code remains valid before it must be reissued, in
+/// seconds.
+const CHALLENGE_TTL_SECS: u64 = 300;
+
+/// Maximum wrong-code attempts before a peer is locked out.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// A single peer's in-flight (or cooling down) OTP challenge.
+///
+/// Timestamps are stored as Unix seconds rather than `Instant`s so a
+/// challenge loaded from an [`OtpChallengeStore`] after a restart still
+/// expires/cools down on the same wall-clock schedule it would have had if
+/// the process had never stopped.
+struct Challenge {
+    code_hash: String,
+    nonce: String,
+    username: Option<String>,
+    sender_name: Option<String>,
+    issued_at: u64,
+    attempts: u32,
+    /// Set once the peer is locked out (too many wrong attempts) or their
+    /// code expires unused. While set, the peer can't trigger a fresh
+    /// challenge until the cooldown elapses.
+    cooldown_until: Option<u64>,
+    /// ID of the most recently sent challenge message, if it was sent
+    /// successfully, so `AlreadyPending` can check whether it was ever
+    /// delivered before resending.
+    message_id: Option<String>,
+}
+
+impl Challenge {
+    fn is_expired(&self) -> bool {
+        now_secs().saturating_sub(self.issued_at) >= CHALLENGE_TTL_SECS
+    }
+
+    /// Whether this challenge is actively awaiting a code from the peer
+    /// (i.e. neither expired nor cooling down).
+    fn is_pending(&self) -> bool {
+        self.cooldown_until.is_none() && !self.is_expired()
+    }
+
+    fn to_persisted(&self) -> PersistedChallenge {
+        PersistedChallenge {
+            code_hash: self.code_hash.clone(),
+            nonce: self.nonce.clone(),
+            username: self.username.clone(),
+            sender_name: self.sender_name.clone(),
+            issued_at: self.issued_at,
+            attempts: self.attempts,
+            cooldown_until: self.cooldown_until,
+            message_id: self.message_id.clone(),
+        }
+    }
+
+    fn from_persisted(p: PersistedChallenge) -> Self {
+        Self {
+            code_hash: p.code_hash,
+            nonce: p.nonce,
+            username: p.username,
+            sender_name: p.sender_name,
+            issued_at: p.issued_at,
+            attempts: p.attempts,
+            cooldown_until: p.cooldown_until,
+            message_id: p.message_id,
+        }
+    }
+}
+
+/// Outcome of [`OtpState::initiate`].
+pub enum OtpInitResult {
+    /// A fresh code was generated; it should be sent to the peer.
+    Created(String),
+    /// A challenge is already pending; the existing prompt should be resent.
+    AlreadyPending,
+    /// The peer is within its post-lockout/expiry cooldown window.
+    LockedOut,
+    /// The account already has the maximum number of pending challenges.
+    /// No reply should be sent and no event emitted, so a burst of DMs from
+    /// unknown peers can't be weaponized to get the account flagged for
+    /// spamming every new contact.
+    Throttled,
+}
+
+/// Outcome of [`OtpState::verify`].
+pub enum OtpVerifyResult {
+    /// The code matched; the peer should be added to the allowlist.
+    Approved,
+    /// The code didn't match; `attempts_left` attempts remain before lockout.
+    WrongCode { attempts_left: u32 },
+    /// Too many wrong attempts; the peer is now in cooldown.
+    LockedOut,
+    /// The code was never entered before `CHALLENGE_TTL_SECS`; the peer is now
+    /// in cooldown before a new one can be issued.
+    Expired,
+    /// No challenge is pending for this peer.
+    NoPending,
+}
+
+/// Snapshot of a pending challenge, for operator visibility.
+pub struct OtpChallengeInfo {
+    pub peer_id: String,
+    pub username: Option<String>,
+    pub sender_name: Option<String>,
+    /// Seconds remaining before the code expires.
+    pub expires_in_secs: u64,
+}
+
+/// OTP challenge tracker for one account.
+///
+/// One `OtpState` lives per `AccountState`, so all counting (`pending_count`)
+/// and the pending cap are naturally scoped per account. When constructed
+/// with [`Self::new_with_store`], every mutation is written through to an
+/// [`OtpChallengeStore`] so attempts and cooldowns survive a restart.
+pub struct OtpState {
+    challenges: HashMap<String, Challenge>,
+    cooldown_secs: u64,
+    max_pending: usize,
+    store: Option<std::sync::Arc<OtpChallengeStore>>,
+}
+
+impl OtpState {
+    pub fn new(cooldown_secs: u64, max_pending: usize) -> Self {
+        Self {
+            challenges: HashMap::new(),
+            cooldown_secs,
+            max_pending,
+            store: None,
+        }
+    }
+
+    /// Build a challenge tracker backed by a persistent store, loading any
+    /// challenges left over from a prior run.
+    pub fn new_with_store(cooldown_secs: u64, max_pending: usize, store: std::sync::Arc<OtpChallengeStore>) -> Self {
+        let challenges = store
+            .load_all()
+            .into_iter()
+            .map(|(peer_id, persisted)| (peer_id, Challenge::from_persisted(persisted)))
+            .collect();
+        Self {
+            challenges,
+            cooldown_secs,
+            max_pending,
+            store: Some(store),
+        }
+    }
+
+    /// Write a challenge through to the backing store, if any. Takes the
+    /// store by reference rather than `&self` so callers already holding a
+    /// `&mut` into `self.challenges` (via `get_mut`) can still call it.
+    fn persist(store: &Option<std::sync::Arc<OtpChallengeStore>>, peer_id: &str, challenge: &Challenge) {
+        if let Some(store) = store {
+            store.save(peer_id, &challenge.to_persisted());
+        }
+    }
+
+    fn forget(store: &Option<std::sync::Arc<OtpChallengeStore>>, peer_id: &str) {
+        if let Some(store) = store {
+            store.remove(peer_id);
+        }
+    }
+
+    /// Whether a challenge is actively awaiting a code from this peer.
+    pub fn has_pending(&self, peer_id: &str) -> bool {
+        self.challenges.get(peer_id).is_some_and(Challenge::is_pending)
+    }
+
+    /// Number of challenges currently pending across all peers on this
+    /// account (i.e. not expired, resolved, or in cooldown).
+    pub fn pending_count(&self) -> usize {
+        self.challenges.values().filter(|c| c.is_pending()).count()
+    }
+
+    /// Start a new challenge for a peer, or report why one can't start yet.
+    pub fn initiate(
+        &mut self,
+        peer_id: &str,
+        username: Option<String>,
+        sender_name: Option<String>,
+    ) -> OtpInitResult {
+        if let Some(existing) = self.challenges.get(peer_id) {
+            if let Some(until) = existing.cooldown_until {
+                if now_secs() < until {
+                    return OtpInitResult::LockedOut;
+                }
+            } else if !existing.is_expired() {
+                return OtpInitResult::AlreadyPending;
+            }
+        }
+
+        if self.pending_count() >= self.max_pending {
+            return OtpInitResult::Throttled;
+        }
+
+        let code = format!("{:06}", rand::rng().random_range(0..1_000_000));
+        let challenge = Challenge {
+            code_hash: hash_code(&code),
+            nonce: uuid::Uuid::new_v4().to_string(),
+            username,
+            sender_name,
+            issued_at: now_secs(),
+            attempts: 0,
+            cooldown_until: None,
+            message_id: None,
+        };
+        Self::persist(&self.store, peer_id, &challenge);
+        self.challenges.insert(peer_id.to_string(), challenge);
+        OtpInitResult::Created(code)
+    }
+
+    /// Record the ID a challenge message was actually sent under, so a later
+    /// `AlreadyPending` resend decision can check its delivery state.
+    pub fn set_challenge_message_id(&mut self, peer_id: &str, message_id: String) {
+        if let Some(challenge) = self.challenges.get_mut(peer_id) {
+            challenge.message_id = Some(message_id);
+            Self::persist(&self.store, peer_id, challenge);
+        }
+    }
+
+    /// ID of the most recently sent challenge message for a peer, if any.
+    pub fn challenge_message_id(&self, peer_id: &str) -> Option<String> {
+        self.challenges.get(peer_id).and_then(|c| c.message_id.clone())
+    }
+
+    /// The opaque nonce correlating a peer's current challenge, if any.
+    pub fn challenge_nonce(&self, peer_id: &str) -> Option<String> {
+        self.challenges.get(peer_id).map(|c| c.nonce.clone())
+    }
+
+    /// Verify a code entered by a peer against its pending challenge.
+    pub fn verify(&mut self, peer_id: &str, code: &str) -> OtpVerifyResult {
+        let Some(challenge) = self.challenges.get_mut(peer_id) else {
+            return OtpVerifyResult::NoPending;
+        };
+
+        if challenge.cooldown_until.is_some() {
+            return OtpVerifyResult::LockedOut;
+        }
+
+        if challenge.is_expired() {
+            challenge.cooldown_until = Some(now_secs() + self.cooldown_secs);
+            Self::persist(&self.store, peer_id, challenge);
+            return OtpVerifyResult::Expired;
+        }
+
+        if challenge.code_hash == hash_code(code) {
+            self.challenges.remove(peer_id);
+            Self::forget(&self.store, peer_id);
+            return OtpVerifyResult::Approved;
+        }
+
+        challenge.attempts += 1;
+        if challenge.attempts >= MAX_ATTEMPTS {
+            challenge.cooldown_until = Some(now_secs() + self.cooldown_secs);
+            Self::persist(&self.store, peer_id, challenge);
+            return OtpVerifyResult::LockedOut;
+        }
+
+        let attempts_left = MAX_ATTEMPTS - challenge.attempts;
+        Self::persist(&self.store, peer_id, challenge);
+        OtpVerifyResult::WrongCode { attempts_left }
+    }
+
+    /// List all currently pending (not expired/cooling-down) challenges.
+    pub fn list_pending(&self) -> Vec<OtpChallengeInfo> {
+        self.challenges
+            .iter()
+            .filter(|(_, c)| c.is_pending())
+            .map(|(peer_id, c)| OtpChallengeInfo {
+                peer_id: peer_id.clone(),
+                username: c.username.clone(),
+                sender_name: c.sender_name.clone(),
+                expires_in_secs: CHALLENGE_TTL_SECS.saturating_sub(now_secs().saturating_sub(c.issued_at)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initiate_creates_pending_challenge() {
+        let mut otp = OtpState::new(300, 10);
+        match otp.initiate("peer1", None, None) {
+            OtpInitResult::Created(code) => assert_eq!(code.len(), 6),
+            _ => panic!("expected Created"),
+        }
+        assert!(otp.has_pending("peer1"));
+        assert_eq!(otp.pending_count(), 1);
+    }
+
+    #[test]
+    fn initiate_twice_is_already_pending() {
+        let mut otp = OtpState::new(300, 10);
+        otp.initiate("peer1", None, None);
+        assert!(matches!(
+            otp.initiate("peer1", None, None),
+            OtpInitResult::AlreadyPending
+        ));
+        assert_eq!(otp.pending_count(), 1);
+    }
+
+    #[test]
+    fn verify_correct_code_approves_and_clears_pending() {
+        let mut otp = OtpState::new(300, 10);
+        let code = match otp.initiate("peer1", None, None) {
+            OtpInitResult::Created(code) => code,
+            _ => panic!("expected Created"),
+        };
+        assert!(matches!(otp.verify("peer1", &code), OtpVerifyResult::Approved));
+        assert!(!otp.has_pending("peer1"));
+        assert_eq!(otp.pending_count(), 0);
+    }
+
+    #[test]
+    fn verify_wrong_code_counts_down_then_locks_out() {
+        let mut otp = OtpState::new(300, 10);
+        otp.initiate("peer1", None, None);
+
+        match otp.verify("peer1", "000000") {
+            OtpVerifyResult::WrongCode { attempts_left } => assert_eq!(attempts_left, 2),
+            _ => panic!("expected WrongCode"),
+        }
+        match otp.verify("peer1", "000000") {
+            OtpVerifyResult::WrongCode { attempts_left } => assert_eq!(attempts_left, 1),
+            _ => panic!("expected WrongCode"),
+        }
+        assert!(matches!(otp.verify("peer1", "000000"), OtpVerifyResult::LockedOut));
+
+        // Locked out: no longer "pending", and initiate refuses until cooldown elapses.
+        assert!(!otp.has_pending("peer1"));
+        assert!(matches!(
+            otp.initiate("peer1", None, None),
+            OtpInitResult::LockedOut
+        ));
+    }
+
+    #[test]
+    fn verify_with_no_pending_challenge() {
+        let mut otp = OtpState::new(300, 10);
+        assert!(matches!(otp.verify("peer1", "123456"), OtpVerifyResult::NoPending));
+    }
+
+    #[test]
+    fn pending_cap_throttles_new_challenges() {
+        let mut otp = OtpState::new(300, 2);
+        assert!(matches!(
+            otp.initiate("peer1", None, None),
+            OtpInitResult::Created(_)
+        ));
+        assert!(matches!(
+            otp.initiate("peer2", None, None),
+            OtpInitResult::Created(_)
+        ));
+        assert!(matches!(
+            otp.initiate("peer3", None, None),
+            OtpInitResult::Throttled
+        ));
+        assert_eq!(otp.pending_count(), 2);
+    }
+
+    #[test]
+    fn challenge_message_id_is_recorded_and_retrievable() {
+        let mut otp = OtpState::new(300, 10);
+        otp.initiate("peer1", None, None);
+        assert_eq!(otp.challenge_message_id("peer1"), None);
+
+        otp.set_challenge_message_id("peer1", "msg-abc".into());
+        assert_eq!(otp.challenge_message_id("peer1"), Some("msg-abc".to_string()));
+    }
+
+    #[test]
+    fn list_pending_reflects_active_challenges_only() {
+        let mut otp = OtpState::new(300, 10);
+        otp.initiate("peer1", Some("alice".into()), Some("Alice".into()));
+        let pending = otp.list_pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].peer_id, "peer1");
+        assert_eq!(pending[0].username.as_deref(), Some("alice"));
+        assert_eq!(pending[0].sender_name.as_deref(), Some("Alice"));
+    }
+}
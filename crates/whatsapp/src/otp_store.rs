@@ -0,0 +1,119 @@
+//! Persistent backing store for [`crate::otp::OtpState`].
+//!
+//! OTP challenge state (hashed codes, attempt counts, cooldowns) used to
+//! live only in memory, so a restart mid-cooldown let a locked-out peer
+//! immediately retry, and a peer who had a code in flight had to start
+//! over. This mirrors [`crate::outbound_queue::OutboundQueue`]: a small
+//! sled database, one row per peer, written through on every mutation.
+
+use serde::{Deserialize, Serialize};
+
+/// A peer's OTP challenge state as persisted to disk.
+///
+/// Never carries the plaintext code — only `code_hash`, so a read of the
+/// store (or a backup of it) can't be used to self-approve.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PersistedChallenge {
+    pub code_hash: String,
+    /// Opaque id correlating this challenge across a peer's replies,
+    /// independent of the peer key itself.
+    pub nonce: String,
+    pub username: Option<String>,
+    pub sender_name: Option<String>,
+    /// Unix timestamp (seconds) the challenge was issued.
+    pub issued_at: u64,
+    pub attempts: u32,
+    /// Unix timestamp (seconds) after which a new challenge may be issued,
+    /// set once the peer is locked out or a code expires unused.
+    pub cooldown_until: Option<u64>,
+    pub message_id: Option<String>,
+}
+
+/// Durable per-peer OTP challenge store for one account.
+pub struct OtpChallengeStore {
+    db: sled::Db,
+}
+
+impl OtpChallengeStore {
+    /// Open (or create) the store's on-disk database.
+    pub fn open(path: impl AsRef<std::path::Path>) -> sled::Result<Self> {
+        Ok(Self { db: sled::open(path)? })
+    }
+
+    /// Write (or overwrite) a peer's challenge state.
+    pub fn save(&self, peer_id: &str, challenge: &PersistedChallenge) {
+        if let Ok(bytes) = serde_json::to_vec(challenge) {
+            let _ = self.db.insert(peer_id.as_bytes(), bytes);
+        }
+    }
+
+    /// Remove a peer's challenge state (resolved, or never existed).
+    pub fn remove(&self, peer_id: &str) {
+        let _ = self.db.remove(peer_id.as_bytes());
+    }
+
+    /// Load every persisted challenge, keyed by peer id.
+    pub fn load_all(&self) -> std::collections::HashMap<String, PersistedChallenge> {
+        self.db
+            .iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|(key, value)| {
+                let peer_id = String::from_utf8(key.to_vec()).ok()?;
+                let challenge: PersistedChallenge = serde_json::from_slice(&value).ok()?;
+                Some((peer_id, challenge))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn sample() -> PersistedChallenge {
+        PersistedChallenge {
+            code_hash: "abc123".to_string(),
+            nonce: "nonce-1".to_string(),
+            username: Some("alice".to_string()),
+            sender_name: None,
+            issued_at: 1000,
+            attempts: 1,
+            cooldown_until: None,
+            message_id: None,
+        }
+    }
+
+    #[test]
+    fn save_and_load_roundtrips() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = OtpChallengeStore::open(dir.path()).unwrap();
+        store.save("peer1", &sample());
+
+        let loaded = store.load_all();
+        let challenge = loaded.get("peer1").unwrap();
+        assert_eq!(challenge.code_hash, "abc123");
+        assert_eq!(challenge.nonce, "nonce-1");
+        assert_eq!(challenge.attempts, 1);
+    }
+
+    #[test]
+    fn remove_clears_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = OtpChallengeStore::open(dir.path()).unwrap();
+        store.save("peer1", &sample());
+        store.remove("peer1");
+        assert!(store.load_all().is_empty());
+    }
+
+    #[test]
+    fn survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = OtpChallengeStore::open(dir.path()).unwrap();
+            store.save("peer1", &sample());
+        }
+        let store = OtpChallengeStore::open(dir.path()).unwrap();
+        assert_eq!(store.load_all().len(), 1);
+    }
+}
@@ -1,6 +1,15 @@
+use std::{
+    io::Read as _,
+    sync::{Arc, Mutex},
+};
+
 use {async_trait::async_trait, tracing::debug};
 
-use {wacore_binary::jid::Jid, waproto::whatsapp as wa, whatsapp_rust::ChatStateType};
+use {
+    wacore_binary::jid::Jid,
+    waproto::whatsapp as wa,
+    whatsapp_rust::{ChatStateType, PresenceType, upload::MediaType},
+};
 
 use {
     moltis_channels::{
@@ -10,7 +19,27 @@ use {
     moltis_common::types::ReplyPayload,
 };
 
-use crate::state::{AccountStateMap, BOT_WATERMARK};
+use crate::{
+    rate_limit::TokenBucket,
+    state::{AccountStateMap, BOT_WATERMARK},
+};
+
+/// Fetch the bytes of a hosted media URL so they can be re-uploaded to
+/// WhatsApp. `ReplyPayload::media` only carries a URL (the same shape the
+/// Telegram outbound hands straight to `InputFile::url`), but WhatsApp has
+/// no "send by URL" primitive — the bytes have to be uploaded to WhatsApp's
+/// own media servers first.
+fn fetch_media_bytes(url: &str) -> ChannelResult<Vec<u8>> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| moltis_channels::Error::unavailable(format!("fetching media url: {e}")))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| moltis_channels::Error::unavailable(format!("reading media bytes: {e}")))?;
+    Ok(bytes)
+}
 
 /// Outbound message sender for WhatsApp.
 pub struct WhatsAppOutbound {
@@ -36,6 +65,27 @@ impl WhatsAppOutbound {
             state.record_sent_id(msg_id);
         }
     }
+
+    /// Clone out the account's ordinary-message rate limiter handle. `Arc`
+    /// so the caller can await the refill delay without holding the account
+    /// map's lock across that wait.
+    fn get_rate_limiter(&self, account_id: &str) -> ChannelResult<Arc<Mutex<TokenBucket>>> {
+        let accounts = self.accounts.read().unwrap_or_else(|e| e.into_inner());
+        accounts
+            .get(account_id)
+            .map(|s| Arc::clone(&s.rate_limiter))
+            .ok_or_else(|| moltis_channels::Error::unknown_account(account_id))
+    }
+
+    /// Acquire a send token before dispatching, waiting out the refill delay
+    /// if the bucket is dry. WhatsApp bans numbers that send too fast, and
+    /// this is the path every gateway-relayed outbound message goes through.
+    async fn acquire_send_token(&self, account_id: &str) -> ChannelResult<()> {
+        let limiter = self.get_rate_limiter(account_id)?;
+        crate::rate_limit::acquire(&limiter, true)
+            .await
+            .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp rate limit: {e}")))
+    }
 }
 
 #[async_trait]
@@ -51,6 +101,7 @@ impl ChannelOutbound for WhatsAppOutbound {
         let jid: Jid = to
             .parse()
             .map_err(|e| moltis_channels::Error::invalid_input(format!("invalid JID: {e:?}")))?;
+        self.acquire_send_token(account_id).await?;
 
         debug!(
             account_id,
@@ -80,25 +131,203 @@ impl ChannelOutbound for WhatsAppOutbound {
         payload: &ReplyPayload,
         _reply_to: Option<&str>,
     ) -> ChannelResult<()> {
-        // For now, send text only. Media upload support to be added.
-        if !payload.text.is_empty() {
-            self.send_text(account_id, to, &payload.text, None).await?;
+        let Some(ref media) = payload.media else {
+            if !payload.text.is_empty() {
+                self.send_text(account_id, to, &payload.text, None).await?;
+            }
+            return Ok(());
+        };
+
+        let client = self.get_client(account_id)?;
+        let jid: Jid = to
+            .parse()
+            .map_err(|e| moltis_channels::Error::invalid_input(format!("invalid JID: {e:?}")))?;
+        self.acquire_send_token(account_id).await?;
+
+        let mut caption = payload.text.clone();
+        if !caption.is_empty() {
+            caption.push_str(BOT_WATERMARK);
         }
+
+        let mime = media.mime_type.as_str();
+        let media_type = if mime.starts_with("image/") {
+            MediaType::Image
+        } else if mime.starts_with("video/") {
+            MediaType::Video
+        } else if mime.starts_with("audio/") {
+            MediaType::Audio
+        } else {
+            MediaType::Document
+        };
+
+        debug!(account_id, to, mime, "uploading WhatsApp media");
+
+        let data = fetch_media_bytes(&media.url)?;
+        let upload = client
+            .upload(&data, media_type)
+            .await
+            .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp media upload: {e}")))?;
+
+        let msg = match media_type {
+            MediaType::Image => wa::Message {
+                image_message: Some(Box::new(wa::ImageMessage {
+                    url: Some(upload.url),
+                    direct_path: Some(upload.direct_path),
+                    media_key: Some(upload.media_key),
+                    mimetype: Some(mime.to_string()),
+                    file_enc_sha256: Some(upload.file_enc_sha256),
+                    file_sha256: Some(upload.file_sha256),
+                    file_length: Some(upload.file_length),
+                    caption: (!caption.is_empty()).then_some(caption),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            MediaType::Video => wa::Message {
+                video_message: Some(Box::new(wa::VideoMessage {
+                    url: Some(upload.url),
+                    direct_path: Some(upload.direct_path),
+                    media_key: Some(upload.media_key),
+                    mimetype: Some(mime.to_string()),
+                    file_enc_sha256: Some(upload.file_enc_sha256),
+                    file_sha256: Some(upload.file_sha256),
+                    file_length: Some(upload.file_length),
+                    caption: (!caption.is_empty()).then_some(caption),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            MediaType::Audio => wa::Message {
+                audio_message: Some(Box::new(wa::AudioMessage {
+                    url: Some(upload.url),
+                    direct_path: Some(upload.direct_path),
+                    media_key: Some(upload.media_key),
+                    mimetype: Some(mime.to_string()),
+                    file_enc_sha256: Some(upload.file_enc_sha256),
+                    file_sha256: Some(upload.file_sha256),
+                    file_length: Some(upload.file_length),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            MediaType::Document => wa::Message {
+                document_message: Some(Box::new(wa::DocumentMessage {
+                    url: Some(upload.url),
+                    direct_path: Some(upload.direct_path),
+                    media_key: Some(upload.media_key),
+                    mimetype: Some(mime.to_string()),
+                    file_enc_sha256: Some(upload.file_enc_sha256),
+                    file_sha256: Some(upload.file_sha256),
+                    file_length: Some(upload.file_length),
+                    file_name: media.filename.clone(),
+                    caption: (!caption.is_empty()).then_some(caption),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+        };
+
+        let msg_id = client
+            .send_message(jid, msg)
+            .await
+            .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp send_media: {e}")))?;
+        self.record_sent_id(account_id, &msg_id);
         Ok(())
     }
 
     async fn send_typing(&self, account_id: &str, to: &str) -> ChannelResult<()> {
+        self.send_chat_state(account_id, to, ChatStateType::Composing)
+            .await
+    }
+
+    fn typing_interval(&self) -> Option<std::time::Duration> {
+        // WhatsApp's "composing" chat state also expires and needs
+        // refreshing while a reply is still being generated.
+        Some(std::time::Duration::from_secs(4))
+    }
+
+    fn supports_markdown(&self) -> bool {
+        // WhatsApp has its own lightweight markup (bold/italic via `*`/`_`)
+        // that doesn't match standard markdown, so replies are sent as
+        // plain text upstream rather than rendered markdown.
+        false
+    }
+}
+
+impl WhatsAppOutbound {
+    /// Emit a chat-state update — `Composing`, `Paused`, or `Recording` —
+    /// following mautrix-whatsapp's typing/recording handling. Unlike
+    /// `send_typing`, which always sends a one-shot `Composing`, this lets
+    /// callers broadcast typing for a long-running reply and then `Paused`
+    /// once it's ready to send, or `Recording` while synthesizing a voice
+    /// reply.
+    pub async fn send_chat_state(
+        &self,
+        account_id: &str,
+        to: &str,
+        state: ChatStateType,
+    ) -> ChannelResult<()> {
         let client = self.get_client(account_id)?;
         let jid: Jid = to
             .parse()
             .map_err(|e| moltis_channels::Error::invalid_input(format!("invalid JID: {e:?}")))?;
         client
             .chatstate()
-            .send(&jid, ChatStateType::Composing)
+            .send(&jid, state)
             .await
             .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp chatstate: {e}")))?;
         Ok(())
     }
+
+    /// Send read receipts for a batch of received message IDs, so the
+    /// sender sees WhatsApp's "seen" ticks.
+    pub async fn mark_read(
+        &self,
+        account_id: &str,
+        to: &str,
+        message_ids: &[String],
+    ) -> ChannelResult<()> {
+        let client = self.get_client(account_id)?;
+        let jid: Jid = to
+            .parse()
+            .map_err(|e| moltis_channels::Error::invalid_input(format!("invalid JID: {e:?}")))?;
+        client
+            .mark_read(&jid, message_ids)
+            .await
+            .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp mark_read: {e}")))?;
+        Ok(())
+    }
+
+    /// Broadcast this account's own presence (available/unavailable) to the
+    /// WhatsApp network.
+    pub async fn set_presence(&self, account_id: &str, available: bool) -> ChannelResult<()> {
+        let client = self.get_client(account_id)?;
+        let presence = if available {
+            PresenceType::Available
+        } else {
+            PresenceType::Unavailable
+        };
+        client
+            .send_presence(presence)
+            .await
+            .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp presence: {e}")))?;
+        Ok(())
+    }
+
+    /// Subscribe to a contact's presence updates, so WhatsApp starts
+    /// pushing `Event::Presence` notifications for them — surfaced via
+    /// `ChannelEvent::PresenceUpdate` once they arrive.
+    pub async fn subscribe_presence(&self, account_id: &str, contact: &str) -> ChannelResult<()> {
+        let client = self.get_client(account_id)?;
+        let jid: Jid = contact
+            .parse()
+            .map_err(|e| moltis_channels::Error::invalid_input(format!("invalid JID: {e:?}")))?;
+        client
+            .subscribe_presence(&jid)
+            .await
+            .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp subscribe_presence: {e}")))?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,255 @@
+//! Persistent outbound message queue with disconnect buffering.
+//!
+//! `AccountState::send_message` used to fire-and-forget: if the WhatsApp
+//! socket was down, the message was simply dropped. That meant a user could
+//! verify a correct OTP code and never receive the "Access granted!" reply,
+//! even though the allowlist event had already fired.
+//!
+//! This queue durably persists outbound messages (keyed by a monotonic sled
+//! id, so iteration order is FIFO) whenever the socket is down, and a
+//! background flush task drains it once `Event::Connected` fires again.
+//! `enqueue_and_wait` only resolves once the client actually acks the send,
+//! via a oneshot channel registered against the queue id. A bounded ring of
+//! recently-delivered content hashes guards against double-sending a
+//! message that was flushed successfully just before a crash prevented its
+//! removal from the durable queue.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use {
+    serde::{Deserialize, Serialize},
+    tracing::{debug, warn},
+    wacore_binary::jid::Jid,
+    waproto::whatsapp as wa,
+};
+
+use crate::{media_cache::sha256_hex, state::AccountState};
+
+/// Maximum number of delivered content hashes to remember for dedup.
+const DEDUP_CAPACITY: usize = 256;
+
+/// A message waiting to be sent, durable across restarts.
+///
+/// Only plain text bodies are supported today — every current caller of
+/// `send_message`/`send_reply` builds a `conversation` or
+/// `extended_text_message` text message (OTP replies, access denials,
+/// command responses), and only its text is queued; a quoted-reply
+/// `contextInfo` is not kept. This covers the whole queueable surface
+/// without inventing a persistence format for attachments.
+#[derive(Serialize, Deserialize)]
+struct QueuedMessage {
+    to: String,
+    text: String,
+}
+
+/// A durable FIFO queue of outbound WhatsApp text messages for one account.
+pub struct OutboundQueue {
+    db: sled::Db,
+    delivered: Mutex<VecDeque<String>>,
+    waiters: Mutex<HashMap<u64, tokio::sync::oneshot::Sender<crate::Result<String>>>>,
+}
+
+impl OutboundQueue {
+    /// Open (or create) the queue's on-disk store.
+    pub fn open(path: impl AsRef<std::path::Path>) -> sled::Result<Self> {
+        Ok(Self {
+            db: sled::open(path)?,
+            delivered: Mutex::new(VecDeque::new()),
+            waiters: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn dedup_key(to: &str, text: &str) -> String {
+        sha256_hex(format!("{to}\u{0}{text}").as_bytes())
+    }
+
+    fn was_delivered(&self, key: &str) -> bool {
+        let delivered = self.delivered.lock().unwrap_or_else(|e| e.into_inner());
+        delivered.iter().any(|k| k == key)
+    }
+
+    fn mark_delivered(&self, key: String) {
+        let mut delivered = self.delivered.lock().unwrap_or_else(|e| e.into_inner());
+        if delivered.len() >= DEDUP_CAPACITY {
+            delivered.pop_front();
+        }
+        delivered.push_back(key);
+    }
+
+    fn enqueue(&self, to: &str, text: &str) -> sled::Result<u64> {
+        let id = self.db.generate_id()?;
+        let entry = QueuedMessage {
+            to: to.to_string(),
+            text: text.to_string(),
+        };
+        let bytes = serde_json::to_vec(&entry).unwrap_or_default();
+        self.db.insert(id.to_be_bytes(), bytes)?;
+        Ok(id)
+    }
+
+    fn remove(&self, id: u64) {
+        let _ = self.db.remove(id.to_be_bytes());
+    }
+
+    /// All queued messages in FIFO order (ascending id — sled keeps keys
+    /// sorted lexicographically, and ids are encoded big-endian).
+    fn pending(&self) -> Vec<(u64, QueuedMessage)> {
+        self.db
+            .iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|(key, value)| {
+                let id = u64::from_be_bytes(key.as_ref().try_into().ok()?);
+                let msg: QueuedMessage = serde_json::from_slice(&value).ok()?;
+                Some((id, msg))
+            })
+            .collect()
+    }
+
+    /// Number of messages currently queued.
+    pub fn len(&self) -> usize {
+        self.db.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
+    /// Durably enqueue a text message and wait for the background flush
+    /// task (triggered on reconnect) to actually deliver it. Resolves to the
+    /// ID the message was eventually sent under.
+    pub async fn enqueue_and_wait(&self, to: &str, text: &str) -> crate::Result<String> {
+        let dedup_key = Self::dedup_key(to, text);
+        if self.was_delivered(&dedup_key) {
+            debug!(to, "skipping enqueue of already-delivered duplicate");
+            // The original send's message ID isn't persisted, so this
+            // degenerate (crash-recovery) duplicate case can't recover it.
+            return Ok(String::new());
+        }
+
+        let id = self
+            .enqueue(to, text)
+            .map_err(|e| crate::Error::Store { message: format!("failed to enqueue outbound message: {e}") })?;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.waiters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, tx);
+
+        rx.await.unwrap_or_else(|_| {
+            Err(crate::Error::Whatsapp {
+                message: "outbound queue dropped without delivering message".into(),
+            })
+        })
+    }
+
+    /// Notify whatever `enqueue_and_wait` call is waiting on `id`, if any.
+    fn notify_waiter(&self, id: u64, result: crate::Result<String>) {
+        if let Some(tx) = self.waiters.lock().unwrap_or_else(|e| e.into_inner()).remove(&id) {
+            let _ = tx.send(result);
+        }
+    }
+
+    /// Drain the queue in FIFO order, sending each message over `client`.
+    /// Stops at the first failure (leaving the rest queued) so order is
+    /// preserved and the remainder is retried on the next reconnect.
+    pub async fn flush(&self, state: &AccountState) {
+        if self.is_empty() {
+            return;
+        }
+        debug!(account_id = %state.account_id, count = self.len(), "flushing outbound queue");
+
+        for (id, queued) in self.pending() {
+            let dedup_key = Self::dedup_key(&queued.to, &queued.text);
+            if self.was_delivered(&dedup_key) {
+                self.remove(id);
+                // Same degenerate case as in `enqueue_and_wait`: the id of
+                // the send that actually delivered this content is gone.
+                self.notify_waiter(id, Ok(String::new()));
+                continue;
+            }
+
+            let Ok(jid) = queued.to.parse::<Jid>() else {
+                warn!(to = %queued.to, "dropping queued message with unparseable JID");
+                self.remove(id);
+                self.notify_waiter(
+                    id,
+                    Err(crate::Error::Whatsapp { message: format!("invalid JID: {}", queued.to) }),
+                );
+                continue;
+            };
+            let msg = wa::Message {
+                conversation: Some(queued.text.clone()),
+                ..Default::default()
+            };
+
+            match state.client.send_message(jid, msg).await {
+                Ok(msg_id) => {
+                    state.record_sent_id(&msg_id);
+                    self.remove(id);
+                    self.mark_delivered(dedup_key);
+                    self.notify_waiter(id, Ok(msg_id));
+                },
+                Err(e) => {
+                    debug!(account_id = %state.account_id, error = %e, "outbound queue flush stopped, will retry on next reconnect");
+                    return;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedup_key_is_stable_and_distinguishes_inputs() {
+        let a = OutboundQueue::dedup_key("peer1", "hello");
+        let b = OutboundQueue::dedup_key("peer1", "hello");
+        let c = OutboundQueue::dedup_key("peer2", "hello");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn enqueue_persists_and_pending_returns_fifo_order() {
+        let dir = tempfile_dir();
+        let queue = OutboundQueue::open(&dir).unwrap();
+        queue.enqueue("peer1", "first").unwrap();
+        queue.enqueue("peer1", "second").unwrap();
+
+        let pending = queue.pending();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].1.text, "first");
+        assert_eq!(pending[1].1.text, "second");
+    }
+
+    #[test]
+    fn delivered_dedup_ring_tracks_recent_keys() {
+        let dir = tempfile_dir();
+        let queue = OutboundQueue::open(&dir).unwrap();
+        let key = OutboundQueue::dedup_key("peer1", "hello");
+        assert!(!queue.was_delivered(&key));
+        queue.mark_delivered(key.clone());
+        assert!(queue.was_delivered(&key));
+    }
+
+    /// Unique temp dir per test so sled doesn't collide across test threads.
+    fn tempfile_dir() -> std::path::PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!(
+            "moltis-outbound-queue-test-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        dir
+    }
+}
@@ -15,11 +15,18 @@ use moltis_channels::{
     message_log::MessageLog,
     plugin::{
         ChannelHealthSnapshot, ChannelOutbound, ChannelPlugin, ChannelStatus, ChannelStreamOutbound,
+        ConfigReload,
     },
 };
 
+/// Capacity of the push-based health broadcast channel. Generous enough that
+/// a slow subscriber doesn't immediately start missing snapshots under a
+/// burst of reconnects across several accounts.
+const HEALTH_BROADCAST_CAPACITY: usize = 64;
+
 use crate::{
     config::WhatsAppAccountConfig, connection, outbound::WhatsAppOutbound, state::AccountStateMap,
+    tls_trust::TlsTrustStore,
 };
 
 /// Cache TTL for probe results (30 seconds).
@@ -33,6 +40,11 @@ pub struct WhatsAppPlugin {
     event_sink: Option<Arc<dyn ChannelEventSink>>,
     data_dir: PathBuf,
     probe_cache: RwLock<HashMap<String, (ChannelHealthSnapshot, Instant)>>,
+    tls_trust: Option<Arc<TlsTrustStore>>,
+    /// Push side of `subscribe_health`. Every `AccountState` holds a clone,
+    /// so an account's event handler can publish a snapshot the instant its
+    /// connection state changes, instead of waiting for a `probe` poll.
+    health_tx: tokio::sync::broadcast::Sender<ChannelHealthSnapshot>,
 }
 
 impl WhatsAppPlugin {
@@ -41,6 +53,7 @@ impl WhatsAppPlugin {
         let outbound = WhatsAppOutbound {
             accounts: Arc::clone(&accounts),
         };
+        let (health_tx, _) = tokio::sync::broadcast::channel(HEALTH_BROADCAST_CAPACITY);
         Self {
             accounts,
             outbound,
@@ -48,6 +61,8 @@ impl WhatsAppPlugin {
             event_sink: None,
             data_dir,
             probe_cache: RwLock::new(HashMap::new()),
+            tls_trust: None,
+            health_tx,
         }
     }
 
@@ -61,6 +76,14 @@ impl WhatsAppPlugin {
         self
     }
 
+    /// Use a shared root-cert store (built from `[tls.trust]` config) for
+    /// every account's HTTP client and WebSocket transport, instead of the
+    /// OS-provided roots.
+    pub fn with_tls_trust(mut self, trust: Arc<TlsTrustStore>) -> Self {
+        self.tls_trust = Some(trust);
+        self
+    }
+
     /// Get a shared reference to the outbound sender.
     pub fn shared_outbound(&self) -> Arc<dyn ChannelOutbound> {
         Arc::new(WhatsAppOutbound {
@@ -131,6 +154,102 @@ impl WhatsAppPlugin {
             })
             .unwrap_or_default()
     }
+
+    /// Parse `text` as an admin control command (see [`crate::commands`])
+    /// and, if it is one, apply it against `account_id`'s config.
+    ///
+    /// Returns `Ok(None)` when `text` isn't a recognized admin command, so
+    /// callers fall through to normal message handling. On success, the
+    /// mutated allow/deny lists are persisted to the on-disk config so bans
+    /// survive a restart.
+    pub fn apply_admin_command(
+        &self,
+        account_id: &str,
+        sender_peer_id: &str,
+        sender_username: Option<&str>,
+        text: &str,
+    ) -> ChannelResult<Option<String>> {
+        let Some(cmd) = crate::commands::parse(text) else {
+            return Ok(None);
+        };
+
+        let reply = {
+            let mut accounts = self.accounts.write().unwrap_or_else(|e| e.into_inner());
+            let state = accounts
+                .get_mut(account_id)
+                .ok_or_else(|| moltis_channels::Error::unknown_account(account_id))?;
+            crate::commands::apply(&mut state.config, sender_peer_id, sender_username, &cmd)
+                .map_err(|e| moltis_channels::Error::invalid_input(e.to_string()))?
+        };
+
+        self.persist_account_config(account_id);
+        Ok(Some(reply))
+    }
+
+    /// Write this account's current in-memory config back into the on-disk
+    /// `MoltisConfig`, so admin-command mutations (and other in-memory-only
+    /// updates) survive a restart.
+    fn persist_account_config(&self, account_id: &str) {
+        let Some(config) = self.account_config_typed(account_id) else {
+            return;
+        };
+        let mut full = moltis_config::discover_and_load();
+        full.channels.whatsapp.accounts.insert(account_id.to_string(), config);
+        if let Err(e) = moltis_config::save_config(&full) {
+            warn!(account_id, "failed to persist config change: {e}");
+        }
+    }
+
+    /// Get a specific account's config, typed (not serialized to JSON).
+    fn account_config_typed(&self, account_id: &str) -> Option<WhatsAppAccountConfig> {
+        let accounts = self.accounts.read().unwrap_or_else(|e| e.into_inner());
+        accounts.get(account_id).map(|s| s.config.clone())
+    }
+
+    /// Resolve the on-disk sled store path for an account, the same way
+    /// [`connection::start_connection`] does.
+    fn store_path_for(&self, account_id: &str, config: &WhatsAppAccountConfig) -> PathBuf {
+        config
+            .store_path
+            .clone()
+            .unwrap_or_else(|| self.data_dir.join("whatsapp").join(account_id))
+    }
+
+    /// Export a paired account's Signal Protocol store and config into a
+    /// single passphrase-encrypted blob, for migrating it to another host
+    /// without re-scanning the QR code. The account does not need to be
+    /// running, but must not be connected concurrently (sled only allows
+    /// one open handle per store).
+    pub fn export_account_session(
+        &self,
+        account_id: &str,
+        passphrase: &str,
+    ) -> ChannelResult<Vec<u8>> {
+        let config = self
+            .account_config_typed(account_id)
+            .ok_or_else(|| moltis_channels::Error::unknown_account(account_id))?;
+        let store_path = self.store_path_for(account_id, &config);
+        crate::migration::export_session(&store_path, &config, passphrase)
+            .map_err(|e| moltis_channels::Error::invalid_input(e.to_string()))
+    }
+
+    /// Import a blob produced by `export_account_session` into a (possibly
+    /// new) account, restoring its Signal Protocol store and config so it
+    /// comes up already paired. The account must not be running.
+    pub fn import_account_session(
+        &self,
+        account_id: &str,
+        blob: &[u8],
+        passphrase: &str,
+    ) -> ChannelResult<WhatsAppAccountConfig> {
+        let existing = self.account_config_typed(account_id);
+        let store_path = match &existing {
+            Some(config) => self.store_path_for(account_id, config),
+            None => self.data_dir.join("whatsapp").join(account_id),
+        };
+        crate::migration::import_session(&store_path, blob, passphrase)
+            .map_err(|e| moltis_channels::Error::invalid_input(e.to_string()))
+    }
 }
 
 #[async_trait]
@@ -159,6 +278,8 @@ impl ChannelPlugin for WhatsAppPlugin {
             self.data_dir.clone(),
             self.message_log.clone(),
             self.event_sink.clone(),
+            self.tls_trust.clone(),
+            self.health_tx.clone(),
         )
         .await
         .map_err(|e| moltis_channels::Error::unavailable(format!("whatsapp start: {e}")))?;
@@ -193,6 +314,26 @@ impl ChannelPlugin for WhatsAppPlugin {
     }
 }
 
+#[async_trait]
+impl ConfigReload for WhatsAppPlugin {
+    /// Apply a `config_save` account-config change without a restart, by
+    /// delegating to [`WhatsAppPlugin::update_account_config`]. Returns
+    /// `Ok(false)` (not an error) for an account this plugin doesn't know
+    /// about yet — `config_save` treats that as restart-required, since a
+    /// brand-new account needs `start_account`, not a reload.
+    async fn reload_account_config(
+        &self,
+        account_id: &str,
+        config: serde_json::Value,
+    ) -> ChannelResult<bool> {
+        if !self.has_account(account_id) {
+            return Ok(false);
+        }
+        self.update_account_config(account_id, config)?;
+        Ok(true)
+    }
+}
+
 #[async_trait]
 impl ChannelStatus for WhatsAppPlugin {
     async fn probe(&self, account_id: &str) -> ChannelResult<ChannelHealthSnapshot> {
@@ -207,32 +348,7 @@ impl ChannelStatus for WhatsAppPlugin {
         let result = {
             let accounts = self.accounts.read().unwrap_or_else(|e| e.into_inner());
             match accounts.get(account_id) {
-                Some(state) => {
-                    let connected = state.connected.load(std::sync::atomic::Ordering::Relaxed);
-                    let details = if connected {
-                        state
-                            .config
-                            .display_name
-                            .as_ref()
-                            .map(|n| format!("WhatsApp: {n}"))
-                            .or_else(|| Some("WhatsApp: connected".into()))
-                    } else if state
-                        .latest_qr
-                        .read()
-                        .ok()
-                        .and_then(|q| q.clone())
-                        .is_some()
-                    {
-                        Some("waiting for QR scan".into())
-                    } else {
-                        Some("disconnected".into())
-                    };
-                    ChannelHealthSnapshot {
-                        connected,
-                        account_id: account_id.to_string(),
-                        details,
-                    }
-                },
+                Some(state) => state.health_snapshot(),
                 None => ChannelHealthSnapshot {
                     connected: false,
                     account_id: account_id.to_string(),
@@ -247,6 +363,14 @@ impl ChannelStatus for WhatsAppPlugin {
 
         Ok(result)
     }
+
+    /// Subscribe to push-based health snapshot deltas, fired whenever any
+    /// account's `connected` state flips, a new QR code appears, or an OTP
+    /// challenge is issued — see `AccountState::publish_health`. Lets the
+    /// gateway serve an SSE/WebSocket stream instead of polling `probe`.
+    fn subscribe_health(&self) -> Option<tokio::sync::broadcast::Receiver<ChannelHealthSnapshot>> {
+        Some(self.health_tx.subscribe())
+    }
 }
 
 #[cfg(test)]
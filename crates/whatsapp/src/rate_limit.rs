@@ -0,0 +1,137 @@
+//! Per-account token-bucket rate limiting for outbound WhatsApp sends.
+//!
+//! WhatsApp aggressively bans numbers that send too fast, so every outbound
+//! dispatch draws a token from a [`TokenBucket`] first. Ordinary replies and
+//! OTP/challenge replies draw from separate buckets (see
+//! `AccountState::rate_limiter`/`otp_rate_limiter`) so a burst of ordinary
+//! traffic can't starve auth messages of their own allowance.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// A token bucket with `capacity` tokens max, refilling at a constant rate.
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// `messages_per_minute` tokens refill per 60s, up to `burst` tokens
+    /// banked at once. Starts full so the first burst after startup isn't
+    /// throttled.
+    pub fn new(messages_per_minute: u32, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            capacity,
+            refill_per_sec: f64::from(messages_per_minute) / 60.0,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Try to take one token. Returns the duration to wait before a token
+    /// would next be available if the bucket is currently dry.
+    fn try_acquire(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if self.refill_per_sec > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec))
+        } else {
+            // No refill configured — it will never succeed on its own.
+            Err(Duration::from_secs(u32::MAX.into()))
+        }
+    }
+
+    /// Current fill level, after applying any refill owed since the last
+    /// check. Exposed via `ChannelHealthSnapshot` details so `probe` shows
+    /// how close an account is to its limit.
+    pub fn level(&mut self) -> u32 {
+        self.refill();
+        self.tokens as u32
+    }
+}
+
+/// Acquire a token from `bucket` before an outbound dispatch. When `wait` is
+/// true, sleeps out the refill delay (with a nonzero refill rate the bucket
+/// always yields a token eventually; with `messages_per_minute` of 0 it
+/// waits indefinitely); otherwise returns [`crate::Error::RateLimited`]
+/// immediately.
+pub(crate) async fn acquire(bucket: &Mutex<TokenBucket>, wait: bool) -> crate::Result<()> {
+    loop {
+        let outcome = {
+            let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+            bucket.try_acquire()
+        };
+        match outcome {
+            Ok(()) => return Ok(()),
+            Err(retry_after) if wait => tokio::time::sleep(retry_after).await,
+            Err(retry_after) => {
+                return Err(crate::Error::RateLimited {
+                    retry_after_secs: retry_after.as_secs_f64(),
+                });
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_full_and_drains() {
+        let mut bucket = TokenBucket::new(60, 3);
+        assert_eq!(bucket.level(), 3);
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_err());
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let mut bucket = TokenBucket::new(3000, 1); // 50 tokens/sec, easy to observe
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_err());
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(bucket.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn retry_after_scales_with_deficit() {
+        let mut bucket = TokenBucket::new(60, 1); // 1 token/sec
+        bucket.try_acquire().unwrap();
+        let err = bucket.try_acquire().unwrap_err();
+        assert!(err <= Duration::from_secs(1));
+        assert!(err > Duration::from_millis(0));
+    }
+
+    #[tokio::test]
+    async fn acquire_non_blocking_returns_rate_limited() {
+        let bucket = Mutex::new(TokenBucket::new(60, 1));
+        acquire(&bucket, false).await.unwrap();
+        let err = acquire(&bucket, false).await.unwrap_err();
+        assert!(matches!(err, crate::Error::RateLimited { .. }));
+    }
+
+    #[tokio::test]
+    async fn acquire_blocking_waits_for_refill() {
+        let bucket = Mutex::new(TokenBucket::new(3000, 1));
+        acquire(&bucket, true).await.unwrap();
+        acquire(&bucket, true).await.unwrap();
+    }
+}
@@ -5,9 +5,22 @@ use std::{
 
 use {tokio_util::sync::CancellationToken, whatsapp_rust::client::Client};
 
-use moltis_channels::{ChannelEventSink, message_log::MessageLog};
+use moltis_channels::{
+    ChannelEvent, ChannelEventSink, ChannelType, message_log::MessageLog, plugin::ChannelHealthSnapshot,
+};
 
-use crate::{config::WhatsAppAccountConfig, otp::OtpState};
+use crate::{
+    config::WhatsAppAccountConfig,
+    delivery::DeliveryTracker,
+    download_state::{BeginOutcome, DownloadRegistry, MediaRef},
+    group_autojoin::GroupIdleTracker,
+    media_cache::MediaCache,
+    otp::OtpState,
+    outbound_queue::OutboundQueue,
+    rate_limit::{self, TokenBucket},
+    sled_store::SledStore,
+    verified_join::VerifiedJoinRegistry,
+};
 
 /// Maximum number of sent message IDs to track for self-chat loop detection.
 const SENT_IDS_CAPACITY: usize = 256;
@@ -25,6 +38,20 @@ pub(crate) const BOT_WATERMARK: &str = "\u{200D}\u{200C}\u{200D}\u{200C}";
 /// Shared account state map.
 pub type AccountStateMap = Arc<RwLock<HashMap<String, AccountState>>>;
 
+/// The minimal context needed to thread a reply under an inbound message via
+/// WhatsApp's `contextInfo.quotedMessage`/`stanzaId` mechanism.
+pub struct IncomingMsg {
+    /// The inbound message's own ID (becomes `contextInfo.stanzaId`).
+    pub id: String,
+    /// The JID of whoever sent the inbound message, as a string
+    /// (`contextInfo.participant` — required for clients to resolve the
+    /// quote, even in a 1:1 chat).
+    pub participant: String,
+    /// The inbound message itself, embedded as `contextInfo.quotedMessage`
+    /// so clients can render the quoted snippet without a round trip.
+    pub quoted: waproto::whatsapp::Message,
+}
+
 /// Per-account runtime state.
 pub struct AccountState {
     pub client: Arc<Client>,
@@ -37,16 +64,61 @@ pub struct AccountState {
     pub latest_qr: RwLock<Option<String>>,
     /// Whether the client is currently connected.
     pub connected: std::sync::atomic::AtomicBool,
+    /// Number of consecutive failed reconnect attempts since the last
+    /// successful `Event::Connected`, used to compute exponential backoff.
+    pub reconnect_attempts: std::sync::atomic::AtomicU32,
     /// In-memory OTP challenges for self-approval (std::sync::Mutex because
     /// all OTP operations are synchronous HashMap lookups, never held across
     /// `.await` points).
     pub otp: Mutex<OtpState>,
+    /// Content-addressed cache of processed media (optimized images,
+    /// transcriptions), keyed by SHA-256 digest, to skip redundant
+    /// downloads and reprocessing of previously-seen media.
+    pub media_cache: Mutex<MediaCache>,
+    /// Durable outbound text-message queue, drained by a background flush
+    /// task once `Event::Connected` fires after a disconnect.
+    pub outbound_queue: Arc<OutboundQueue>,
     /// Recently sent message IDs, used to distinguish bot echoes from user
     /// messages in self-chat. When the bot sends a message, the ID is recorded
     /// here. Incoming `is_from_me` messages whose ID matches are bot echoes
     /// and get skipped; non-matching ones are genuine user messages from
     /// another device (phone, WhatsApp Web) and get processed.
     pub(crate) recent_sent_ids: Mutex<VecDeque<String>>,
+    /// Deferred media downloads (media above
+    /// [`crate::download_state::DEFER_DOWNLOAD_THRESHOLD_BYTES`]), keyed by
+    /// message ID, pulled lazily via `ensure_downloaded`.
+    pub(crate) pending_downloads: Mutex<DownloadRegistry>,
+    /// Delivery/read state for recently sent messages, fed by inbound
+    /// WhatsApp receipt stanzas. Used by the OTP flow to decide whether a
+    /// prior challenge needs resending.
+    pub(crate) delivery: Mutex<DeliveryTracker>,
+    /// Broadcasts a fresh [`ChannelHealthSnapshot`] whenever `connected`
+    /// flips, a new QR code appears, or an OTP challenge is issued — the
+    /// push side of [`WhatsAppPlugin::subscribe_health`][crate::plugin::WhatsAppPlugin::subscribe_health],
+    /// so the UI doesn't have to wait out `ChannelStatus::probe`'s cache TTL.
+    pub(crate) health_tx: tokio::sync::broadcast::Sender<ChannelHealthSnapshot>,
+    /// Token bucket for ordinary outbound sends, built from
+    /// `config.messages_per_minute`/`config.burst`. `Arc`-wrapped so
+    /// [`crate::outbound::WhatsAppOutbound`] can clone it out of the
+    /// account map and await the refill delay without holding that map's
+    /// lock.
+    pub(crate) rate_limiter: Arc<Mutex<TokenBucket>>,
+    /// Separate token bucket for OTP/challenge replies, so a burst of
+    /// ordinary traffic can't starve auth messages of their own allowance.
+    pub(crate) otp_rate_limiter: Arc<Mutex<TokenBucket>>,
+    /// SecureJoin-style verified-allowlist handshake state, consulted when
+    /// `config.require_verified_allowlist` is set — see
+    /// [`crate::verified_join`].
+    pub(crate) verified_join: Mutex<VerifiedJoinRegistry>,
+    /// This account's Signal Protocol store, kept around (alongside the
+    /// copy `whatsapp_rust::bot::Bot` holds internally) so
+    /// [`crate::verified_join`] can look up a peer's live identity key
+    /// directly instead of trusting a self-reported fingerprint.
+    pub(crate) identity_store: Arc<SledStore>,
+    /// Tracks how long each `GroupPolicy::AutoJoin`-joined group has gone
+    /// with no allowlisted member present, to enforce
+    /// `config.auto_join_idle_grace_secs` — see [`crate::group_autojoin`].
+    pub(crate) group_idle: Mutex<GroupIdleTracker>,
 }
 
 impl AccountState {
@@ -71,23 +143,312 @@ impl AccountState {
         ids.iter().any(|sent_id| sent_id == id)
     }
 
-    /// Send a WhatsApp message and record its ID for self-chat loop detection.
-    /// Appends an invisible watermark to text messages for secondary loop detection.
+    /// Build a [`ChannelHealthSnapshot`] reflecting this account's current
+    /// connection state — shared by `ChannelStatus::probe`'s polling path and
+    /// [`AccountState::publish_health`]'s push path so the two never drift.
+    pub fn health_snapshot(&self) -> ChannelHealthSnapshot {
+        let connected = self.connected.load(std::sync::atomic::Ordering::Relaxed);
+        let tokens_left = {
+            let mut bucket = self.rate_limiter.lock().unwrap_or_else(|e| e.into_inner());
+            bucket.level()
+        };
+        let status = if connected {
+            self.config
+                .display_name
+                .as_ref()
+                .map(|n| format!("WhatsApp: {n}"))
+                .unwrap_or_else(|| "WhatsApp: connected".into())
+        } else if self.latest_qr.read().ok().and_then(|q| q.clone()).is_some() {
+            "waiting for QR scan".into()
+        } else {
+            "disconnected".into()
+        };
+        ChannelHealthSnapshot {
+            connected,
+            account_id: self.account_id.clone(),
+            details: Some(format!(
+                "{status} ({tokens_left}/{} send tokens)",
+                self.config.burst
+            )),
+        }
+    }
+
+    /// Push the current health snapshot to every `subscribe_health` listener.
+    /// A send with no active receivers is a no-op, not an error.
+    pub fn publish_health(&self) {
+        let _ = self.health_tx.send(self.health_snapshot());
+    }
+
+    /// Acquire a token from the ordinary-message rate limiter, waiting out
+    /// the refill delay if the bucket is dry. Ordinary replies never get
+    /// dropped for being rate limited — they just wait their turn, the same
+    /// way `send_single_message`'s outbound-queue fallback makes them wait
+    /// for reconnection instead of failing outright.
+    async fn acquire_send_token(&self) -> crate::Result<()> {
+        rate_limit::acquire(&self.rate_limiter, true).await
+    }
+
+    /// Acquire a token from the OTP/challenge rate limiter without waiting —
+    /// an OTP code is time-boxed, so delaying its delivery by the refill
+    /// period defeats the purpose. Returns [`crate::Error::RateLimited`]
+    /// immediately if the bucket is dry.
+    async fn acquire_otp_token(&self) -> crate::Result<()> {
+        rate_limit::acquire(&self.otp_rate_limiter, false).await
+    }
+
+    /// Send a WhatsApp message, transparently splitting an overlong text body
+    /// into an ordered sequence of messages.
+    ///
+    /// WhatsApp rejects or truncates bodies past a few thousand bytes, but
+    /// nothing upstream enforces that, so `conversation`/`extended_text_message`
+    /// bodies are split via [`chunking::str_chunks`] on UTF-8-safe, whitespace-
+    /// preferring boundaries and sent one chunk at a time, in order. Any
+    /// `contextInfo` (e.g. a quoted-reply thread) only carries on the first
+    /// chunk — later chunks are plain continuations of the same reply.
+    ///
+    /// Returns the ID of the first chunk sent, since that's the chunk
+    /// carrying `contextInfo` and is what a receipt stanza will reference.
+    ///
+    /// Draws a token from the ordinary-message rate limiter — OTP/challenge
+    /// replies go through [`AccountState::send_reply`] instead, which draws
+    /// from the separate `otp_rate_limiter` so they don't compete for the
+    /// same allowance.
     pub async fn send_message(
+        &self,
+        to: wacore_binary::jid::Jid,
+        msg: waproto::whatsapp::Message,
+    ) -> crate::Result<String> {
+        self.acquire_send_token().await?;
+        self.chunk_and_dispatch(to, msg).await
+    }
+
+    /// Split and send a message's chunks in order, without touching either
+    /// rate limiter — callers ([`AccountState::send_message`],
+    /// [`AccountState::send_reply`]) acquire their own token first.
+    async fn chunk_and_dispatch(
+        &self,
+        to: wacore_binary::jid::Jid,
+        msg: waproto::whatsapp::Message,
+    ) -> crate::Result<String> {
+        let body = msg.conversation.clone().or_else(|| {
+            msg.extended_text_message
+                .as_ref()
+                .and_then(|m| m.text.clone())
+        });
+
+        let Some(body) = body else {
+            return self.send_single_message(to, msg).await;
+        };
+
+        let chunks = crate::chunking::str_chunks(&body, crate::chunking::MAX_CHUNK_BYTES);
+        if chunks.len() <= 1 {
+            return self.send_single_message(to, msg).await;
+        }
+
+        let mut first_id = String::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut chunk_msg = msg.clone();
+            if chunk_msg.conversation.is_some() {
+                chunk_msg.conversation = Some(chunk.to_string());
+            } else if let Some(ref mut ext) = chunk_msg.extended_text_message {
+                ext.text = Some(chunk.to_string());
+                if i > 0 {
+                    ext.context_info = None;
+                }
+            }
+            let id = self.send_single_message(to.clone(), chunk_msg).await?;
+            if i == 0 {
+                first_id = id;
+            }
+        }
+        Ok(first_id)
+    }
+
+    /// Send a single WhatsApp message and record its ID for self-chat loop
+    /// detection. Appends an invisible watermark to text messages for
+    /// secondary loop detection.
+    ///
+    /// If the socket is currently down (or the immediate send fails), the
+    /// message is durably enqueued instead of being dropped, and this future
+    /// only resolves once a background flush task (triggered on the next
+    /// `Event::Connected`) actually delivers it. Only plain-text messages
+    /// (`conversation` / `extended_text_message`) can be queued this way —
+    /// every current caller sends one of those.
+    async fn send_single_message(
         &self,
         to: wacore_binary::jid::Jid,
         mut msg: waproto::whatsapp::Message,
-    ) -> crate::Result<()> {
+    ) -> crate::Result<String> {
         watermark_message(&mut msg);
-        let msg_id =
-            self.client
-                .send_message(to, msg)
-                .await
-                .map_err(|e| crate::Error::Whatsapp {
-                    message: e.to_string(),
-                })?;
-        self.record_sent_id(&msg_id);
-        Ok(())
+
+        if self.connected.load(std::sync::atomic::Ordering::Relaxed) {
+            match self.client.send_message(to.clone(), msg.clone()).await {
+                Ok(msg_id) => {
+                    self.record_sent_id(&msg_id);
+                    return Ok(msg_id);
+                },
+                Err(e) => {
+                    tracing::warn!(
+                        account_id = %self.account_id,
+                        error = %e,
+                        "send failed while connected, falling back to outbound queue"
+                    );
+                },
+            }
+        }
+
+        let text = msg.conversation.clone().or_else(|| {
+            msg.extended_text_message
+                .as_ref()
+                .and_then(|m| m.text.clone())
+        });
+        let Some(text) = text else {
+            return Err(crate::Error::Whatsapp {
+                message: "cannot queue a non-text message while disconnected".into(),
+            });
+        };
+
+        self.outbound_queue.enqueue_and_wait(&to.to_string(), &text).await
+    }
+
+    /// Send a text reply threaded under `quoted` via WhatsApp's native
+    /// quote-reply mechanism, so it visibly appears under the message it's
+    /// responding to instead of as a loose message in a busy chat.
+    ///
+    /// `ephemeral_secs`, when set, marks the message to disappear from the
+    /// chat that many seconds after delivery (via `contextInfo.expiration`)
+    /// — used for OTP challenge/grant replies so codes don't linger in
+    /// history once they've served their purpose.
+    ///
+    /// Note: if the socket is down, the outbound-queue fallback only persists
+    /// plain text, so a queued reply is still delivered once reconnected but
+    /// loses its thread (and its ephemeral timer) — the same honest degrade
+    /// as any other queued message.
+    ///
+    /// Every current caller of this method is the OTP self-approval flow
+    /// (challenge/grant/denial replies) or the verified-join handshake's
+    /// replies, so it draws from `otp_rate_limiter`
+    /// rather than the ordinary-message bucket, without waiting — an OTP
+    /// code is time-boxed, so delaying it by the refill period would just
+    /// mean it often arrives expired.
+    pub async fn send_reply(
+        &self,
+        to: wacore_binary::jid::Jid,
+        body: &str,
+        quoted: &IncomingMsg,
+        ephemeral_secs: Option<u32>,
+    ) -> crate::Result<String> {
+        let context_info = waproto::whatsapp::ContextInfo {
+            stanza_id: Some(quoted.id.clone()),
+            participant: Some(quoted.participant.clone()),
+            quoted_message: Some(Box::new(quoted.quoted.clone())),
+            expiration: ephemeral_secs,
+            ..Default::default()
+        };
+        let msg = waproto::whatsapp::Message {
+            extended_text_message: Some(Box::new(
+                waproto::whatsapp::message::ExtendedTextMessage {
+                    text: Some(body.to_string()),
+                    context_info: Some(Box::new(context_info)),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+        self.acquire_otp_token().await?;
+        self.chunk_and_dispatch(to, msg).await
+    }
+
+    /// Register a media attachment for on-demand download instead of
+    /// fetching it eagerly, keyed by the inbound message's ID.
+    pub(crate) fn register_pending_download(
+        &self,
+        msg_id: String,
+        chat_id: String,
+        media: MediaRef,
+    ) {
+        let mut registry = self
+            .pending_downloads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        registry.register(msg_id, chat_id, media);
+    }
+
+    /// Lazily fetch and decrypt a deferred media download, returning the
+    /// cached bytes if it was already fetched.
+    ///
+    /// Emits `ChannelEvent::MediaReady`/`MediaFailed` so the gateway can
+    /// react once a deferred download completes, since the original inbound
+    /// dispatch already happened without the attachment.
+    pub async fn ensure_downloaded(&self, msg_id: &str) -> crate::Result<Vec<u8>> {
+        let (outcome, chat_id) = {
+            let mut registry = self
+                .pending_downloads
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let chat_id = registry.chat_id(msg_id).map(str::to_string);
+            (registry.begin(msg_id), chat_id)
+        };
+
+        let media = match outcome {
+            Some(BeginOutcome::AlreadyDone(data)) => return Ok(data),
+            Some(BeginOutcome::InProgress) => {
+                return Err(crate::Error::Whatsapp {
+                    message: "download already in progress".into(),
+                });
+            },
+            Some(BeginOutcome::Start(media)) => media,
+            None => {
+                return Err(crate::Error::Whatsapp {
+                    message: format!("no deferred download registered for message {msg_id}"),
+                });
+            },
+        };
+        let chat_id = chat_id.unwrap_or_default();
+
+        let result = match &media {
+            MediaRef::Image(m) => self.client.download(m.as_ref()).await,
+            MediaRef::Video(m) => self.client.download(m.as_ref()).await,
+            MediaRef::Document(m) => self.client.download(m.as_ref()).await,
+        };
+
+        let mut registry = self
+            .pending_downloads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(data) => {
+                registry.complete(msg_id, data.clone());
+                drop(registry);
+                if let Some(ref sink) = self.event_sink {
+                    sink.emit(ChannelEvent::MediaReady {
+                        channel_type: ChannelType::Whatsapp,
+                        account_id: self.account_id.clone(),
+                        chat_id,
+                        message_id: msg_id.to_string(),
+                    })
+                    .await;
+                }
+                Ok(data)
+            },
+            Err(e) => {
+                registry.fail(msg_id);
+                drop(registry);
+                if let Some(ref sink) = self.event_sink {
+                    sink.emit(ChannelEvent::MediaFailed {
+                        channel_type: ChannelType::Whatsapp,
+                        account_id: self.account_id.clone(),
+                        chat_id,
+                        message_id: msg_id.to_string(),
+                        reason: e.to_string(),
+                    })
+                    .await;
+                }
+                Err(crate::Error::Whatsapp {
+                    message: format!("deferred download failed: {e}"),
+                })
+            },
+        }
     }
 }
 
@@ -0,0 +1,48 @@
+//! Shared TLS root-certificate store built from `[tls.trust]` config.
+//!
+//! Threaded into both the `UreqHttpClient` and the WebSocket transport
+//! factory in [`crate::connection::start_connection`], so a corporate proxy
+//! CA or a fully private PKI only needs to be configured once.
+
+use std::sync::Arc;
+
+/// A pre-built root-cert store shared between WhatsApp's HTTP client and
+/// WebSocket transport.
+#[derive(Clone)]
+pub struct TlsTrustStore {
+    pub(crate) roots: Arc<rustls::RootCertStore>,
+}
+
+impl TlsTrustStore {
+    /// Build from a `[tls.trust]` config section: starts from the Mozilla
+    /// roots bundled in `webpki_roots` unless `use_system_roots` is false
+    /// (the OS certificate store is not read), then adds every PEM
+    /// file listed in `extra_ca_certs`.
+    pub fn build(trust: &moltis_config::schema::TlsTrustConfig) -> crate::Result<Self> {
+        let mut roots = rustls::RootCertStore::empty();
+        if trust.use_system_roots {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        for path in &trust.extra_ca_certs {
+            load_ca_file(&mut roots, path)?;
+        }
+        Ok(Self { roots: Arc::new(roots) })
+    }
+}
+
+fn load_ca_file(roots: &mut rustls::RootCertStore, path: &str) -> crate::Result<()> {
+    let pem = std::fs::read(path).map_err(|e| crate::Error::Config {
+        message: format!("reading extra_ca_certs entry '{path}': {e}"),
+    })?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| crate::Error::Config {
+            message: format!("parsing extra_ca_certs entry '{path}' as PEM: {e}"),
+        })?;
+    for cert in certs {
+        roots.add(cert).map_err(|e| crate::Error::Config {
+            message: format!("adding extra_ca_certs entry '{path}' to root store: {e}"),
+        })?;
+    }
+    Ok(())
+}
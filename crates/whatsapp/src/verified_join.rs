@@ -0,0 +1,327 @@
+//! SecureJoin-style verified allowlisting.
+//!
+//! Plain `allowlist`/`group_allowlist` entries are just JID/phone strings —
+//! a peer who first contacts the bot under a spoofed or MITM'd identity is
+//! allowlisted by name alone. This module adds an opt-in double-opt-in
+//! handshake, modeled on Delta Chat's SecureJoin, built on the Signal
+//! Protocol identity keys [`crate::sled_store::SledStore`] already persists:
+//!
+//! 1. The operator calls [`VerifiedJoinRegistry::create_invite`], which mints
+//!    an `invite_number` and `auth_secret` and binds them to the account's
+//!    own identity fingerprint. This is shared out of band (QR/link).
+//! 2. A peer's "request" echoing `invite_number` gets an "auth-required"
+//!    reply (see [`VerifiedJoinRegistry::handle_request`]).
+//! 3. The peer's "request-with-auth" carries the `auth_secret` plus its own
+//!    identity fingerprint ([`VerifiedJoinRegistry::handle_auth`]); once the
+//!    secret checks out, the peer is recorded as a verified entry under the
+//!    fingerprint passed to `handle_auth`. The message handler passes the
+//!    one computed from the peer's live Signal identity key; the
+//!    self-reported fingerprint is only compared to log a mismatch.
+//!
+//! A verified entry's fingerprint is remembered, so a later silent identity
+//! key change (a safety-number change) can be detected and the entry
+//! flipped back to unverified via [`VerifiedJoinRegistry::recheck`].
+
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Hex-encode bytes without pulling in the `hex` crate.
+fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
+
+/// A peer's identity fingerprint, derived from its current Signal identity
+/// public key. Not a full safety-number (no QR-rendering concerns here) —
+/// just a stable digest suitable for equality checks across a handshake and
+/// for detecting a later key change.
+pub fn fingerprint(identity_public_key: &[u8]) -> String {
+    let digest = crate::media_cache::sha256_hex(identity_public_key);
+    digest[..16].to_string()
+}
+
+/// An outstanding invite, ready to be shared out of band (QR code, link).
+#[derive(Clone)]
+pub struct Invite {
+    pub invite_number: String,
+    pub auth_secret: String,
+    /// Fingerprint of the inviting account's own identity key, so the peer
+    /// can confirm it's talking to the expected account before replying
+    /// with its own fingerprint.
+    pub account_fingerprint: String,
+}
+
+/// A peer's verified-join record.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VerifiedEntry {
+    pub peer_id: String,
+    /// Fingerprint observed at verification time. Compared against the
+    /// live fingerprint on every [`VerifiedJoinRegistry::recheck`] to catch
+    /// a silent identity-key change.
+    pub fingerprint: String,
+    pub verified: bool,
+}
+
+/// Outcome of [`VerifiedJoinRegistry::handle_request`].
+pub enum RequestOutcome {
+    /// Reply "auth-required" — the invite is known and still valid.
+    AuthRequired,
+    /// `invite_number` doesn't match any outstanding invite.
+    UnknownInvite,
+}
+
+/// Outcome of [`VerifiedJoinRegistry::handle_auth`].
+pub enum AuthOutcome {
+    /// The secret and fingerprint checked out; the peer is now verified.
+    Verified,
+    /// `invite_number` doesn't match any outstanding invite.
+    UnknownInvite,
+    /// The AUTH secret didn't match.
+    AuthMismatch,
+}
+
+/// Tracks outstanding invites and verified peers for one account.
+pub struct VerifiedJoinRegistry {
+    invites: HashMap<String, Invite>,
+    verified: HashMap<String, VerifiedEntry>,
+}
+
+impl VerifiedJoinRegistry {
+    pub fn new() -> Self {
+        Self {
+            invites: HashMap::new(),
+            verified: HashMap::new(),
+        }
+    }
+
+    /// Restore previously verified entries (e.g. loaded from config at
+    /// startup) so a restart doesn't forget who was already verified.
+    pub fn with_verified(entries: Vec<VerifiedEntry>) -> Self {
+        let mut registry = Self::new();
+        for entry in entries {
+            registry.verified.insert(entry.peer_id.clone(), entry);
+        }
+        registry
+    }
+
+    /// Mint a fresh invite bound to the account's current identity
+    /// fingerprint. Replaces any invite minted earlier under the same
+    /// number is not a concern — each call gets its own random number.
+    pub fn create_invite(&mut self, account_fingerprint: String) -> Invite {
+        let invite_number = format!("{:08x}", rand::rng().random::<u32>());
+        let auth_secret = hex_encode(&rand::rng().random::<[u8; 16]>());
+        let invite = Invite {
+            invite_number: invite_number.clone(),
+            auth_secret,
+            account_fingerprint,
+        };
+        self.invites.insert(invite_number, invite.clone());
+        invite
+    }
+
+    /// Step 2: a peer's bare "request" echoing an invite number.
+    pub fn handle_request(&self, invite_number: &str) -> RequestOutcome {
+        if self.invites.contains_key(invite_number) {
+            RequestOutcome::AuthRequired
+        } else {
+            RequestOutcome::UnknownInvite
+        }
+    }
+
+    /// Step 3: a peer's "request-with-auth", carrying the AUTH secret and
+    /// its own identity fingerprint. On success, the invite is consumed
+    /// (one-shot) and the peer becomes verified.
+    pub fn handle_auth(&mut self, invite_number: &str, auth_secret: &str, peer_id: &str, peer_fingerprint: &str) -> AuthOutcome {
+        let Some(invite) = self.invites.get(invite_number) else {
+            return AuthOutcome::UnknownInvite;
+        };
+
+        if invite.auth_secret != auth_secret {
+            return AuthOutcome::AuthMismatch;
+        }
+
+        self.invites.remove(invite_number);
+        self.verified.insert(
+            peer_id.to_string(),
+            VerifiedEntry {
+                peer_id: peer_id.to_string(),
+                fingerprint: peer_fingerprint.to_string(),
+                verified: true,
+            },
+        );
+        AuthOutcome::Verified
+    }
+
+    /// Whether `peer_id` has a verified entry on file.
+    pub fn is_verified(&self, peer_id: &str) -> bool {
+        self.verified.get(peer_id).is_some_and(|e| e.verified)
+    }
+
+    /// Re-check a verified peer's fingerprint against its live Signal
+    /// session. If the identity key changed since verification (a
+    /// safety-number change), flip the entry back to unverified and return
+    /// `false` so the caller can re-gate the peer.
+    pub fn recheck(&mut self, peer_id: &str, live_fingerprint: &str) -> bool {
+        let Some(entry) = self.verified.get_mut(peer_id) else {
+            return false;
+        };
+        if entry.fingerprint != live_fingerprint {
+            entry.verified = false;
+            return false;
+        }
+        true
+    }
+
+    /// All verified entries, e.g. for persisting back into the account
+    /// config's allowlist alongside the plain string list.
+    pub fn verified_entries(&self) -> Vec<VerifiedEntry> {
+        self.verified.values().cloned().collect()
+    }
+}
+
+/// Parse an admin's `!invite` message, requesting a fresh invite to hand to
+/// a prospective peer out of band. Takes no arguments — the invite isn't
+/// bound to a particular peer until that peer completes the handshake.
+/// Case-insensitive, matching the word-boundary-checked style of
+/// [`parse_request_message`]/[`parse_auth_message`] below.
+pub fn parse_invite_command(text: &str) -> bool {
+    text.trim().eq_ignore_ascii_case("!invite")
+}
+
+/// Parse a prospective peer's `request <invite_number>` message — step 2 of
+/// the handshake (see the module docs). Case-insensitive and
+/// whitespace-delimited, so `Request abc123` and `requesting abc123` are
+/// handled as "matches" and "doesn't match" respectively (a bare
+/// `strip_prefix` would wrongly accept the latter too).
+pub fn parse_request_message(text: &str) -> Option<&str> {
+    let mut parts = text.trim().split_whitespace();
+    if !parts.next()?.eq_ignore_ascii_case("request") {
+        return None;
+    }
+    parts.next()
+}
+
+/// Parse a prospective peer's `auth <invite_number> <auth_secret>
+/// <fingerprint>` message — step 3 of the handshake. `fingerprint` is
+/// self-reported by the peer (read off their own app's safety-number/
+/// encryption-details screen). The handshake records the fingerprint of
+/// the peer's live identity key (from `AccountState::identity_store`)
+/// instead, and only logs a mismatch with this one.
+pub fn parse_auth_message(text: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = text.trim().split_whitespace();
+    if !parts.next()?.eq_ignore_ascii_case("auth") {
+        return None;
+    }
+    let invite_number = parts.next()?;
+    let auth_secret = parts.next()?;
+    let fingerprint = parts.next()?;
+    Some((invite_number, auth_secret, fingerprint))
+}
+
+impl Default for VerifiedJoinRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_handshake_verifies_peer() {
+        let mut registry = VerifiedJoinRegistry::new();
+        let invite = registry.create_invite("account-fp".to_string());
+
+        assert!(matches!(
+            registry.handle_request(&invite.invite_number),
+            RequestOutcome::AuthRequired
+        ));
+
+        let outcome = registry.handle_auth(&invite.invite_number, &invite.auth_secret, "peer1", "peer-fp");
+        assert!(matches!(outcome, AuthOutcome::Verified));
+        assert!(registry.is_verified("peer1"));
+    }
+
+    #[test]
+    fn wrong_auth_secret_is_rejected() {
+        let mut registry = VerifiedJoinRegistry::new();
+        let invite = registry.create_invite("account-fp".to_string());
+        let outcome = registry.handle_auth(&invite.invite_number, "wrong-secret", "peer1", "peer-fp");
+        assert!(matches!(outcome, AuthOutcome::AuthMismatch));
+        assert!(!registry.is_verified("peer1"));
+    }
+
+    #[test]
+    fn unknown_invite_number_is_rejected() {
+        let mut registry = VerifiedJoinRegistry::new();
+        assert!(matches!(registry.handle_request("nope"), RequestOutcome::UnknownInvite));
+        assert!(matches!(
+            registry.handle_auth("nope", "secret", "peer1", "peer-fp"),
+            AuthOutcome::UnknownInvite
+        ));
+    }
+
+    #[test]
+    fn invite_is_single_use() {
+        let mut registry = VerifiedJoinRegistry::new();
+        let invite = registry.create_invite("account-fp".to_string());
+        registry.handle_auth(&invite.invite_number, &invite.auth_secret, "peer1", "peer-fp");
+        assert!(matches!(
+            registry.handle_auth(&invite.invite_number, &invite.auth_secret, "peer2", "peer-fp-2"),
+            AuthOutcome::UnknownInvite
+        ));
+    }
+
+    #[test]
+    fn recheck_detects_identity_key_change() {
+        let mut registry = VerifiedJoinRegistry::new();
+        let invite = registry.create_invite("account-fp".to_string());
+        registry.handle_auth(&invite.invite_number, &invite.auth_secret, "peer1", "peer-fp");
+
+        assert!(registry.recheck("peer1", "peer-fp"));
+        assert!(!registry.recheck("peer1", "different-fp"));
+        assert!(!registry.is_verified("peer1"));
+    }
+
+    #[test]
+    fn parses_invite_command() {
+        assert!(parse_invite_command("!invite"));
+        assert!(parse_invite_command("  !invite  "));
+        assert!(parse_invite_command("!Invite"));
+        assert!(!parse_invite_command("!invite now"));
+        assert!(!parse_invite_command("hello"));
+    }
+
+    #[test]
+    fn parses_request_message() {
+        assert_eq!(parse_request_message("request abc123"), Some("abc123"));
+        assert_eq!(parse_request_message("  request   abc123  "), Some("abc123"));
+        assert_eq!(parse_request_message("Request abc123"), Some("abc123"));
+        assert_eq!(parse_request_message("request"), None);
+        assert_eq!(parse_request_message("requesting abc123"), None);
+        assert_eq!(parse_request_message("hello"), None);
+    }
+
+    #[test]
+    fn parses_auth_message() {
+        assert_eq!(
+            parse_auth_message("auth abc123 secret-xyz fp-123"),
+            Some(("abc123", "secret-xyz", "fp-123"))
+        );
+        assert_eq!(
+            parse_auth_message("Auth abc123 secret-xyz fp-123"),
+            Some(("abc123", "secret-xyz", "fp-123"))
+        );
+        assert_eq!(parse_auth_message("auth abc123 secret-xyz"), None);
+        assert_eq!(parse_auth_message("authorize abc123 secret-xyz fp-123"), None);
+        assert_eq!(parse_auth_message("hello"), None);
+    }
+}